@@ -0,0 +1,274 @@
+// Copyright 2025 Justin Hu
+//
+// This file is part of Grid Online.
+//
+// Grid Online is free software: you can redistribute it and/or modify it under
+// the terms of the GNU Affero General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// Grid Online is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU Affero General Public License
+// for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with Grid Online. If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! A bot for Grid Online, playing over the same websocket protocol as the web client
+//!
+//! Handy as a stand-in opponent, as a reference client, and for loading a server with many
+//! players at once
+
+mod protocol;
+mod strategy;
+
+use clap::Parser;
+use futures_util::{SinkExt, StreamExt};
+use grid_common::{PROTOCOL, PlayerMove, PlayerVisibleGameState};
+use thiserror::Error;
+use tokio::task::JoinSet;
+use tokio_tungstenite::{
+    connect_async,
+    tungstenite::{
+        self, Message,
+        client::IntoClientRequest,
+        http::{HeaderValue, header::SEC_WEBSOCKET_PROTOCOL},
+        protocol::CloseFrame,
+    },
+};
+
+use crate::{
+    protocol::{CLOSE_WON, ProtocolError, ServerMessage},
+    strategy::{Difficulty, Strategy, StrategyName, legal_moves},
+};
+
+#[derive(Parser)]
+struct Args {
+    /// Server to connect to, like ws://localhost:3030
+    server_url: String,
+    /// Join code of the game to play in
+    join_code: String,
+    /// Name to play as, numbered from 1 when running more than one bot
+    #[clap(long, default_value = "bot")]
+    username: String,
+    /// How many bots to run at once, each its own player
+    #[clap(long, default_value = "1")]
+    count: usize,
+    /// How the bots pick their moves
+    #[clap(long, value_enum, default_value_t = StrategyName::Greedy)]
+    strategy: StrategyName,
+    /// How hard the bots are to beat, picking their strategy for them
+    #[clap(long, value_enum, conflicts_with = "strategy")]
+    bot_difficulty: Option<Difficulty>,
+    /// Seed for random choices, so a bot plays the same game the same way
+    #[clap(long)]
+    seed: Option<u64>,
+}
+
+/// How a bot's game ended
+enum Outcome {
+    Won,
+    Lost(String),
+    /// The game ended with nobody winning, since everyone forfeited
+    NobodyWon,
+    /// The server hung up without anyone winning
+    Closed(Option<CloseFrame>),
+}
+
+#[derive(Debug, Error)]
+enum BotError {
+    #[error(transparent)]
+    Websocket(#[from] tungstenite::Error),
+    #[error("server refused login: {0}")]
+    Refused(String),
+    #[error(transparent)]
+    Protocol(#[from] ProtocolError),
+    #[error("no legal moves on our turn")]
+    Stuck,
+    #[error("connection dropped")]
+    Dropped,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    if args.count == 0 {
+        eprintln!("error: --count must be at least 1");
+        return;
+    }
+
+    let strategy = args
+        .bot_difficulty
+        .map_or(args.strategy, Difficulty::strategy);
+    let mut bots = JoinSet::new();
+    for bot in 0..args.count {
+        let username = if args.count == 1 {
+            args.username.clone()
+        } else {
+            format!("{}{}", args.username, bot + 1)
+        };
+        // bots sharing a seed would all play alike
+        let strategy = strategy.build(args.seed.map(|seed| seed.wrapping_add(bot as u64)));
+        let server_url = args.server_url.clone();
+        let join_code = args.join_code.clone();
+        bots.spawn(async move {
+            let outcome = play(&server_url, &username, &join_code, strategy).await;
+            (username, outcome)
+        });
+    }
+
+    while let Some(result) = bots.join_next().await {
+        let Ok((username, outcome)) = result else {
+            eprintln!("error: a bot panicked");
+            continue;
+        };
+        match outcome {
+            Ok(Outcome::Won) => println!("{username}: won"),
+            Ok(Outcome::Lost(winner)) => println!("{username}: lost to {winner}"),
+            Ok(Outcome::NobodyWon) => println!("{username}: nobody won"),
+            Ok(Outcome::Closed(Some(frame))) => println!(
+                "{username}: disconnected ({}: {})",
+                u16::from(frame.code),
+                frame.reason
+            ),
+            Ok(Outcome::Closed(None)) => println!("{username}: disconnected"),
+            Err(err) => eprintln!("{username}: error: {err}"),
+        }
+    }
+}
+
+/// Play one game as one player, from logging in until the server closes the connection
+async fn play(
+    server_url: &str,
+    username: &str,
+    join_code: &str,
+    mut strategy: Box<dyn Strategy + Send>,
+) -> Result<Outcome, BotError> {
+    // whole states only - a bot has no use for the bandwidth deltas save
+    let mut request = server_url.into_client_request()?;
+    request
+        .headers_mut()
+        .insert(SEC_WEBSOCKET_PROTOCOL, HeaderValue::from_static(PROTOCOL));
+    let (socket, _) = connect_async(request).await?;
+    let (mut send, mut recv) = socket.split();
+    send.send(Message::text(format!("{username}\n{join_code}")))
+        .await?;
+
+    let mut logged_in = false;
+    let mut require_capture = false;
+    let mut state: Option<PlayerVisibleGameState> = None;
+    // the last move sent and how many moves had been played then, until the server shows it
+    // was taken or turned down, so each turn gets one move
+    let mut waiting: Option<(usize, PlayerMove)> = None;
+    // moves a lenient server turned down this turn
+    let mut rejected: Vec<PlayerMove> = Vec::new();
+    let mut paused = false;
+
+    while let Some(frame) = recv.next().await {
+        let text = match frame? {
+            Message::Text(text) => text,
+            Message::Close(frame) => {
+                return Ok(match frame {
+                    Some(frame) if u16::from(frame.code) == CLOSE_WON => {
+                        match frame.reason.strip_prefix("player won\n") {
+                            Some(winner) if winner == username => Outcome::Won,
+                            Some(winner) => Outcome::Lost(winner.to_string()),
+                            None if frame.reason == "no winner" => Outcome::NobodyWon,
+                            None => Outcome::Closed(Some(frame)),
+                        }
+                    }
+                    frame => Outcome::Closed(frame),
+                });
+            }
+            // the websocket library answers pings itself
+            _ => continue,
+        };
+        if !logged_in {
+            if text.as_str() != "ok" {
+                return Err(BotError::Refused(text.to_string()));
+            }
+            logged_in = true;
+            continue;
+        }
+
+        match ServerMessage::parse(&text)? {
+            ServerMessage::Options(options) => {
+                require_capture = options
+                    .get("require_capture")
+                    .and_then(serde_json::Value::as_bool)
+                    == Some(true);
+            }
+            ServerMessage::State(new_state) => {
+                if state
+                    .as_ref()
+                    .is_none_or(|state| state.move_count != new_state.move_count)
+                {
+                    rejected.clear();
+                }
+                if waiting
+                    .as_ref()
+                    .is_some_and(|(move_count, _)| move_taken(&new_state, *move_count))
+                {
+                    waiting = None;
+                }
+                state = Some(*new_state);
+            }
+            ServerMessage::MoveRejected(_) if paused => {
+                // the move was fine, it just came while the game was paused
+                waiting = None;
+            }
+            ServerMessage::MoveRejected(_) => {
+                // a move skipped at the end of a round is heard of after the next round's state,
+                // which has already ended the wait
+                if state
+                    .as_ref()
+                    .is_some_and(|state| state.submitted.is_none())
+                    && let Some((_, player_move)) = waiting.take()
+                {
+                    rejected.push(player_move);
+                }
+            }
+            ServerMessage::Paused => paused = true,
+            ServerMessage::Resumed => paused = false,
+            ServerMessage::MoveOk(_) | ServerMessage::Other => {}
+        }
+
+        let Some(state) = &state else {
+            continue;
+        };
+        if paused || !state.is_my_turn() || waiting.is_some() {
+            continue;
+        }
+        let moves = legal_moves(state, require_capture)
+            .into_iter()
+            .filter(|player_move| !rejected.contains(player_move))
+            .collect::<Vec<_>>();
+        if moves.is_empty() {
+            return Err(BotError::Stuck);
+        }
+        let player_move = strategy.choose(state, &moves);
+        send.send(Message::text(
+            serde_json::to_string(&player_move).expect("should always be able to serialize moves"),
+        ))
+        .await?;
+        waiting = Some((state.move_count, player_move));
+    }
+
+    Err(BotError::Dropped)
+}
+
+/// Whether a state shows the server has dealt with a move sent when this many moves were played
+///
+/// When everyone moves at once, that's once it shows the move was picked, or a new round with
+/// no moves picked yet, since a round can end with every move in it skipped
+fn move_taken(state: &PlayerVisibleGameState, move_count: usize) -> bool {
+    match (&state.submitted, state.my_index) {
+        (Some(submitted), Some(my_index)) => {
+            submitted.get(my_index) == Some(&true) || submitted.iter().all(|picked| !picked)
+        }
+        _ => state.move_count != move_count,
+    }
+}
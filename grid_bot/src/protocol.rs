@@ -0,0 +1,150 @@
+// Copyright 2025 Justin Hu
+//
+// This file is part of Grid Online.
+//
+// Grid Online is free software: you can redistribute it and/or modify it under
+// the terms of the GNU Affero General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// Grid Online is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU Affero General Public License
+// for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with Grid Online. If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Reading what the server sends a player
+//!
+//! After connecting, a client sends one login message, "username\njoin code". The server answers
+//! "ok", or says why not and hangs up. From then on, game states come as bare JSON, and
+//! everything else as a kind on the first line with the details after it. The game ends with the
+//! server closing the connection.
+
+use grid_common::PlayerVisibleGameState;
+use thiserror::Error;
+
+/// Close code for a game that's over; the reason is "player won", then their name, or "no winner"
+/// if everyone forfeited
+pub const CLOSE_WON: u16 = 4000;
+
+/// A text message from the server, after logging in
+#[derive(Debug, PartialEq)]
+pub enum ServerMessage {
+    /// The lobby's game options
+    Options(serde_json::Value),
+    /// The game, as this player sees it
+    State(Box<PlayerVisibleGameState>),
+    /// The last move was ignored, and why
+    MoveRejected(String),
+    /// The last move was played, taking this many cards
+    MoveOk(usize),
+    /// The host paused the game, so moves are turned down until it's resumed
+    Paused,
+    /// The host picked a paused game up again
+    Resumed,
+    /// Anything a bot doesn't need, like spectator counts and reactions
+    Other,
+}
+
+#[derive(Debug, Error)]
+pub enum ProtocolError {
+    #[error("malformed {kind} message: {err}")]
+    Malformed {
+        kind: &'static str,
+        err: serde_json::Error,
+    },
+    #[error("malformed move-ok message: {0:?}")]
+    BadCaptureCount(String),
+    #[error("got a delta without asking for them")]
+    UnexpectedDelta,
+}
+
+impl ServerMessage {
+    pub fn parse(message: &str) -> Result<Self, ProtocolError> {
+        if message.starts_with('{') {
+            return serde_json::from_str(message)
+                .map(|state| ServerMessage::State(Box::new(state)))
+                .map_err(|err| ProtocolError::Malformed { kind: "state", err });
+        }
+        let (kind, details) = message.split_once('\n').unwrap_or((message, ""));
+        match kind {
+            "options" => serde_json::from_str(details)
+                .map(ServerMessage::Options)
+                .map_err(|err| ProtocolError::Malformed {
+                    kind: "options",
+                    err,
+                }),
+            // servers that confirm moves say which kind of message was turned down
+            "rejected" | "move-rejected" => Ok(ServerMessage::MoveRejected(details.to_string())),
+            "move-ok" => details
+                .parse()
+                .map(ServerMessage::MoveOk)
+                .map_err(|_| ProtocolError::BadCaptureCount(details.to_string())),
+            "paused" => Ok(ServerMessage::Paused),
+            "resumed" => Ok(ServerMessage::Resumed),
+            "delta" => Err(ProtocolError::UnexpectedDelta),
+            _ => Ok(ServerMessage::Other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            ServerMessage::parse("move-rejected\nnot your turn").unwrap(),
+            ServerMessage::MoveRejected("not your turn".to_string())
+        );
+        assert_eq!(
+            ServerMessage::parse("rejected\nnot your turn").unwrap(),
+            ServerMessage::MoveRejected("not your turn".to_string())
+        );
+        assert_eq!(
+            ServerMessage::parse("move-ok\n3").unwrap(),
+            ServerMessage::MoveOk(3)
+        );
+        assert_eq!(
+            ServerMessage::parse("options\n{\"wrap\":true}").unwrap(),
+            ServerMessage::Options(serde_json::json!({"wrap": true}))
+        );
+        assert_eq!(
+            ServerMessage::parse("paused\nAlice").unwrap(),
+            ServerMessage::Paused
+        );
+        assert_eq!(
+            ServerMessage::parse("resumed").unwrap(),
+            ServerMessage::Resumed
+        );
+        assert_eq!(
+            ServerMessage::parse("spectators\n2").unwrap(),
+            ServerMessage::Other
+        );
+        assert_eq!(
+            ServerMessage::parse("react\nAlice\n👍").unwrap(),
+            ServerMessage::Other
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            ServerMessage::parse("move-ok\nlots"),
+            Err(ProtocolError::BadCaptureCount(_))
+        ));
+        assert!(matches!(
+            ServerMessage::parse("{\"board\":"),
+            Err(ProtocolError::Malformed { kind: "state", .. })
+        ));
+        assert!(matches!(
+            ServerMessage::parse("delta\n{}"),
+            Err(ProtocolError::UnexpectedDelta)
+        ));
+    }
+}
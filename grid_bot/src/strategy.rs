@@ -0,0 +1,238 @@
+// Copyright 2025 Justin Hu
+//
+// This file is part of Grid Online.
+//
+// Grid Online is free software: you can redistribute it and/or modify it under
+// the terms of the GNU Affero General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// Grid Online is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU Affero General Public License
+// for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with Grid Online. If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! How bots pick their moves
+
+use clap::ValueEnum;
+use grid_common::{PlayerMove, PlayerVisibleGameState};
+use rand::{SeedableRng, rngs::StdRng, seq::IndexedRandom};
+
+/// A way of picking a move
+pub trait Strategy {
+    /// Pick one of the moves, which are all legal and never empty
+    fn choose(&mut self, state: &PlayerVisibleGameState, moves: &[PlayerMove]) -> PlayerMove;
+}
+
+/// Strategies that can be picked on the command line
+#[derive(Clone, Copy, ValueEnum)]
+pub enum StrategyName {
+    /// Any legal move
+    Random,
+    /// Whatever takes the most cards
+    Greedy,
+}
+
+/// How hard a bot is to beat, for those who'd rather not pick a strategy
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Difficulty {
+    /// Plays any legal move
+    Easy,
+    /// Takes as many cards as it can each turn
+    Medium,
+}
+
+impl Difficulty {
+    pub fn strategy(self) -> StrategyName {
+        match self {
+            Difficulty::Easy => StrategyName::Random,
+            Difficulty::Medium => StrategyName::Greedy,
+        }
+    }
+}
+
+impl StrategyName {
+    pub fn build(self, seed: Option<u64>) -> Box<dyn Strategy + Send> {
+        match self {
+            StrategyName::Random => Box::new(Random(match seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_os_rng(),
+            })),
+            StrategyName::Greedy => Box::new(Greedy),
+        }
+    }
+}
+
+pub struct Random(StdRng);
+
+impl Strategy for Random {
+    fn choose(&mut self, _: &PlayerVisibleGameState, moves: &[PlayerMove]) -> PlayerMove {
+        moves.choose(&mut self.0).expect("never empty").clone()
+    }
+}
+
+pub struct Greedy;
+
+impl Strategy for Greedy {
+    fn choose(&mut self, state: &PlayerVisibleGameState, moves: &[PlayerMove]) -> PlayerMove {
+        // the first of the best, so the same state always gets the same move
+        let most = moves
+            .iter()
+            .map(|player_move| state.cards_taken_by(player_move))
+            .max()
+            .expect("never empty");
+        moves
+            .iter()
+            .find(|player_move| state.cards_taken_by(player_move) == most)
+            .expect("has the most")
+            .clone()
+    }
+}
+
+/// Every move the player could make, in hand order then board order
+///
+/// When the game requires taking something if possible, only moves that do are listed, unless
+/// there are none
+pub fn legal_moves(state: &PlayerVisibleGameState, require_capture: bool) -> Vec<PlayerMove> {
+    let moves = state.legal_moves();
+    if require_capture
+        && moves
+            .iter()
+            .any(|player_move| state.cards_taken_by(player_move) > 0)
+    {
+        moves
+            .into_iter()
+            .filter(|player_move| state.cards_taken_by(player_move) > 0)
+            .collect()
+    } else {
+        moves
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grid_common::{
+        BOARD_SIZE, Board, Card, Deck, FirstMove, Hand, RankOrder, Suit, TakingVariant, Value,
+    };
+
+    fn create_state(board: Board, hand: &[Card]) -> PlayerVisibleGameState {
+        PlayerVisibleGameState {
+            started: !board.is_empty(),
+            board,
+            hand: Hand::from_cards(hand),
+            deck: Deck::from_cards(&[]),
+            username: "Alice".to_string(),
+            players: vec![("Alice".to_string(), 5), ("Bob".to_string(), 5)],
+            turn: 0,
+            my_index: Some(0),
+            top_card: None,
+            wrap: false,
+            taking_variant: TakingVariant::SameNumber,
+            rank_order: RankOrder::AceLow,
+            first_move: FirstMove::Center,
+            reserves: Vec::new(),
+            last_move: None,
+            shared_deck: None,
+            hidden_deck: None,
+            drawn: Vec::new(),
+            submitted: None,
+            move_count: 0,
+            board_hash: None,
+        }
+    }
+
+    #[test]
+    fn test_legal_moves_first_move() {
+        let hand = [
+            Card(Suit::Clubs, Value::Two),
+            Card(Suit::Hearts, Value::Nine),
+        ];
+        let state = create_state(Board::from_positions(&[]), &hand);
+        let center = (BOARD_SIZE / 2, BOARD_SIZE / 2);
+        let moves = legal_moves(&state, false);
+        assert_eq!(moves.len(), 2);
+        assert!(
+            moves
+                .iter()
+                .all(|player_move| player_move.location == center)
+        );
+
+        // nothing is legal out of turn
+        let state = PlayerVisibleGameState { turn: 1, ..state };
+        assert!(legal_moves(&state, false).is_empty());
+    }
+
+    #[test]
+    fn test_require_capture() {
+        let center = BOARD_SIZE / 2;
+        let board = Board::from_positions(&[((center, center), Card(Suit::Hearts, Value::Seven))]);
+        let hand = [
+            Card(Suit::Clubs, Value::Seven),
+            Card(Suit::Clubs, Value::Two),
+        ];
+        let state = create_state(board, &hand);
+
+        // eight spots around the center card, for each card
+        assert_eq!(legal_moves(&state, false).len(), 16);
+        let moves = legal_moves(&state, true);
+        assert_eq!(moves.len(), 8);
+        assert!(moves.iter().all(|player_move| player_move.card == 0));
+    }
+
+    #[test]
+    fn test_greedy() {
+        let center = BOARD_SIZE / 2;
+        let board = Board::from_positions(&[
+            ((center, center), Card(Suit::Hearts, Value::Seven)),
+            ((center, center + 1), Card(Suit::Hearts, Value::Two)),
+        ]);
+        let hand = [
+            Card(Suit::Clubs, Value::Three),
+            Card(Suit::Clubs, Value::Seven),
+        ];
+        let state = create_state(board, &hand);
+        let moves = legal_moves(&state, false);
+
+        // the seven just past the two takes both cards
+        let player_move = Greedy.choose(&state, &moves);
+        assert_eq!(player_move.card, 1);
+        assert_eq!(player_move.location, (center, center + 2));
+        assert_eq!(state.cards_taken_by(&player_move), 2);
+
+        // and any random pick is one of the legal moves
+        let player_move = StrategyName::Random.build(Some(0)).choose(&state, &moves);
+        assert!(moves.contains(&player_move));
+    }
+
+    #[test]
+    fn test_difficulty() {
+        let center = BOARD_SIZE / 2;
+        let board = Board::from_positions(&[
+            ((center, center), Card(Suit::Hearts, Value::Seven)),
+            ((center, center + 1), Card(Suit::Hearts, Value::Two)),
+        ]);
+        let hand = [
+            Card(Suit::Clubs, Value::Three),
+            Card(Suit::Clubs, Value::Seven),
+        ];
+        let state = create_state(board, &hand);
+        let moves = legal_moves(&state, false);
+
+        // medium always finds the move taking both cards
+        let player_move = Difficulty::Medium
+            .strategy()
+            .build(None)
+            .choose(&state, &moves);
+        assert_eq!(state.cards_taken_by(&player_move), 2);
+
+        // easy doesn't look, so over enough turns it passes up the best move
+        let mut easy = Difficulty::Easy.strategy().build(Some(0));
+        assert!((0..20).any(|_| state.cards_taken_by(&easy.choose(&state, &moves)) < 2));
+    }
+}
@@ -17,54 +17,225 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use std::ops::Range;
+
 use dioxus::prelude::*;
-use grid_common::HAND_SIZE;
+use grid_common::{
+    BOARD_SIZE, CARD_GAP, CARD_HEIGHT, CARD_WIDTH, Card, FirstMove, HAND_SIZE, MoveSource,
+    PlayContext, PlayerMove, RankOrder, Suit, TakingVariant, Value, drawn_size, find_captures,
+};
+
+use crate::{
+    ASSIST, AWAY_MOVES, BOARD_STYLE, COUNT_CHANGES, DEAL_COMMITMENT, DEAL_REVEAL, FULL_BOARD,
+    GAME_NAME, HEAT_MAP, LOBBY_OPTIONS, PAUSED, RECENT_REACTIONS, SPECTATORS, TOURNAMENT,
+    TURN_NOTIFICATIONS,
+};
+
+/// Most cards in a deck to show one by one - bigger decks are summarised by suit
+const DECK_GLYPH_LIMIT: usize = 20;
+
+/// How the board is drawn
+#[derive(Clone, Copy, PartialEq)]
+pub enum BoardStyle {
+    /// Playing card characters - light, but rough when blown up
+    Glyphs,
+    /// Drawn card faces, which stay sharp at any size
+    Vector,
+}
+
+/// A reaction someone sent, shown briefly
+#[derive(Clone, PartialEq)]
+pub struct Reaction {
+    pub id: u64,
+    pub username: String,
+    pub reaction: String,
+}
 
 #[component]
 pub fn Game(
     game_state: grid_common::PlayerVisibleGameState,
     to_play: Option<usize>,
+    /// Whether our reserve card is picked to play, instead of a card from the hand
+    #[props(default)]
+    reserve_selected: bool,
     on_hand_click: Callback<usize, ()>,
+    /// Called when our reserve card is clicked, if it can be picked
+    on_reserve_click: Option<Callback<(), ()>>,
     on_board_click: Callback<(usize, usize), ()>,
+    /// Whether to briefly highlight what someone else's last move took
+    #[props(default)]
+    show_last_move: bool,
+    /// Whether we're only watching, so have no hand to show
+    #[props(default)]
+    spectating: bool,
+    /// Move to mark as a suggestion, in assist mode
+    #[props(default)]
+    suggestion: Option<PlayerMove>,
 ) -> Element {
+    let captured = game_state
+        .last_move
+        .as_ref()
+        .filter(|last_move| show_last_move && last_move.player != game_state.username)
+        .map(|last_move| last_move.taken.clone())
+        .unwrap_or_default();
+    let selected = if reserve_selected {
+        game_state.my_reserve()
+    } else {
+        to_play.and_then(|index| game_state.hand.0.get(index).copied())
+    };
+    let preview = selected.filter(|_| HEAT_MAP());
+    let suggested_spot = suggestion.as_ref().map(|suggestion| suggestion.location);
+    let suggested_card = suggestion
+        .as_ref()
+        .filter(|suggestion| suggestion.from == MoveSource::Hand)
+        .map(|suggestion| suggestion.card);
+
     rsx! {
-        div { class: "row",
-            div { class: "col-xl-4",
-                Board { board: game_state.board, on_board_click }
+        Reactions {}
+        if let Some(host) = PAUSED() {
+            div { class: "alert alert-warning", "Game paused by {host}" }
+        }
+        WhileAway { username: game_state.username.clone() }
+        // on narrow screens everything stacks, with the hand right under the board
+        div {
+            class: "row",
+            // the recap goes once we're back to playing
+            onclick: move |_| {
+                if !AWAY_MOVES.read().is_empty() {
+                    AWAY_MOVES.write().clear();
+                }
+            },
+            div { class: "col-xl-4 order-1 order-xl-1",
+                match BOARD_STYLE() {
+                    BoardStyle::Glyphs => rsx! {
+                        Board {
+                            board: game_state.board,
+                            wrap: game_state.wrap,
+                            started: game_state.started,
+                            first_move: game_state.first_move,
+                            taking_variant: game_state.taking_variant,
+                            rank_order: game_state.rank_order,
+                            preview,
+                            captured: captured.clone(),
+                            suggested: suggested_spot,
+                            on_board_click,
+                        }
+                    },
+                    BoardStyle::Vector => rsx! {
+                        VectorBoard {
+                            board: game_state.board,
+                            wrap: game_state.wrap,
+                            started: game_state.started,
+                            first_move: game_state.first_move,
+                            taking_variant: game_state.taking_variant,
+                            rank_order: game_state.rank_order,
+                            preview,
+                            captured: captured.clone(),
+                            suggested: suggested_spot,
+                            on_board_click,
+                        }
+                    },
+                }
             }
-            div { class: "col-xl-2",
-                Standings { standings: game_state.players }
+            div { class: "col-xl-2 order-3 order-xl-2",
+                Standings {
+                    standings: game_state.players,
+                    reserves: game_state.reserves,
+                    my_index: game_state.my_index,
+                    turn: game_state.turn,
+                    submitted: game_state.submitted,
+                    move_count: game_state.move_count,
+                    reserve_selected,
+                    on_reserve_click,
+                }
+                Spectators {}
+                DealCheck {}
+                BoardStyleToggle {}
+                HeatMapToggle {}
+                BoardCropToggle {}
+                if !spectating {
+                    AssistToggle {}
+                    TurnNotificationToggle {}
+                }
+                RulesHelp {
+                    taking_variant: game_state.taking_variant,
+                    wrap: game_state.wrap,
+                }
             }
-        }
-        div { class: "row",
-            div { class: "col-xl-4",
-                Hand { hand: game_state.hand, to_play, on_hand_click }
+            // ends the first line on wide screens
+            div { class: "w-100 d-none d-xl-block order-xl-3" }
+            if !spectating {
+                div { class: "col-xl-4 order-2 order-xl-4",
+                    Hand {
+                    hand: game_state.hand,
+                    drawn: game_state.drawn,
+                    to_play,
+                    suggested: suggested_card,
+                    on_hand_click,
+                }
+                }
             }
-            div { class: "col-xl-8",
-                Deck { deck: game_state.deck }
+            div { class: "col-xl-8 order-4 order-xl-5",
+                Deck {
+                    deck: game_state.deck,
+                    top_card: game_state.top_card,
+                    shared_deck: game_state.shared_deck,
+                    hidden_deck: game_state.hidden_deck,
+                }
             }
         }
     }
 }
 
 #[component]
-fn Board(board: grid_common::Board, on_board_click: Callback<(usize, usize), ()>) -> Element {
+fn Board(
+    board: grid_common::Board,
+    wrap: bool,
+    started: bool,
+    first_move: FirstMove,
+    taking_variant: TakingVariant,
+    rank_order: RankOrder,
+    preview: Option<Card>,
+    /// Positions taken by the last move, to highlight
+    captured: Vec<(usize, usize)>,
+    /// Spot suggested in assist mode
+    suggested: Option<(usize, usize)>,
+    on_board_click: Callback<(usize, usize), ()>,
+) -> Element {
+    let playable = board.playable_positions(PlayContext {
+        wrap,
+        started,
+        first_move,
+    });
+    let (rows, cols) = shown_area(&board, wrap);
     rsx! {
-        table { class: "user-select-none",
-            for (row_n , row) in board.0.into_iter().enumerate() {
+        table {
+            class: "user-select-none board-glyphs",
+            style: "--board-size: {cols.len()}",
+            for row_n in rows {
                 tr {
-                    for (card_n , card) in row.into_iter().enumerate() {
-                        match card {
+                    for card_n in cols.clone() {
+                        match board.0[row_n][card_n] {
                             Some(card) => {
                                 rsx! {
-                                    td { style: "font-size: 200%; color: {card.0.colour()}; font-family: DejaVu", "{card}" }
+                                    td { style: "color: {card.0.colour()}", "{card}" }
                                 }
                             }
                             None => {
-                                if board.can_play_at(row_n, card_n) {
+                                if playable.contains(&(row_n, card_n)) {
+                                    let heat = preview
+                                        .map(|card| {
+                                            capture_count(&board, card, (row_n, card_n), taking_variant, rank_order, wrap)
+                                        });
                                     rsx! {
                                         td {
-                                            style: "font-size:200%; color:#888888; font-family: DejaVu",
+                                            style: "color:#888888; background-color: {heat_colour(heat)}",
+                                            title: heat.map(|taken| format!("Takes {taken}")),
+                                            class: format!(
+                                                "{} {}",
+                                                if captured.contains(&(row_n, card_n)) { "captured" } else { "" },
+                                                if suggested == Some((row_n, card_n)) { "suggested" } else { "" },
+                                            ),
                                             role: "button",
                                             onclick: move |_| on_board_click((row_n, card_n)),
                                             "🂠"
@@ -72,7 +243,11 @@ fn Board(board: grid_common::Board, on_board_click: Callback<(usize, usize), ()>
                                     }
                                 } else {
                                     rsx! {
-                                        td { style: "font-size:200%; color:#888888; font-family: DejaVu", "🂠" }
+                                        td {
+                                            style: "color:#888888",
+                                            class: if captured.contains(&(row_n, card_n)) { "captured" },
+                                            "🂠"
+                                        }
                                     }
                                 }
                             }
@@ -84,47 +259,575 @@ fn Board(board: grid_common::Board, on_board_click: Callback<(usize, usize), ()>
     }
 }
 
+/// The board drawn as SVG, for big screens
 #[component]
-fn Deck(deck: grid_common::Deck) -> Element {
+fn VectorBoard(
+    board: grid_common::Board,
+    wrap: bool,
+    started: bool,
+    first_move: FirstMove,
+    taking_variant: TakingVariant,
+    rank_order: RankOrder,
+    preview: Option<Card>,
+    /// Positions taken by the last move, to highlight
+    captured: Vec<(usize, usize)>,
+    /// Spot suggested in assist mode
+    suggested: Option<(usize, usize)>,
+    on_board_click: Callback<(usize, usize), ()>,
+) -> Element {
+    let playable = board.playable_positions(PlayContext {
+        wrap,
+        started,
+        first_move,
+    });
+    // cells keep their place on the full board, and the view just looks at the part shown
+    let (rows, cols) = shown_area(&board, wrap);
+    let left = cols.start * (CARD_WIDTH + CARD_GAP);
+    let top = rows.start * (CARD_HEIGHT + CARD_GAP);
+    let (width, height) = drawn_size(&rows, &cols);
+
     rsx! {
-        p {
-            span { class: "user-select-none",
-                for card in deck.0.iter() {
-                    span { style: "font-size:200%; color:{card.0.colour()}; font-family: DejaVu",
-                        "{card}"
+        svg {
+            class: "user-select-none w-100",
+            view_box: "{left} {top} {width} {height}",
+            for row_n in rows {
+                for col_n in cols.clone() {
+                    {
+                        let card = board.0[row_n][col_n];
+                        let x = col_n * (CARD_WIDTH + CARD_GAP) + CARD_GAP;
+                        let y = row_n * (CARD_HEIGHT + CARD_GAP) + CARD_GAP;
+                        match card {
+                            Some(card) => rsx! {
+                                CardFace { card, x, y }
+                            },
+                            None if playable.contains(&(row_n, col_n)) => {
+                                let heat = preview
+                                    .map(|card| {
+                                        capture_count(&board, card, (row_n, col_n), taking_variant, rank_order, wrap)
+                                    });
+                                rsx! {
+                                    rect {
+                                        x: "{x}",
+                                        y: "{y}",
+                                        width: "{CARD_WIDTH}",
+                                        height: "{CARD_HEIGHT}",
+                                        rx: "6",
+                                        fill: if heat.is_some() { heat_colour(heat) } else { "#f0f0f0".to_string() },
+                                        stroke: "#888888",
+                                        stroke_dasharray: "4 3",
+                                        style: "cursor: pointer",
+                                        role: "button",
+                                        onclick: move |_| on_board_click((row_n, col_n)),
+                                    }
+                                    if captured.contains(&(row_n, col_n)) {
+                                        CaptureMark { x, y }
+                                    }
+                                    if suggested == Some((row_n, col_n)) {
+                                        circle {
+                                            cx: "{x + CARD_WIDTH - 10}",
+                                            cy: "{y + 10}",
+                                            r: "6",
+                                            fill: "var(--bs-success)",
+                                            pointer_events: "none",
+                                        }
+                                    }
+                                    if let Some(taken) = heat.filter(|&taken| taken > 0) {
+                                        text {
+                                            x: "{x + CARD_WIDTH / 2}",
+                                            y: "{y + CARD_HEIGHT / 2 + 8}",
+                                            fill: "#333333",
+                                            font_size: "24",
+                                            text_anchor: "middle",
+                                            pointer_events: "none",
+                                            "{taken}"
+                                        }
+                                    }
+                                }
+                            }
+                            None => rsx! {
+                                rect {
+                                    x: "{x}",
+                                    y: "{y}",
+                                    width: "{CARD_WIDTH}",
+                                    height: "{CARD_HEIGHT}",
+                                    rx: "6",
+                                    fill: "#f8f8f8",
+                                }
+                                if captured.contains(&(row_n, col_n)) {
+                                    CaptureMark { x, y }
+                                }
+                            },
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Rows and columns of the board to draw
+///
+/// Unless the whole board is asked for, that's the cards played and a spot either side of them,
+/// which is everywhere the next card can go. Wrapping boards can be played on at the far edge, so
+/// are always drawn whole, as are empty ones.
+fn shown_area(board: &grid_common::Board, wrap: bool) -> (Range<usize>, Range<usize>) {
+    if FULL_BOARD() || wrap {
+        (0..BOARD_SIZE, 0..BOARD_SIZE)
+    } else {
+        board.occupied_area()
+    }
+}
+
+/// Highlight over a spot on the vector board that the last move took from, which fades away
+#[component]
+fn CaptureMark(x: usize, y: usize) -> Element {
+    rsx! {
+        rect {
+            class: "capture-mark",
+            x: "{x}",
+            y: "{y}",
+            width: "{CARD_WIDTH}",
+            height: "{CARD_HEIGHT}",
+            rx: "6",
+            fill: "#ffc107",
+            pointer_events: "none",
+        }
+    }
+}
+
+/// How many cards on the board playing a card at a position would take
+fn capture_count(
+    board: &grid_common::Board,
+    card: Card,
+    position: (usize, usize),
+    taking_variant: TakingVariant,
+    rank_order: RankOrder,
+    wrap: bool,
+) -> usize {
+    // the played card is counted among the captures, but it's not taken from anyone
+    find_captures(board, card, position, taking_variant, rank_order, wrap)
+        .len()
+        .saturating_sub(1)
+}
+
+/// Background for a spot on the heat map - deeper red the more it takes, clear when off
+fn heat_colour(heat: Option<usize>) -> String {
+    match heat {
+        None => "transparent".to_string(),
+        Some(taken) => format!("rgba(220, 53, 69, {:.2})", 0.1 + 0.15 * taken.min(4) as f64),
+    }
+}
+
+/// A card face for the vector board, with its value and suit in the corner and a large pip
+#[component]
+fn CardFace(card: Card, x: usize, y: usize) -> Element {
+    let colour = card.0.colour();
+    let value = card.1.label();
+    let suit = card.0.symbol();
+
+    rsx! {
+        rect {
+            x: "{x}",
+            y: "{y}",
+            width: "{CARD_WIDTH}",
+            height: "{CARD_HEIGHT}",
+            rx: "6",
+            fill: "#ffffff",
+            stroke: "#333333",
+            stroke_width: "1.5",
+        }
+        text {
+            x: "{x + 6}",
+            y: "{y + 20}",
+            fill: "{colour}",
+            font_size: "18",
+            font_weight: "bold",
+            "{value}"
+        }
+        text {
+            x: "{x + CARD_WIDTH / 2}",
+            y: "{y + CARD_HEIGHT * 3 / 4}",
+            fill: "{colour}",
+            font_size: "36",
+            text_anchor: "middle",
+            "{suit}"
+        }
+    }
+}
+
+/// Button switching between the glyph and vector boards
+#[component]
+fn BoardStyleToggle() -> Element {
+    let (next, label) = match BOARD_STYLE() {
+        BoardStyle::Glyphs => (BoardStyle::Vector, "Sharper board"),
+        BoardStyle::Vector => (BoardStyle::Glyphs, "Simpler board"),
+    };
+    rsx! {
+        button {
+            class: "btn btn-sm btn-outline-secondary",
+            onclick: move |_| *BOARD_STYLE.write() = next,
+            "{label}"
+        }
+    }
+}
+
+/// Button switching between the whole board and just the part with cards on it
+#[component]
+fn BoardCropToggle() -> Element {
+    let label = if FULL_BOARD() {
+        "Zoom to cards"
+    } else {
+        "Show full board"
+    };
+    rsx! {
+        button {
+            class: "btn btn-sm btn-outline-secondary ms-1",
+            onclick: move |_| {
+                let on = FULL_BOARD();
+                *FULL_BOARD.write() = !on;
+            },
+            "{label}"
+        }
+    }
+}
+
+/// Button turning browser notifications for our turn on and off
+#[component]
+fn TurnNotificationToggle() -> Element {
+    let label = if TURN_NOTIFICATIONS() {
+        "Stop turn notifications"
+    } else {
+        "Notify me on my turn"
+    };
+    rsx! {
+        button {
+            class: "btn btn-sm btn-outline-secondary ms-1",
+            onclick: move |_| {
+                let on = TURN_NOTIFICATIONS();
+                if !on {
+                    // if the player says no, the browser just won't show them
+                    document::eval("if (window.Notification) { Notification.requestPermission(); }");
+                }
+                *TURN_NOTIFICATIONS.write() = !on;
+            },
+            "{label}"
+        }
+    }
+}
+
+/// Button turning the capture heat map on and off
+#[component]
+fn HeatMapToggle() -> Element {
+    let label = if HEAT_MAP() {
+        "Hide heat map"
+    } else {
+        "Show heat map"
+    };
+    rsx! {
+        button {
+            class: "btn btn-sm btn-outline-secondary ms-1",
+            onclick: move |_| {
+                let on = HEAT_MAP();
+                *HEAT_MAP.write() = !on;
+            },
+            "{label}"
+        }
+    }
+}
+
+/// Button turning assist mode on and off, which suggests a move on our turn
+#[component]
+fn AssistToggle() -> Element {
+    let label = if ASSIST() {
+        "Stop suggesting moves"
+    } else {
+        "Suggest moves"
+    };
+    rsx! {
+        button {
+            class: "btn btn-sm btn-outline-secondary ms-1",
+            onclick: move |_| {
+                let on = ASSIST();
+                *ASSIST.write() = !on;
+            },
+            "{label}"
+        }
+    }
+}
+
+/// Button opening an explanation of the rules this game is played with
+///
+/// The taking variant and wrapping come from the game state, since players who join mid-game
+/// never see the lobby's options
+#[component]
+fn RulesHelp(taking_variant: TakingVariant, wrap: bool) -> Element {
+    let mut open = use_signal(|| false);
+
+    rsx! {
+        button {
+            class: "btn btn-sm btn-outline-secondary ms-1",
+            onclick: move |_| open.set(true),
+            "Rules"
+        }
+        if open() {
+            div { class: "rules-overlay", onclick: move |_| open.set(false),
+                div {
+                    class: "card rules-card",
+                    onclick: move |e| e.stop_propagation(),
+                    div { class: "card-body",
+                        h2 { class: "card-title", "How to play" }
+                        ul {
+                            for rule in rules(taking_variant, wrap, &LOBBY_OPTIONS()) {
+                                li { "{rule}" }
+                            }
+                        }
+                        button {
+                            class: "btn btn-outline-primary",
+                            onclick: move |_| open.set(false),
+                            "Close"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The rules of the game, in plain words, for the given options
+fn rules(
+    taking_variant: TakingVariant,
+    wrap: bool,
+    options: &serde_json::Map<String, serde_json::Value>,
+) -> Vec<String> {
+    let flag = |name: &str| options.get(name).and_then(serde_json::Value::as_bool) == Some(true);
+    let choice = |name: &str| options.get(name).and_then(serde_json::Value::as_str);
+
+    let first_card = match choice("first_move") {
+        Some("center-line") => "anywhere in the middle row or column",
+        Some("anywhere") => "anywhere",
+        _ => "in the centre",
+    };
+    let mut rules = vec![format!(
+        "Take turns playing a card from your hand next to a card on the board - beside, above, \
+         below, or diagonally. The first card goes {first_card}."
+    )];
+    if flag("simultaneous") {
+        rules.push(
+            "Instead of taking turns, everyone picks their play in secret each round. Then they're \
+             played in turn order, skipping any that can't be played by the time they come up."
+                .to_string(),
+        );
+    }
+    rules.push(if wrap {
+        format!(
+            "The board is {BOARD_SIZE} by {BOARD_SIZE}, and its edges wrap around to the opposite \
+             side."
+        )
+    } else {
+        format!("The board is {BOARD_SIZE} by {BOARD_SIZE}.")
+    });
+    rules.push(
+        match taking_variant {
+            TakingVariant::SameNumber => {
+                "In each of the eight directions, a card takes the furthest card of the same \
+                 value, and every card in between."
+            }
+            TakingVariant::SameNumberOrSuitRanked => {
+                "In each of the eight directions, a card takes the furthest card of the same \
+                 value, or of the same suit and a lower value, and every card in between."
+            }
+        }
+        .to_string(),
+    );
+    if taking_variant == TakingVariant::SameNumberOrSuitRanked
+        && choice("rank_order") == Some("ace-high")
+    {
+        rules.push("Aces are high, above kings.".to_string());
+    }
+    let number = |name: &str| options.get(name).and_then(serde_json::Value::as_u64);
+    if let Some(max_capture) = number("max_capture") {
+        rules.push(format!(
+            "A play takes at most {max_capture} cards in all, nearest first."
+        ));
+    }
+    if flag("optional_capture") {
+        rules.push(
+            "Taking is optional - when a play can take something, you pick which directions to \
+             take in, if any."
+                .to_string(),
+        );
+    }
+    let placement = match choice("capture_placement") {
+        Some("top") => "on top of your deck, so you draw them next",
+        _ => "on the bottom of your deck",
+    };
+    let order = match choice("capture_order") {
+        Some("ordered") => "in board order",
+        _ => "shuffled",
+    };
+    rules.push(format!("Cards you take go {placement}, {order}."));
+    let draw = match number("draw_per_turn") {
+        Some(draws) => format!(
+            "draw {draws} cards from your deck, holding at most {} cards",
+            number("max_hand_size").unwrap_or(HAND_SIZE as u64)
+        ),
+        None => format!("draw from your deck to get up to {HAND_SIZE} cards"),
+    };
+    rules.push(if flag("deal_to_empty") {
+        format!("Hands start empty. At the start of each of your turns, you {draw}.")
+    } else {
+        format!("After each play, you {draw}.")
+    });
+    if flag("shared_deck") {
+        rules.push(
+            "Everyone draws from one shared deck. Cards you take go to your own deck, which you \
+             draw from once the shared deck runs out."
+                .to_string(),
+        );
+    }
+    if flag("sequester_cards") {
+        rules.push(
+            "Some cards are set aside before the deal, and never come into play.".to_string(),
+        );
+    }
+    if flag("face_card_hands") {
+        rules.push("Everyone's opening hand has at least one jack, queen, or king.".to_string());
+    }
+    if flag("balanced_deal") {
+        rules.push("Everyone is dealt about the same points' worth of cards.".to_string());
+    }
+    if flag("reserve_card") {
+        rules.push(
+            "Everyone starts with a face-up reserve card, which anyone can see and its owner can \
+             play instead of a card from their hand."
+                .to_string(),
+        );
+    }
+    if flag("require_capture") {
+        rules.push(
+            "If any of your cards can take something, you must play one that does.".to_string(),
+        );
+    }
+    if flag("hide_own_deck") {
+        rules.push("You can see how many cards are in your deck, but not which.".to_string());
+    }
+    if flag("peek_top") {
+        rules.push("You can see the next card you'll draw.".to_string());
+    }
+    rules.push(
+        "You're out once you have no cards left. The last player with cards wins.".to_string(),
+    );
+    rules
+}
+
+#[component]
+fn Deck(
+    deck: grid_common::Deck,
+    top_card: Option<grid_common::Card>,
+    shared_deck: Option<usize>,
+    /// Size of our deck, if the game doesn't show what's in it
+    hidden_deck: Option<usize>,
+) -> Element {
+    rsx! {
+        if let Some(card) = top_card {
+            p { class: "user-select-none",
+                "Next draw: "
+                span { style: "font-size:300%; color:{card.0.colour()}; font-family: DejaVu",
+                    "{card}"
+                }
+            }
+        }
+        if let Some(count) = hidden_deck {
+            p { "({count} in deck)" }
+        } else {
+            p {
+                if deck.0.len() <= DECK_GLYPH_LIMIT {
+                    span { class: "user-select-none",
+                        for card in deck.0.iter() {
+                            span { style: "font-size:200%; color:{card.0.colour()}; font-family: DejaVu",
+                                "{card}"
+                            }
+                        }
+                    }
+                } else {
+                    for (suit , count , values) in deck_by_suit(&deck) {
+                        span { class: "me-3 text-nowrap",
+                            span { style: "color:{suit.colour()}", "{suit.symbol()}" }
+                            " ({count}): {values}"
+                        }
                     }
                 }
+                br {}
+                "({deck.0.len()} in deck)"
             }
-            br {}
-            "({deck.0.len()} in deck)"
+        }
+        if let Some(count) = shared_deck {
+            p { "{count} left in the shared deck" }
         }
     }
 }
 
+/// Each suit in a deck, with how many cards of it there are and their values, lowest first
+fn deck_by_suit(deck: &grid_common::Deck) -> Vec<(Suit, usize, String)> {
+    Suit::ALL
+        .into_iter()
+        .filter_map(|suit| {
+            let values = Value::ALL
+                .into_iter()
+                .flat_map(|value| {
+                    let count = deck
+                        .0
+                        .iter()
+                        .filter(|&&card| card == Card(suit, value))
+                        .count();
+                    std::iter::repeat_n(value.label(), count)
+                })
+                .collect::<Vec<_>>();
+            (!values.is_empty()).then(|| (suit, values.len(), values.join(" ")))
+        })
+        .collect()
+}
+
 #[component]
 fn Hand(
     hand: grid_common::Hand,
+    /// Positions of cards just drawn, which slide in
+    drawn: Vec<usize>,
     to_play: Option<usize>,
+    /// Card suggested in assist mode
+    #[props(default)]
+    suggested: Option<usize>,
     on_hand_click: Callback<usize, ()>,
 ) -> Element {
+    // hands can grow past the usual size if the game draws a set number each turn
+    let slots = hand.0.len().max(HAND_SIZE);
     rsx! {
-        table { class: "user-select-none", style: "border-collapse: separate",
+        table {
+            class: "user-select-none hand-glyphs",
+            style: "border-collapse: separate; --hand-size: {slots}",
             tr {
-                for index in 0..HAND_SIZE {
+                for index in 0..slots {
                     {
                         let card = hand.0.get(index);
                         match card {
                             Some(card) => rsx! {
                                 td {
-                                    style: "font-size:400%; color:{card.0.colour()}; font-family: DejaVu",
+                                    // keyed by card, so cards shifting along aren't redrawn and only new ones slide in
+                                    key: "{card}",
+                                    style: "color:{card.0.colour()}",
                                     role: "button",
-                                    class: if to_play.is_some_and(|to_play| to_play == index) { "border border-3 border-dark" } else { "border border-3 border-white" },
+                                    class: format!(
+                                        "border border-3 {} {} {}",
+                                        if to_play.is_some_and(|to_play| to_play == index) { "selected-card" } else { "border-white" },
+                                        if drawn.contains(&index) { "drawn-card" } else { "" },
+                                        if suggested == Some(index) { "suggested" } else { "" },
+                                    ),
                                     onclick: move |_| on_hand_click(index),
                                     "{card}"
                                 }
                             },
                             None => rsx! {
-                                td { style: "font-size:400%; color:#888888; font-family: DejaVu", "🂠" }
+                                td { key: "empty-{index}", style: "color:#888888", "🂠" }
                             },
                         }
                     }
@@ -134,13 +837,242 @@ fn Hand(
     }
 }
 
+/// Recent reactions, floating in the corner until they fade away
 #[component]
-fn Standings(standings: Vec<(String, u32)>) -> Element {
+fn Reactions() -> Element {
     rsx! {
-        table {
-            for (player , count) in standings {
+        div { class: "reactions",
+            for reaction in RECENT_REACTIONS() {
+                div { key: "{reaction.id}", class: "reaction",
+                    span { class: "badge text-bg-light fs-5",
+                        "{reaction.reaction} {reaction.username}"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// What happened since our own last move, after rejoining a game
+#[component]
+fn WhileAway(username: String) -> Element {
+    let moves = AWAY_MOVES()
+        .into_iter()
+        .map(|last_move| {
+            let who = if last_move.player == username {
+                "You".to_string()
+            } else {
+                last_move.player
+            };
+            (who, last_move.card, !last_move.taken.is_empty())
+        })
+        .collect::<Vec<_>>();
+    rsx! {
+        if !moves.is_empty() {
+            div { class: "alert alert-info",
+                h6 { "While you were away…" }
+                ul { class: "mb-0",
+                    for (who , card , took) in moves {
+                        li {
+                            "{who} played "
+                            span { style: "color:{card.0.colour()}; font-family: DejaVu", "{card}" }
+                            if took {
+                                ", taking cards"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// How many people are watching, if anyone is
+#[component]
+pub fn Spectators() -> Element {
+    let count = SPECTATORS();
+    rsx! {
+        if count > 0 {
+            p { "👀 {count} watching" }
+        }
+    }
+}
+
+/// The hash the server committed to the deal with, and the deal itself once it's revealed
+#[component]
+fn DealCheck() -> Element {
+    let Some(hash) = DEAL_COMMITMENT() else {
+        return rsx! {};
+    };
+    rsx! {
+        p { class: "small text-muted text-break", title: "SHA-256 of the deal, revealed when the game ends",
+            "Deal hash: "
+            code { "{hash}" }
+        }
+        if let Some(reveal) = DEAL_REVEAL() {
+            details { class: "small mb-3",
+                summary { "Revealed deal" }
+                p { class: "text-muted", "Its SHA-256 hash should match the deal hash above." }
+                pre { class: "text-break", style: "white-space: pre-wrap", "{reveal}" }
+            }
+        }
+    }
+}
+
+/// Wins so far in the tournament, if there is one
+#[component]
+pub fn TournamentTally() -> Element {
+    let Some(standings) = TOURNAMENT() else {
+        return rsx! {};
+    };
+    rsx! {
+        h2 { "Tournament" }
+        p {
+            if standings.is_over() {
+                match standings.champion() {
+                    Some(champion) => rsx! { "{champion} is the champion after {standings.rounds} games" },
+                    None => rsx! { "The lead is shared after {standings.rounds} games" },
+                }
+            } else {
+                "{standings.played} of {standings.rounds} games played"
+            }
+        }
+        table { class: "table table-sm w-auto",
+            for (name , wins) in standings.wins.iter() {
                 tr {
-                    td { "{player}: {count} cards" }
+                    td { "{name}" }
+                    td { "{wins}" }
+                }
+            }
+        }
+    }
+}
+
+/// Everyone in turn order, picking out whoever's up and dimming anyone out of cards
+#[component]
+fn TurnOrder(
+    players: Vec<(String, u32)>,
+    turn: usize,
+    /// Who has picked their move this round, if everyone moves at once
+    submitted: Option<Vec<bool>>,
+    my_index: Option<usize>,
+) -> Element {
+    // when everyone moves at once, everyone still picking is up
+    let is_up = |index: usize| match &submitted {
+        Some(submitted) => submitted.get(index) == Some(&false),
+        None => index == turn,
+    };
+    let seats = players
+        .into_iter()
+        .enumerate()
+        .map(|(index, (player, count))| {
+            let name = if Some(index) == my_index {
+                "You".to_string()
+            } else {
+                player
+            };
+            let class = if count == 0 {
+                "badge text-bg-light text-decoration-line-through opacity-50"
+            } else if is_up(index) {
+                "badge text-bg-primary"
+            } else {
+                "badge text-bg-light"
+            };
+            (name, class)
+        })
+        .collect::<Vec<_>>();
+
+    rsx! {
+        div { class: "d-flex flex-wrap align-items-center gap-1 mb-3",
+            for (index , (name , class)) in seats.into_iter().enumerate() {
+                if index > 0 {
+                    span { class: "text-muted", "→" }
+                }
+                span { class, "{name}" }
+            }
+        }
+    }
+}
+
+#[component]
+fn Standings(
+    standings: Vec<(String, u32)>,
+    /// Each player's face-up reserve card, in the same order
+    reserves: Vec<Option<Card>>,
+    /// This player's own seat, if they're playing
+    my_index: Option<usize>,
+    turn: usize,
+    /// Who has picked their move this round, if everyone moves at once
+    submitted: Option<Vec<bool>>,
+    /// Moves played so far this game
+    move_count: usize,
+    reserve_selected: bool,
+    on_reserve_click: Option<Callback<(), ()>>,
+) -> Element {
+    let (change_id, changes) = COUNT_CHANGES();
+    let change_for = |player: &str| {
+        changes
+            .iter()
+            .find(|(changed, _)| changed == player)
+            .map(|(_, change)| *change)
+    };
+
+    rsx! {
+        if let Some(name) = GAME_NAME() {
+            h5 { "{name}" }
+        }
+        if let Some(my_index) = my_index {
+            p { "You are player {my_index + 1} of {standings.len()}" }
+        }
+        TurnOrder {
+            players: standings.clone(),
+            turn,
+            submitted,
+            my_index,
+        }
+        p { "Moves played: {move_count}" }
+        table { class: "user-select-none",
+            for (index , (player , count)) in standings.into_iter().enumerate() {
+                tr {
+                    // a changed row is drawn afresh, so its flash starts over
+                    key: "{player}-{change_for(&player).map_or(0, |_| change_id)}",
+                    class: if Some(index) == my_index { "fw-bold" },
+                    td { class: if change_for(&player).is_some() { "count-changed" },
+                        "{player}: {count} cards"
+                        if let Some(change) = change_for(&player) {
+                            span { class: "count-change ms-1 text-muted",
+                                if change > 0 {
+                                    "+{change}"
+                                } else {
+                                    "{change}"
+                                }
+                            }
+                        }
+                    }
+                    match reserves.get(index).copied().flatten() {
+                        Some(card) if Some(index) == my_index && on_reserve_click.is_some() => rsx! {
+                            td {
+                                style: "font-size:200%; color:{card.0.colour()}; font-family: DejaVu",
+                                role: "button",
+                                title: "Your reserve card",
+                                class: if reserve_selected { "border border-3 selected-card" } else { "border border-3 border-white" },
+                                onclick: move |_| {
+                                    if let Some(on_reserve_click) = on_reserve_click {
+                                        on_reserve_click(());
+                                    }
+                                },
+                                "{card}"
+                            }
+                        },
+                        Some(card) => rsx! {
+                            td {
+                                style: "font-size:200%; color:{card.0.colour()}; font-family: DejaVu",
+                                title: "Reserve card",
+                                "{card}"
+                            }
+                        },
+                        None => rsx! {},
+                    }
                 }
             }
         }
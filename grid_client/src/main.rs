@@ -20,15 +20,80 @@
 //! Client for Grid Online
 
 mod display;
+#[cfg(feature = "mock")]
+mod mock;
 mod scenes;
+#[cfg_attr(feature = "mock", allow(dead_code))]
+mod websocket;
 
 use dioxus::prelude::*;
-use grid_common::PlayerVisibleGameState;
-use ws_queue_web::WebSocketClient;
+use grid_common::{LastMove, PlayerVisibleGameState, TournamentStandings};
 
-use crate::scenes::*;
+use crate::{
+    display::{BoardStyle, Reaction},
+    scenes::*,
+};
 
-static WEBSOCKET: GlobalSignal<Option<WebSocketClient>> = Global::new(|| None);
+#[cfg(not(feature = "mock"))]
+type Client = websocket::WebSocketClient;
+#[cfg(feature = "mock")]
+type Client = mock::MockConnection;
+
+static WEBSOCKET: GlobalSignal<Option<Client>> = Global::new(|| None);
+static SPECTATORS: GlobalSignal<usize> = Global::new(|| 0);
+/// Name the host gave this game, if any
+static GAME_NAME: GlobalSignal<Option<String>> = Global::new(|| None);
+static RECENT_REACTIONS: GlobalSignal<Vec<Reaction>> = Global::new(Vec::new);
+static BOARD_STYLE: GlobalSignal<BoardStyle> = Global::new(|| BoardStyle::Glyphs);
+/// Whether to draw the whole board, instead of just around the cards played
+static FULL_BOARD: GlobalSignal<bool> = Global::new(|| false);
+/// Whether to shade the board by how much the selected card would take at each spot
+static HEAT_MAP: GlobalSignal<bool> = Global::new(|| false);
+/// Whether to suggest the move that takes the most, on our turn
+static ASSIST: GlobalSignal<bool> = Global::new(|| false);
+/// Why the server refused our last move, if it did and we haven't moved since
+static MOVE_REJECTED: GlobalSignal<Option<String>> = Global::new(|| None);
+/// Who paused the game, while it's paused
+static PAUSED: GlobalSignal<Option<String>> = Global::new(|| None);
+/// Why the server refused to pause or resume the game, if it did
+static PAUSE_REJECTED: GlobalSignal<Option<String>> = Global::new(|| None);
+/// Cards our last move took, once the server confirms it and before the new state arrives
+static MOVE_CONFIRMED: GlobalSignal<Option<usize>> = Global::new(|| None);
+/// Move picked during someone else's turn, to play as soon as ours starts
+static QUEUED_MOVE: GlobalSignal<Option<QueuedMove>> = Global::new(|| None);
+/// Options the next game will be played with, as sent by the server
+static LOBBY_OPTIONS: GlobalSignal<serde_json::Map<String, serde_json::Value>> =
+    Global::new(serde_json::Map::new);
+/// Seconds until the game starts without a full table, if enough players have joined
+static COUNTDOWN: GlobalSignal<Option<u64>> = Global::new(|| None);
+/// Why the server refused our last change to the options
+static OPTIONS_REJECTED: GlobalSignal<Option<String>> = Global::new(|| None);
+/// Server URL and login message we joined with, to reconnect with if the connection drops
+static LOGIN: GlobalSignal<Option<(String, String)>> = Global::new(|| None);
+/// Wins so far, if the server is running a tournament and it's begun
+static TOURNAMENT: GlobalSignal<Option<TournamentStandings>> = Global::new(|| None);
+/// Moves made since our own last one, sent on rejoining, until we next click something
+static AWAY_MOVES: GlobalSignal<Vec<LastMove>> = Global::new(Vec::new);
+/// How much each player's card count changed with the latest state, for players whose count did
+///
+/// Numbered so each new change flashes afresh, even if it's the same as the last
+static COUNT_CHANGES: GlobalSignal<(u64, Vec<(String, i64)>)> = Global::new(|| (0, Vec::new()));
+/// Hash the server committed to this game's deal with, if it's committing to deals
+static DEAL_COMMITMENT: GlobalSignal<Option<String>> = Global::new(|| None);
+/// The deal and salt the commitment was made from, once the game is over
+static DEAL_REVEAL: GlobalSignal<Option<String>> = Global::new(|| None);
+/// Spectators we've let see our hand this game
+static COACHES: GlobalSignal<Vec<String>> = Global::new(Vec::new);
+/// Whether we joined to watch rather than play
+static SPECTATING: GlobalSignal<bool> = Global::new(|| false);
+/// Who won the game we're watching, once it's over - nobody, if everyone forfeited
+static SPECTATED_WINNER: GlobalSignal<Option<Option<String>>> = Global::new(|| None);
+/// Whether we've just got back into the game, and it's still the turn we came back to
+static RECONNECTED: GlobalSignal<bool> = Global::new(|| false);
+/// Whether to ask the browser to tell us when our turn comes while we're looking elsewhere
+static TURN_NOTIFICATIONS: GlobalSignal<bool> = Global::new(|| false);
+/// Tries at reconnecting made since the connection last dropped
+static RECONNECT_ATTEMPT: GlobalSignal<u32> = Global::new(|| 0);
 
 enum ClientState {
     Error(String),
@@ -38,6 +103,10 @@ enum ClientState {
     YourTurn(PlayerVisibleGameState),
     YouLost(PlayerVisibleGameState),
     YouWin(PlayerVisibleGameState),
+    /// Connection dropped mid-game, showing the last state we had while we try to get back in
+    Reconnecting(PlayerVisibleGameState),
+    /// Watching someone else's game, with no hand and no moves to make
+    Spectating(PlayerVisibleGameState),
 }
 
 fn main() {
@@ -48,8 +117,40 @@ fn main() {
 fn App() -> Element {
     let state = use_signal(|| ClientState::Login);
 
+    // Ask before leaving mid-game, since closing the tab drops the connection
+    use_effect(move || {
+        let in_game = matches!(
+            *state.read(),
+            ClientState::NotYourTurn(_) | ClientState::YourTurn(_) | ClientState::Reconnecting(_)
+        );
+        document::eval(if in_game {
+            "window.onbeforeunload = (event) => { event.preventDefault(); return event.returnValue = 'Leave the game?'; };"
+        } else {
+            "window.onbeforeunload = null;"
+        });
+    });
+
+    // Fall back to our own basic styles if Bootstrap can't be fetched, and drop them if it turns
+    // up late
+    use_effect(|| {
+        document::eval(
+            r#"
+const bootstrap = document.querySelector('link[href*="bootstrap.min.css"]');
+const check = () => {
+    if (!getComputedStyle(document.documentElement).getPropertyValue("--bs-body-bg")) {
+        document.documentElement.classList.add("no-bootstrap");
+    }
+};
+bootstrap?.addEventListener("error", check);
+bootstrap?.addEventListener("load", () => document.documentElement.classList.remove("no-bootstrap"));
+setTimeout(check, 5000);
+"#,
+        );
+    });
+
     rsx! {
         document::Link { rel: "stylesheet", href: asset!("/assets/main.css") }
+        document::Link { rel: "stylesheet", href: asset!("/assets/fallback.css") }
         document::Style {
             r#"
 @font-face {{
@@ -117,12 +218,22 @@ fn App() -> Element {
             }
             ClientState::YouLost(ref game_state) => {
                 rsx! {
-                    YouLost { game_state: game_state.clone() }
+                    YouLost { state, game_state: game_state.clone() }
                 }
             }
             ClientState::YouWin(ref game_state) => {
                 rsx! {
-                    YouWin { game_state: game_state.clone() }
+                    YouWin { state, game_state: game_state.clone() }
+                }
+            }
+            ClientState::Reconnecting(ref game_state) => {
+                rsx! {
+                    Reconnecting { game_state: game_state.clone() }
+                }
+            }
+            ClientState::Spectating(ref game_state) => {
+                rsx! {
+                    Spectator { state, game_state: game_state.clone() }
                 }
             }
         }
@@ -0,0 +1,216 @@
+// Copyright 2025 Justin Hu
+//
+// This file is part of Grid Online.
+//
+// Grid Online is free software: you can redistribute it and/or modify it under
+// the terms of the GNU Affero General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// Grid Online is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU Affero General Public License
+// for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with Grid Online. If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Scripted stand-in for the game server, for working on the scenes without one
+//!
+//! Build with `--features mock` to use it in place of the real connection. Any server URL and
+//! join code are accepted, and moves sent are ignored.
+
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    rc::{Rc, Weak},
+};
+
+use grid_common::{
+    BOARD_SIZE, Board, Card, Deck, FirstMove, HAND_SIZE, Hand, PlayerVisibleGameState, RankOrder,
+    Suit, TakingVariant, Value,
+};
+use wasm_bindgen::{JsCast, closure::Closure};
+
+use crate::websocket::{CloseFrame, Connection, ConnectionError};
+
+type Handler<T> = Box<dyn FnMut(T)>;
+
+/// Milliseconds between scripted messages
+const STEP_DELAY: i32 = 3000;
+
+/// Connection that plays back a canned game
+pub struct MockConnection {
+    shared: Rc<RefCell<Shared>>,
+}
+
+#[derive(Default)]
+struct Shared {
+    script: VecDeque<Step>,
+    onmessage: Option<Handler<String>>,
+    onclose: Option<Handler<CloseFrame>>,
+    /// Bumped whenever a handler is set, so a running handler isn't put back over its replacement
+    generation: u64,
+}
+
+enum Step {
+    Message(String),
+    Close(CloseFrame),
+}
+
+impl Connection for MockConnection {
+    fn connect(_url: &str, login: &str) -> Result<Self, ConnectionError> {
+        let username = login.split('\n').next().unwrap_or_default();
+        let shared = Rc::new(RefCell::new(Shared {
+            script: script(username),
+            ..Shared::default()
+        }));
+        schedule_step(Rc::downgrade(&shared), 0);
+        Ok(Self { shared })
+    }
+
+    fn send(&mut self, _message: &str) {}
+
+    fn set_onmessage(&mut self, handler: Option<Handler<String>>) {
+        let mut shared = self.shared.borrow_mut();
+        shared.onmessage = handler;
+        shared.generation += 1;
+    }
+
+    fn set_onerror(&mut self, _handler: Option<Handler<ConnectionError>>) {
+        // the script never fails
+    }
+
+    fn set_onclose(&mut self, handler: Option<Handler<CloseFrame>>) {
+        let mut shared = self.shared.borrow_mut();
+        shared.onclose = handler;
+        shared.generation += 1;
+    }
+}
+
+/// Play the next step of the script after a delay, for as long as the connection is alive
+fn schedule_step(shared: Weak<RefCell<Shared>>, delay: i32) {
+    let callback = Closure::once_into_js(move || {
+        let Some(shared) = shared.upgrade() else {
+            return;
+        };
+        if step(&shared) {
+            schedule_step(Rc::downgrade(&shared), STEP_DELAY);
+        }
+    });
+    if let Some(window) = web_sys::window() {
+        let _ = window
+            .set_timeout_with_callback_and_timeout_and_arguments_0(callback.unchecked_ref(), delay);
+    }
+}
+
+/// Deliver the next step, if there's a handler for it
+///
+/// Returns whether there's more of the script to play
+fn step(shared: &RefCell<Shared>) -> bool {
+    let (next, generation) = {
+        let mut shared = shared.borrow_mut();
+        let ready = match shared.script.front() {
+            None => return false,
+            Some(Step::Message(_)) => shared.onmessage.is_some(),
+            Some(Step::Close(_)) => shared.onclose.is_some(),
+        };
+        if !ready {
+            // wait for the next scene to start listening
+            return true;
+        }
+        (
+            shared.script.pop_front().expect("checked above"),
+            shared.generation,
+        )
+    };
+
+    match next {
+        Step::Message(message) => {
+            let mut handler = shared.borrow_mut().onmessage.take().expect("checked above");
+            handler(message);
+            let mut shared = shared.borrow_mut();
+            if shared.generation == generation {
+                shared.onmessage = Some(handler);
+            }
+        }
+        Step::Close(frame) => {
+            let mut handler = shared.borrow_mut().onclose.take().expect("checked above");
+            handler(frame);
+            let mut shared = shared.borrow_mut();
+            if shared.generation == generation {
+                shared.onclose = Some(handler);
+            }
+        }
+    }
+    true
+}
+
+/// A short game against one opponent, ending in a win
+fn script(username: &str) -> VecDeque<Step> {
+    let mut cards = Suit::ALL
+        .into_iter()
+        .flat_map(|suit| Value::ALL.map(|value| Card(suit, value)));
+    let mut take = |count: usize| cards.by_ref().take(count).collect::<Vec<_>>();
+
+    let mut board = Board([[None; BOARD_SIZE]; BOARD_SIZE]);
+    let centre = BOARD_SIZE / 2;
+    board.0[centre][centre] = take(1).pop();
+    let mut state = PlayerVisibleGameState {
+        board,
+        hand: Hand(take(HAND_SIZE)),
+        deck: Deck(take(10)),
+        username: username.to_string(),
+        players: vec![("Opponent".to_string(), 20), (username.to_string(), 15)],
+        turn: 0,
+        my_index: Some(1),
+        top_card: None,
+        wrap: false,
+        taking_variant: TakingVariant::SameNumber,
+        rank_order: RankOrder::AceLow,
+        first_move: FirstMove::Center,
+        reserves: Vec::new(),
+        last_move: None,
+        shared_deck: None,
+        hidden_deck: None,
+        started: true,
+        drawn: Vec::new(),
+        submitted: None,
+        move_count: 0,
+        board_hash: None,
+    };
+
+    let mut steps = vec![
+        Step::Message("ok".to_string()),
+        Step::Message("spectators\n1".to_string()),
+        Step::Message(format!(
+            "game-started\n{}",
+            serde_json::json!(["Opponent", username])
+        )),
+    ];
+    let mut send_state = |state: &PlayerVisibleGameState| {
+        steps.push(Step::Message(
+            serde_json::to_string(state).expect("should always be able to serialize states"),
+        ));
+    };
+
+    send_state(&state);
+    state.turn = 1;
+    state.board.0[centre][centre + 1] = take(1).pop();
+    state.players[0].1 -= 1;
+    send_state(&state);
+    state.turn = 0;
+    state.board.0[centre + 1][centre] = Some(state.hand.0.remove(0));
+    state.hand.0.push(state.deck.0.remove(0));
+    state.players[1].1 -= 1;
+    send_state(&state);
+
+    steps.push(Step::Message("react\nOpponent\n😮".to_string()));
+    steps.push(Step::Close(CloseFrame {
+        code: 4000,
+        reason: format!("player won\n{username}"),
+    }));
+    steps.into()
+}
@@ -17,24 +17,55 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use std::{cell::RefCell, rc::Rc};
+
 use dioxus::prelude::*;
-use grid_common::{BOARD_SIZE, PlayerMove, PlayerVisibleGameState};
-use ws_queue_web::WebSocketClient;
+use grid_common::{
+    Card, DIRECTIONS, FirstMove, GameStateDelta, MoveSource, PlayerMove, PlayerVisibleGameState,
+    REACTIONS, can_take_any, find_captures_in,
+};
+use wasm_bindgen::{JsCast, closure::Closure};
+
+use crate::{
+    ASSIST, AWAY_MOVES, COACHES, COUNT_CHANGES, COUNTDOWN, Client, ClientState, DEAL_COMMITMENT,
+    DEAL_REVEAL, GAME_NAME, LOBBY_OPTIONS, LOGIN, MOVE_CONFIRMED, MOVE_REJECTED, OPTIONS_REJECTED,
+    PAUSE_REJECTED, PAUSED, QUEUED_MOVE, RECENT_REACTIONS, RECONNECT_ATTEMPT, RECONNECTED,
+    SPECTATED_WINNER, SPECTATING, SPECTATORS, TOURNAMENT, TURN_NOTIFICATIONS, WEBSOCKET,
+    display::{Game, Reaction, Spectators, TournamentTally},
+    websocket::{CloseFrame, Connection, ConnectionError, normalize_server_url},
+};
+
+/// Most reactions floating on screen at once
+const MAX_SHOWN_REACTIONS: usize = 5;
+/// Tries at getting back into a game before giving up
+const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+/// Milliseconds to wait before the first try at reconnecting, and more for each try after
+const RECONNECT_DELAY: i32 = 2000;
+/// Milliseconds to wait for the server to answer a login before giving up on it
+const LOGIN_TIMEOUT: i32 = 10_000;
+/// Milliseconds to show how a tournament game ended before joining the next one
+const NEXT_GAME_DELAY: i32 = 10_000;
 
-use crate::{ClientState, WEBSOCKET, display::Game};
+/// A move picked ahead of our turn
+#[derive(Clone, Copy, PartialEq)]
+pub struct QueuedMove {
+    card: Card,
+    location: (usize, usize),
+}
 
 #[component]
 pub fn Join(state: Signal<ClientState>) -> Element {
     let mut username = use_signal(|| "".to_string());
     let mut server_url = use_signal(|| "".to_string());
     let mut join_code = use_signal(|| "".to_string());
+    let mut spectate = use_signal(|| false);
     let mut submitting = use_signal(|| false);
     let mut error_message: Signal<Option<String>> = use_signal(|| None);
 
     const VERSION: &str = env!("CARGO_PKG_VERSION");
 
     rsx! {
-        div { class: "container",
+        div { class: "container scene",
             h1 { class: "row mb-3", "Grid Online version {VERSION}" }
             div { class: "row mb-3",
                 label {
@@ -81,6 +112,22 @@ pub fn Join(state: Signal<ClientState>) -> Element {
                     }
                 }
             }
+            div { class: "row mb-3",
+                div { class: "col-lg-5 offset-lg-1",
+                    div { class: "form-check",
+                        input {
+                            r#type: "checkbox",
+                            id: "spectate",
+                            class: "form-check-input",
+                            checked: *spectate.read(),
+                            oninput: move |e| spectate.set(e.checked()),
+                        }
+                        label { r#for: "spectate", class: "form-check-label",
+                            "Watch instead of play"
+                        }
+                    }
+                }
+            }
             if let Some(ref error) = *error_message.read() {
                 div { class: "row",
                     p { class: "text-danger", "{error}" }
@@ -90,65 +137,40 @@ pub fn Join(state: Signal<ClientState>) -> Element {
                 class: "row btn btn-primary",
                 r#type: "submit",
                 onclick: move |_| {
+                    let server_url = match normalize_server_url(&server_url.read()) {
+                        Ok(server_url) => server_url,
+                        Err(err) => {
+                            error_message.set(Some(err.to_string()));
+                            return;
+                        }
+                    };
                     submitting.set(true);
-                    let Ok(mut client) = WebSocketClient::new(
-                        &server_url.read(),
-                        Some(format!("{}\n{}", username.read(), join_code.read())),
-                    ) else {
+                    let mut login = format!("{}\n{}", username.read(), join_code.read());
+                    if *spectate.read() {
+                        login.push_str("\nspectate");
+                    }
+                    *SPECTATING.write() = *spectate.read();
+                    let joining = join_game(
+                        state,
+                        &server_url,
+                        &login,
+                        move |reason| {
+                            error_message.set(Some(reason.to_string()));
+                            submitting.set(false);
+                        },
+                    );
+                    if !joining {
                         error_message.set(Some("Couldn't connect to server".to_string()));
-                        return;
-                    };
-                    client
-                        .set_onmessage(
-                            Some(
-                                Box::new(move |message| {
-                                    match message.as_str() {
-                                        "ok" => {
-                                            state.set(ClientState::WaitingForPlayers);
-                                            WEBSOCKET
-                                                .write()
-                                                .as_mut()
-                                                .expect("got message from socket")
-                                                .set_onmessage(None);
-                                        }
-                                        "full" => {
-                                            error_message.set(Some("No open seats".to_string()));
-                                            *submitting.write() = false;
-                                            *WEBSOCKET.write() = None;
-                                        }
-                                        "username" => {
-                                            error_message
-                                                .set(Some("Username already taken".to_string()));
-                                            *submitting.write() = false;
-                                            *WEBSOCKET.write() = None;
-                                        }
-                                        "join code" => {
-                                            error_message.set(Some("Incorrect join code".to_string()));
-                                            *submitting.write() = false;
-                                            *WEBSOCKET.write() = None;
-                                        }
-                                        _ => {
-                                            protocol_error(state);
-                                        }
-                                    }
-                                }),
-                            ),
-                        );
-                    client
-                        .set_onerror(
-                            Some(
-                                Box::new(move |err| {
-                                    state
-                                        .set(
-                                            ClientState::Error(format!("Connection lost\n{err:#?}")),
-                                        );
-                                }),
-                            ),
-                        );
-                    *WEBSOCKET.write() = Some(client);
+                        submitting.set(false);
+                    }
                 },
                 disabled: *submitting.read(),
-                "Join Game"
+                if *submitting.read() {
+                    span { class: "spinner-border spinner-border-sm me-2", role: "status" }
+                    "Joining..."
+                } else {
+                    "Join Game"
+                }
             }
             div { class: "row",
                 p {
@@ -166,6 +188,95 @@ pub fn Join(state: Signal<ClientState>) -> Element {
     }
 }
 
+/// Connect and log in, moving to the lobby if the server lets us in
+///
+/// Returns whether we could start connecting. If the server turns us away, or doesn't answer in
+/// time, `refused` hears why.
+fn join_game(
+    mut state: Signal<ClientState>,
+    server_url: &str,
+    login: &str,
+    refused: impl FnMut(&str) + 'static,
+) -> bool {
+    *LOGIN.write() = Some((server_url.to_string(), login.to_string()));
+    *RECONNECT_ATTEMPT.write() = 0;
+    // a lobby partway through a tournament sends the tally again, and any other has none
+    *TOURNAMENT.write() = None;
+    let Ok(mut client) = Client::connect(server_url, login) else {
+        return false;
+    };
+
+    // something that accepts connections but never answers would otherwise leave us joining forever
+    let refused = Rc::new(RefCell::new(refused));
+    let timeout = web_sys::window().and_then(|window| {
+        let refused = refused.clone();
+        let callback = Closure::once_into_js(move || {
+            *WEBSOCKET.write() = None;
+            refused.borrow_mut()("Server not responding");
+        });
+        window
+            .set_timeout_with_callback_and_timeout_and_arguments_0(
+                callback.unchecked_ref(),
+                LOGIN_TIMEOUT,
+            )
+            .ok()
+    });
+    let cancel_timeout = move || {
+        if let (Some(window), Some(timeout)) = (web_sys::window(), timeout) {
+            window.clear_timeout_with_handle(timeout);
+        }
+    };
+
+    client.set_onmessage(Some(Box::new(move |message| {
+        cancel_timeout();
+        let reason = match message.as_str() {
+            "ok" => {
+                state.set(ClientState::WaitingForPlayers);
+                WEBSOCKET
+                    .write()
+                    .as_mut()
+                    .expect("got message from socket")
+                    .set_onmessage(None);
+                return;
+            }
+            "full" => "No open seats",
+            // spectators are told so in more words
+            "username" | "username taken" => "Username already taken",
+            "join code" => "Incorrect join code",
+            _ => {
+                protocol_error(state);
+                return;
+            }
+        };
+        refused.borrow_mut()(reason);
+        *WEBSOCKET.write() = None;
+    })));
+    client.set_onerror(Some(Box::new(move |err| {
+        cancel_timeout();
+        connection_error(state, err);
+    })));
+    client.set_onclose(Some(Box::new(move |frame| {
+        cancel_timeout();
+        handle_close(state, frame);
+    })));
+    *WEBSOCKET.write() = Some(client);
+    true
+}
+
+/// Join the server again for the next game of the tournament
+fn next_round(mut state: Signal<ClientState>) {
+    let Some((server_url, login)) = LOGIN() else {
+        protocol_error(state);
+        return;
+    };
+    let joining = join_game(state, &server_url, &login, move |reason| {
+        state.set(ClientState::Error(reason.to_string()));
+    });
+    if !joining {
+        state.set(ClientState::Error("Couldn't connect to server".to_string()));
+    }
+}
+
 #[component]
 pub fn WaitingForPlayers(state: Signal<ClientState>) -> Element {
     WEBSOCKET
@@ -176,8 +287,20 @@ pub fn WaitingForPlayers(state: Signal<ClientState>) -> Element {
             dispatch_next_game_state(state, message);
         })));
     rsx! {
-        div { class: "container",
+        div { class: "container scene",
             h1 { "Waiting For Players..." }
+            if let Some(name) = GAME_NAME() {
+                p { class: "lead", "{name}" }
+            }
+            if let Some(seconds) = COUNTDOWN() {
+                p { "Starting in {seconds} seconds unless everyone joins sooner" }
+            }
+            Spectators {}
+            TournamentTally {}
+            // spectators can't change the rules, and the server drops them for trying
+            if !SPECTATING() {
+                LobbyOptions {}
+            }
         }
     }
 }
@@ -191,15 +314,81 @@ pub fn NotYourTurn(state: Signal<ClientState>, game_state: PlayerVisibleGameStat
         .set_onmessage(Some(Box::new(move |message| {
             dispatch_next_game_state(state, message);
         })));
+    let mut to_queue = use_signal(|| None);
+    let mut reserve_to_queue = use_signal(|| false);
+    let hand = game_state.hand.clone();
+    let reserve = game_state.my_reserve();
+
     rsx! {
-        div { class: "container",
+        div {
+            class: "container scene",
+            tabindex: "-1",
+            onkeydown: move |e| {
+                if e.key() == Key::Escape {
+                    to_queue.set(None);
+                    reserve_to_queue.set(false);
+                }
+            },
             div { class: "row",
-                h1 { "{game_state.players[game_state.turn].0}'s turn" }
+                if game_state.submitted.is_some() {
+                    // everyone moves at once, so we're only ever waiting on the rest of the round
+                    h1 { class: "col", "Move picked, waiting for the others" }
+                } else {
+                    h1 { class: "col",
+                        "{game_state.active_player_name().unwrap_or_default()}'s turn"
+                    }
+                }
+                div { class: "col-auto", ReactionBar {} }
+                div { class: "col-auto", Resync {} }
+                div { class: "col-auto", PauseToggle {} }
+                div { class: "col-auto", Coaching {} }
+            }
+            if let Some(QueuedMove { card, location: (row, col) }) = QUEUED_MOVE() {
+                div { class: "row",
+                    p { class: "col text-muted",
+                        "Playing "
+                        span { style: "color:{card.0.colour()}; font-family: DejaVu", "{card}" }
+                        " at row {row + 1}, column {col + 1} when your turn comes "
+                        button {
+                            class: "btn btn-sm btn-outline-secondary",
+                            onclick: move |_| *QUEUED_MOVE.write() = None,
+                            "Cancel"
+                        }
+                    }
+                }
             }
             Game {
                 game_state,
-                on_hand_click: |_| {},
-                on_board_click: |_| {},
+                to_play: *to_queue.read(),
+                reserve_selected: *reserve_to_queue.read(),
+                show_last_move: true,
+                on_hand_click: move |index| {
+                    reserve_to_queue.set(false);
+                    let to_queue = &mut *to_queue.write();
+                    match to_queue {
+                        Some(selected) if *selected == index => {
+                            *to_queue = None;
+                        }
+                        Some(_) | None => {
+                            *to_queue = Some(index);
+                        }
+                    }
+                },
+                on_reserve_click: move |_| {
+                    to_queue.set(None);
+                    let selected = *reserve_to_queue.read();
+                    reserve_to_queue.set(!selected);
+                },
+                on_board_click: move |location| {
+                    let card = if reserve_to_queue.replace(false) {
+                        reserve
+                    } else {
+                        to_queue.take().and_then(|index| hand.0.get(index).copied())
+                    };
+                    if let Some(card) = card {
+                        *QUEUED_MOVE.write() = Some(QueuedMove { card, location });
+                    }
+                },
             }
         }
     }
@@ -215,41 +404,254 @@ pub fn YourTurn(state: Signal<ClientState>, game_state: PlayerVisibleGameState)
             dispatch_next_game_state(state, message);
         })));
     let mut to_play = use_signal(|| None);
-    let mut sent = use_signal(|| false);
+    let mut reserve_selected = use_signal(|| false);
+    let mut sent = use_signal(|| send_queued_move(&game_state));
+    let center = game_state.board.center();
+    let wastes_play = {
+        let game_state = game_state.clone();
+        move |player_move: &PlayerMove| {
+            let card = match player_move.from {
+                MoveSource::Hand => game_state.hand.0.get(player_move.card).copied(),
+                MoveSource::Reserve => game_state.my_reserve(),
+            };
+            card.is_some_and(|card| {
+                takes_nothing_needlessly(
+                    &game_state,
+                    card,
+                    player_move.location,
+                    &player_move.declined,
+                )
+            })
+        }
+    };
+    // when taking is optional, a move that would take something waits here for the player to
+    // pick which directions to take in
+    let mut choosing: Signal<Option<PlayerMove>> = use_signal(|| None);
+    let optional_capture = LOBBY_OPTIONS()
+        .get("optional_capture")
+        .and_then(serde_json::Value::as_bool)
+        == Some(true);
+    let capturing_directions = {
+        let game_state = game_state.clone();
+        move |player_move: &PlayerMove| {
+            let card = match player_move.from {
+                MoveSource::Hand => game_state.hand.0.get(player_move.card).copied(),
+                MoveSource::Reserve => game_state.my_reserve(),
+            };
+            card.map(|card| {
+                DIRECTIONS
+                    .into_iter()
+                    .map(|direction| {
+                        let taken = find_captures_in(
+                            &game_state.board,
+                            card,
+                            player_move.location,
+                            game_state.taking_variant,
+                            game_state.rank_order,
+                            game_state.wrap,
+                            &[direction],
+                        )
+                        .len();
+                        // the played card itself is listed with what it takes
+                        (direction, taken.saturating_sub(1))
+                    })
+                    .filter(|&(_, taken)| taken > 0)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+        }
+    };
+    let mut send_move = move |player_move: &PlayerMove| {
+        WEBSOCKET
+            .write()
+            .as_mut()
+            .expect("state transition guarded")
+            .send(
+                &serde_json::to_string(player_move)
+                    .expect("should always be able to serialize moves"),
+            );
+        *sent.write() = true;
+        *MOVE_REJECTED.write() = None;
+        *MOVE_CONFIRMED.write() = None;
+    };
+    let wastes_chosen_play = wastes_play.clone();
+    let choices = choosing
+        .read()
+        .as_ref()
+        .map(|player_move| {
+            capturing_directions(player_move)
+                .into_iter()
+                .map(|(direction, taken)| {
+                    (direction, taken, !player_move.declined.contains(&direction))
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    // a misclick on a taken or out of reach spot would get us disconnected, so it's never sent
+    let check_move = {
+        let game_state = game_state.clone();
+        move |card, location, from| PlayerMove::new_checked(card, location, from, &game_state)
+    };
+    // a lenient server may turn the move down, letting us try again
+    let waiting = *sent.read() && MOVE_REJECTED().is_none();
+    // only ever marked and offered - assist mode never plays for us
+    let suggestion = (ASSIST() && !waiting)
+        .then(|| game_state.best_move())
+        .flatten();
+    let suggested = suggestion.as_ref().and_then(|suggestion| {
+        let card = match suggestion.from {
+            MoveSource::Hand => game_state.hand.0.get(suggestion.card).copied(),
+            MoveSource::Reserve => game_state.my_reserve(),
+        }?;
+        Some((card, game_state.cards_taken_by(suggestion)))
+    });
+    let selected = if *reserve_selected.read() {
+        game_state.my_reserve()
+    } else {
+        to_play
+            .read()
+            .and_then(|index| game_state.hand.0.get(index).copied())
+    };
 
     rsx! {
-        div { class: "container",
+        div {
+            class: "container scene",
+            // focused by clicking anywhere in the scene, so it hears keys without a text box
+            tabindex: "-1",
+            onkeydown: move |e| {
+                if e.key() == Key::Escape {
+                    to_play.set(None);
+                    reserve_selected.set(false);
+                }
+            },
             div { class: "row",
-                h1 { "Your turn" }
+                h1 { class: "col", "Your turn" }
+                div { class: "col-auto", ReactionBar {} }
+                div { class: "col-auto", Resync {} }
+                div { class: "col-auto", PauseToggle {} }
+                div { class: "col-auto", Coaching {} }
+            }
+            if RECONNECTED() && !waiting {
+                div { class: "row",
+                    div { class: "col alert alert-warning d-flex align-items-center",
+                        strong { class: "fs-4 me-auto", "It's your turn!" }
+                        button {
+                            class: "btn-close",
+                            aria_label: "Dismiss",
+                            onclick: move |_| *RECONNECTED.write() = false,
+                        }
+                    }
+                }
             }
-            if !*sent.read()
-                && game_state.board.0.iter().all(|row| row.iter().all(|card| card.is_none()))
+            if let Some(reason) = MOVE_REJECTED() {
+                div { class: "row",
+                    p { class: "text-danger", "Move rejected: {reason}" }
+                }
+            }
+            if !waiting && game_state.board.is_empty() && !game_state.started
+                && game_state.first_move == FirstMove::Center
             {
                 Game {
                     game_state,
                     on_hand_click: move |index| {
-                        WEBSOCKET
-                            .write()
-                            .as_mut()
-                            .expect("state transition guarded")
-                            .send(
-                                &serde_json::to_string(
-                                        &PlayerMove {
-                                            card: index,
-                                            location: (BOARD_SIZE / 2, BOARD_SIZE / 2),
-                                        },
-                                    )
-                                    .expect("should always be able to serialize moves"),
-                            );
-                        *sent.write() = true;
+                        send_move(&PlayerMove::new(index, center, MoveSource::Hand));
+                    },
+                    on_reserve_click: move |_| {
+                        send_move(&PlayerMove::new(0, center, MoveSource::Reserve));
                     },
                     on_board_click: |_| {},
                 }
-            } else if !*sent.read() {
+            } else if !waiting {
+                if let (Some(suggestion), Some((card, taken))) = (suggestion.clone(), suggested) {
+                    div { class: "row",
+                        p { class: "col text-muted",
+                            "Suggestion: play "
+                            span { style: "color:{card.0.colour()}; font-family: DejaVu", "{card}" }
+                            if suggestion.from == MoveSource::Reserve {
+                                " from your reserve"
+                            }
+                            " on the marked spot, taking {taken} cards "
+                            button {
+                                class: "btn btn-sm btn-outline-success",
+                                onclick: move |_| send_move(&suggestion),
+                                "Play it"
+                            }
+                        }
+                    }
+                }
+                if choosing.read().is_some() {
+                    div { class: "row",
+                        div { class: "col",
+                            span { class: "text-muted me-2", "Take in:" }
+                            for (direction, taken, chosen) in choices {
+                                button {
+                                    class: if chosen {
+                                        "btn btn-sm btn-primary me-1"
+                                    } else {
+                                        "btn btn-sm btn-outline-primary me-1"
+                                    },
+                                    title: "{taken} cards",
+                                    onclick: move |_| {
+                                        if let Some(player_move) = &mut *choosing.write() {
+                                            if chosen {
+                                                player_move.declined.push(direction);
+                                            } else {
+                                                player_move.declined.retain(|&declined| declined != direction);
+                                            }
+                                        }
+                                    },
+                                    "{direction_arrow(direction)}"
+                                }
+                            }
+                            button {
+                                class: "btn btn-sm btn-success me-1",
+                                onclick: move |_| {
+                                    let Some(player_move) = choosing.take() else {
+                                        return;
+                                    };
+                                    if wastes_chosen_play(&player_move) {
+                                        *MOVE_REJECTED.write() = Some(
+                                            "that takes nothing, but one of your cards could take something"
+                                                .to_string(),
+                                        );
+                                        return;
+                                    }
+                                    send_move(&player_move);
+                                },
+                                "Play"
+                            }
+                            button {
+                                class: "btn btn-sm btn-outline-secondary",
+                                onclick: move |_| choosing.set(None),
+                                "Cancel"
+                            }
+                        }
+                    }
+                } else if let Some(card) = selected {
+                    div { class: "row",
+                        p { class: "col text-muted",
+                            "Playing "
+                            span { style: "color:{card.0.colour()}; font-family: DejaVu", "{card}" }
+                            " - pick a spot on the board "
+                            button {
+                                class: "btn btn-sm btn-outline-secondary",
+                                title: "Esc",
+                                onclick: move |_| {
+                                    to_play.set(None);
+                                    reserve_selected.set(false);
+                                },
+                                "Cancel selection"
+                            }
+                        }
+                    }
+                }
                 Game {
                     game_state,
                     to_play: *to_play.read(),
+                    reserve_selected: *reserve_selected.read(),
+                    suggestion,
                     on_hand_click: move |index| {
+                        reserve_selected.set(false);
                         let to_play = &mut *to_play.write();
                         match to_play {
                             Some(selected) if *selected == index => {
@@ -260,21 +662,45 @@ pub fn YourTurn(state: Signal<ClientState>, game_state: PlayerVisibleGameState)
                             }
                         }
                     },
+                    on_reserve_click: move |_| {
+                        to_play.set(None);
+                        let selected = *reserve_selected.read();
+                        reserve_selected.set(!selected);
+                    },
                     on_board_click: move |location| {
-                        if let Some(card) = *to_play.read() {
-                            WEBSOCKET
-                                .write()
-                                .as_mut()
-                                .expect("state transition guarded")
-                                .send(
-                                    &serde_json::to_string(&PlayerMove { card, location })
-                                        .expect("should always be able to serialize moves"),
-                                );
-                            *sent.write() = true;
+                        let player_move = if *reserve_selected.read() {
+                            check_move(0, location, MoveSource::Reserve)
+                        } else {
+                            (*to_play.read())
+                                .and_then(|card| check_move(card, location, MoveSource::Hand))
+                        };
+                        if player_move.as_ref().is_some_and(&wastes_play) {
+                            *MOVE_REJECTED.write() = Some(
+                                "that takes nothing, but one of your cards could take something"
+                                    .to_string(),
+                            );
+                            return;
+                        }
+                        if let Some(player_move) = player_move {
+                            if optional_capture && !capturing_directions(&player_move).is_empty() {
+                                choosing.set(Some(player_move));
+                            } else {
+                                send_move(&player_move);
+                            }
                         }
                     },
                 }
             } else {
+                div { class: "row",
+                    p { class: "text-muted",
+                        span { class: "spinner-border spinner-border-sm me-2", role: "status" }
+                        match MOVE_CONFIRMED() {
+                            Some(0) => "Move played, waiting...".to_string(),
+                            Some(captured) => format!("Move played, taking {captured} cards, waiting..."),
+                            None => "Move sent, waiting...".to_string(),
+                        }
+                    }
+                }
                 Game {
                     game_state,
                     on_hand_click: |_| {},
@@ -286,12 +712,13 @@ pub fn YourTurn(state: Signal<ClientState>, game_state: PlayerVisibleGameState)
 }
 
 #[component]
-pub fn YouLost(game_state: PlayerVisibleGameState) -> Element {
+pub fn YouLost(state: Signal<ClientState>, game_state: PlayerVisibleGameState) -> Element {
     rsx! {
-        div { class: "container",
+        div { class: "container scene",
             div { class: "row",
-                h1 { "You lost ({game_state.players[game_state.turn].0}'s turn)" }
+                h1 { "You lost ({game_state.active_player_name().unwrap_or_default()}'s turn)" }
             }
+            NextRound { state }
             Game {
                 game_state,
                 on_hand_click: |_| {},
@@ -302,16 +729,86 @@ pub fn YouLost(game_state: PlayerVisibleGameState) -> Element {
 }
 
 #[component]
-pub fn YouWin(game_state: PlayerVisibleGameState) -> Element {
+pub fn YouWin(state: Signal<ClientState>, game_state: PlayerVisibleGameState) -> Element {
     rsx! {
-        div { class: "container",
+        div { class: "container scene",
             div { class: "row",
                 h1 { "You won" }
             }
+            NextRound { state }
+            Game {
+                game_state,
+                on_hand_click: |_| {},
+                on_board_click: |_| {},
+            }
+        }
+    }
+}
+
+#[component]
+pub fn Reconnecting(game_state: PlayerVisibleGameState) -> Element {
+    rsx! {
+        div { class: "container scene",
+            div { class: "row",
+                h1 { "Connection lost" }
+            }
+            div { class: "row",
+                p { class: "text-muted",
+                    span { class: "spinner-border spinner-border-sm me-2", role: "status" }
+                    "Reconnecting... (attempt {RECONNECT_ATTEMPT()} of {MAX_RECONNECT_ATTEMPTS})"
+                }
+            }
+            // the last state we had, which can't be played on until we're back
+            div { class: "opacity-50 pe-none",
+                Game {
+                    game_state,
+                    on_hand_click: |_| {},
+                    on_board_click: |_| {},
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn Spectator(state: Signal<ClientState>, game_state: PlayerVisibleGameState) -> Element {
+    if let Some(websocket) = WEBSOCKET.write().as_mut() {
+        websocket.set_onmessage(Some(Box::new(move |message| {
+            dispatch_next_game_state(state, message);
+        })));
+    }
+    // a player has let us see their hand
+    let coaching = game_state.my_index.is_some();
+
+    rsx! {
+        div { class: "container scene",
+            div { class: "row",
+                if let Some(winner) = SPECTATED_WINNER() {
+                    h1 { class: "col",
+                        match winner {
+                            Some(winner) => format!("{winner} won"),
+                            None => "Nobody won".to_string(),
+                        }
+                    }
+                } else if coaching {
+                    h1 { class: "col",
+                        "Coaching {game_state.username} - {game_state.active_player_name().unwrap_or_default()}'s turn"
+                    }
+                    div { class: "col-auto", Resync {} }
+                } else {
+                    h1 { class: "col",
+                        "Watching - {game_state.active_player_name().unwrap_or_default()}'s turn"
+                    }
+                    div { class: "col-auto", Resync {} }
+                }
+            }
+            TournamentTally {}
             Game {
                 game_state,
+                spectating: !coaching,
                 on_hand_click: |_| {},
                 on_board_click: |_| {},
+                show_last_move: true,
             }
         }
     }
@@ -320,7 +817,7 @@ pub fn YouWin(game_state: PlayerVisibleGameState) -> Element {
 #[component]
 pub fn Error(message: String) -> Element {
     rsx! {
-        div { class: "container",
+        div { class: "container scene",
             h1 { "Something Went Wrong" }
             p { "{message}" }
             p {
@@ -331,6 +828,269 @@ pub fn Error(message: String) -> Element {
     }
 }
 
+/// Tournament tally, with a way into the next game if there's more to play
+#[component]
+fn NextRound(state: Signal<ClientState>) -> Element {
+    rsx! {
+        TournamentTally {}
+        if TOURNAMENT().is_some_and(|standings| !standings.is_over()) {
+            p { class: "text-muted", "The next game starts in a few seconds." }
+            button {
+                class: "btn btn-primary mb-3",
+                onclick: move |_| next_round(state),
+                "Play the next game now"
+            }
+        }
+    }
+}
+
+/// Button asking the server to re-send the current game state
+#[component]
+fn Resync() -> Element {
+    rsx! {
+        button {
+            class: "btn btn-outline-secondary",
+            onclick: move |_| {
+                if let Some(websocket) = WEBSOCKET.write().as_mut() {
+                    websocket.send("resync");
+                }
+            },
+            "Refresh"
+        }
+    }
+}
+
+/// Button asking the server to pause or resume the game, which only works for the host
+#[component]
+fn PauseToggle() -> Element {
+    let (label, message) = if PAUSED().is_some() {
+        ("Resume", "resume")
+    } else {
+        ("Pause (host only)", "pause")
+    };
+    rsx! {
+        button {
+            class: "btn btn-outline-secondary",
+            onclick: move |_| {
+                if let Some(websocket) = WEBSOCKET.write().as_mut() {
+                    websocket.send(message);
+                }
+            },
+            "{label}"
+        }
+        if let Some(reason) = PAUSE_REJECTED() {
+            p { class: "small text-danger mb-0", "{reason}" }
+        }
+    }
+}
+
+/// Who can see our hand, with ways to let someone else see it or stop them
+#[component]
+fn Coaching() -> Element {
+    let mut coach = use_signal(String::new);
+
+    rsx! {
+        details {
+            summary { "Coaching" }
+            div { class: "input-group input-group-sm my-1",
+                input {
+                    r#type: "text",
+                    class: "form-control",
+                    placeholder: "Spectator's name",
+                    value: coach(),
+                    oninput: move |e| coach.set(e.value()),
+                }
+                button {
+                    class: "btn btn-outline-secondary",
+                    onclick: move |_| {
+                        if let Some(websocket) = WEBSOCKET.write().as_mut() {
+                            websocket.send(&format!("coach\n{}", coach.read()));
+                        }
+                        coach.set(String::new());
+                    },
+                    "Show my hand"
+                }
+            }
+            {
+                COACHES()
+                    .into_iter()
+                    .map(|name| {
+                        let message = format!("uncoach\n{name}");
+                        rsx! {
+                            div { key: "{name}",
+                                "{name} can see your hand "
+                                button {
+                                    class: "btn btn-sm btn-outline-danger",
+                                    onclick: move |_| {
+                                        if let Some(websocket) = WEBSOCKET.write().as_mut() {
+                                            websocket.send(&message);
+                                        }
+                                    },
+                                    "Stop"
+                                }
+                            }
+                        }
+                    })
+            }
+        }
+    }
+}
+
+/// Buttons sending each of the allowed reactions
+#[component]
+fn ReactionBar() -> Element {
+    rsx! {
+        div { class: "btn-group",
+            for reaction in REACTIONS {
+                button {
+                    class: "btn btn-outline-secondary",
+                    onclick: move |_| {
+                        if let Some(websocket) = WEBSOCKET.write().as_mut() {
+                            websocket.send(&format!("react\n{reaction}"));
+                        }
+                    },
+                    "{reaction}"
+                }
+            }
+        }
+    }
+}
+
+/// The rules for the next game, which the host can change
+#[component]
+fn LobbyOptions() -> Element {
+    let options = LOBBY_OPTIONS();
+    let current = serde_json::to_string_pretty(&options).unwrap_or_default();
+    // what's in the editor, if it's been changed from the current options
+    let mut edited: Signal<Option<String>> = use_signal(|| None);
+
+    rsx! {
+        h2 { "Rules" }
+        table { class: "table table-sm w-auto",
+            for (name , value) in options.iter() {
+                tr {
+                    td { "{name}" }
+                    td { "{value}" }
+                }
+            }
+        }
+        details {
+            summary { "Change rules (host only)" }
+            textarea {
+                class: "form-control font-monospace mb-2",
+                rows: "10",
+                value: edited().unwrap_or_else(|| current.clone()),
+                oninput: move |e| edited.set(Some(e.value())),
+            }
+            if let Some(reason) = OPTIONS_REJECTED() {
+                p { class: "text-danger", "{reason}" }
+            }
+            button {
+                class: "btn btn-outline-primary",
+                onclick: move |_| {
+                    if let Some(options) = edited()
+                        && let Some(websocket) = WEBSOCKET.write().as_mut()
+                    {
+                        websocket.send(&format!("options\n{options}"));
+                    }
+                },
+                "Change rules"
+            }
+        }
+    }
+}
+
+/// Play the move queued during someone else's turn, if there is one
+///
+/// Moves that are no longer legal are dropped rather than sent, since the server would disconnect
+/// us for them. Returns whether a move was sent.
+fn send_queued_move(game_state: &PlayerVisibleGameState) -> bool {
+    let Some(QueuedMove { card, location }) = QUEUED_MOVE.write().take() else {
+        return false;
+    };
+    let (index, from) = match game_state.hand.0.iter().position(|&held| held == card) {
+        Some(index) => (index, MoveSource::Hand),
+        None if game_state.my_reserve() == Some(card) => (0, MoveSource::Reserve),
+        None => return false,
+    };
+    let Some(player_move) = PlayerMove::new_checked(index, location, from, game_state) else {
+        return false;
+    };
+    if takes_nothing_needlessly(game_state, card, location, &[]) {
+        return false;
+    }
+
+    WEBSOCKET
+        .write()
+        .as_mut()
+        .expect("state transition guarded")
+        .send(
+            &serde_json::to_string(&player_move).expect("should always be able to serialize moves"),
+        );
+    *MOVE_REJECTED.write() = None;
+    *MOVE_CONFIRMED.write() = None;
+    true
+}
+
+/// Whether the server would turn a play down for taking nothing when something could be taken
+fn takes_nothing_needlessly(
+    game_state: &PlayerVisibleGameState,
+    card: Card,
+    location: (usize, usize),
+    declined: &[(i32, i32)],
+) -> bool {
+    let required = LOBBY_OPTIONS()
+        .get("require_capture")
+        .and_then(serde_json::Value::as_bool)
+        == Some(true);
+    let held = game_state
+        .hand
+        .0
+        .iter()
+        .copied()
+        .chain(game_state.my_reserve())
+        .collect::<Vec<_>>();
+    let directions = DIRECTIONS
+        .into_iter()
+        .filter(|direction| !declined.contains(direction))
+        .collect::<Vec<_>>();
+    required
+        && find_captures_in(
+            &game_state.board,
+            card,
+            location,
+            game_state.taking_variant,
+            game_state.rank_order,
+            game_state.wrap,
+            &directions,
+        )
+        .is_empty()
+        && can_take_any(
+            &game_state.board,
+            &held,
+            game_state.taking_variant,
+            game_state.rank_order,
+            game_state.wrap,
+            game_state.started,
+            game_state.first_move,
+        )
+}
+
+/// An arrow pointing the way of one of the eight directions
+fn direction_arrow((row, col): (i32, i32)) -> &'static str {
+    match (row.signum(), col.signum()) {
+        (-1, -1) => "↖",
+        (-1, 0) => "↑",
+        (-1, 1) => "↗",
+        (0, -1) => "←",
+        (0, 1) => "→",
+        (1, -1) => "↙",
+        (1, 0) => "↓",
+        (1, 1) => "↘",
+        _ => "·",
+    }
+}
+
 fn protocol_error(mut state: Signal<ClientState>) {
     state.set(ClientState::Error(
         "Connection lost: protocol error".to_string(),
@@ -338,40 +1098,421 @@ fn protocol_error(mut state: Signal<ClientState>) {
     *WEBSOCKET.write() = None;
 }
 
-fn dispatch_next_game_state(mut state: Signal<ClientState>, message: String) {
-    let Ok(game_state) = serde_json::from_str::<PlayerVisibleGameState>(&message) else {
+/// The server closes the connection when the game ends, or when something goes wrong
+fn handle_close(mut state: Signal<ClientState>, frame: CloseFrame) {
+    match frame.code {
+        4000 => {
+            // a game ends with no winner when everyone forfeits
+            let winner = match frame.reason.strip_prefix("player won\n") {
+                Some(winner) => Some(winner),
+                None if frame.reason == "no winner" => None,
+                None => {
+                    protocol_error(state);
+                    return;
+                }
+            };
+            let game_state = match *state.read() {
+                ClientState::NotYourTurn(ref game_state)
+                | ClientState::YourTurn(ref game_state)
+                | ClientState::YouLost(ref game_state)
+                | ClientState::YouWin(ref game_state)
+                | ClientState::Reconnecting(ref game_state) => game_state.clone(),
+                ClientState::Spectating(_) => {
+                    *SPECTATED_WINNER.write() = Some(winner.map(str::to_string));
+                    *WEBSOCKET.write() = None;
+                    return;
+                }
+                ClientState::Error(_) | ClientState::Login | ClientState::WaitingForPlayers => {
+                    protocol_error(state);
+                    return;
+                }
+            };
+            if winner == Some(game_state.username.as_str()) {
+                state.set(ClientState::YouWin(game_state));
+            } else {
+                state.set(ClientState::YouLost(game_state));
+            }
+            // there's no turn left to play it on
+            *QUEUED_MOVE.write() = None;
+            *WEBSOCKET.write() = None;
+            if TOURNAMENT().is_some_and(|standings| !standings.is_over()) {
+                join_next_game_soon(state);
+            }
+        }
+        4002 => protocol_error(state),
+        4003 => {
+            state.set(ClientState::Error(
+                "Too many connections from your address".to_string(),
+            ));
+            *WEBSOCKET.write() = None;
+        }
+        4004 => {
+            state.set(ClientState::Error(
+                "Not enough players joined in time, so the game was called off".to_string(),
+            ));
+            *WEBSOCKET.write() = None;
+        }
+        code => {
+            // a game we're still in is worth getting back into
+            if let Some(game_state) = game_in_progress(state) {
+                reconnect(state, game_state);
+                return;
+            }
+            // keep any more specific error we already have
+            if !matches!(*state.read(), ClientState::Error(_)) {
+                state.set(ClientState::Error(format!(
+                    "Connection lost: closed with code {code}"
+                )));
+            }
+            *WEBSOCKET.write() = None;
+        }
+    }
+}
+
+/// Errors are followed by the connection closing, which is where a game in progress reconnects
+fn connection_error(mut state: Signal<ClientState>, err: ConnectionError) {
+    if game_in_progress(state).is_none() {
+        state.set(ClientState::Error(format!("Connection lost\n{err}")));
+    }
+}
+
+/// The state of the game we're playing in or watching, if we're still in it
+fn game_in_progress(state: Signal<ClientState>) -> Option<PlayerVisibleGameState> {
+    match *state.read() {
+        ClientState::NotYourTurn(ref game_state)
+        | ClientState::YourTurn(ref game_state)
+        | ClientState::Reconnecting(ref game_state)
+        | ClientState::Spectating(ref game_state) => Some(game_state.clone()),
+        ClientState::Error(_)
+        | ClientState::Login
+        | ClientState::WaitingForPlayers
+        | ClientState::YouLost(_)
+        | ClientState::YouWin(_) => None,
+    }
+}
+
+/// Join the tournament's next game after a while, unless the player already has
+fn join_next_game_soon(state: Signal<ClientState>) {
+    let callback = Closure::once_into_js(move || {
+        if matches!(
+            *state.read(),
+            ClientState::YouWin(_) | ClientState::YouLost(_)
+        ) {
+            next_round(state);
+        }
+    });
+    if let Some(window) = web_sys::window() {
+        let _ = window.set_timeout_with_callback_and_timeout_and_arguments_0(
+            callback.unchecked_ref(),
+            NEXT_GAME_DELAY,
+        );
+    }
+}
+
+/// Try getting back into the game after a while, unless we've already tried too often
+fn reconnect(mut state: Signal<ClientState>, game_state: PlayerVisibleGameState) {
+    *WEBSOCKET.write() = None;
+    let attempt = RECONNECT_ATTEMPT() + 1;
+    if attempt > MAX_RECONNECT_ATTEMPTS {
+        state.set(ClientState::Error(
+            "Connection lost, and couldn't reconnect".to_string(),
+        ));
+        return;
+    }
+    *RECONNECT_ATTEMPT.write() = attempt;
+    state.set(ClientState::Reconnecting(game_state));
+
+    // back off a little more each time, in case the server is struggling
+    let callback = Closure::once_into_js(move || connect_again(state));
+    if let Some(window) = web_sys::window() {
+        let _ = window.set_timeout_with_callback_and_timeout_and_arguments_0(
+            callback.unchecked_ref(),
+            RECONNECT_DELAY * attempt as i32,
+        );
+    }
+}
+
+/// Ask the browser to tell the player their turn has come, if they allowed it and might miss it
+/// otherwise
+fn notify_turn() {
+    if !TURN_NOTIFICATIONS() {
+        return;
+    }
+    let away = if RECONNECTED() {
+        "true"
+    } else {
+        "document.hidden"
+    };
+    document::eval(&format!(
+        "if (window.Notification && Notification.permission === 'granted' && {away}) {{ new Notification('Grid Online', {{ body: \"It's your turn!\" }}); }}"
+    ));
+}
+
+/// Rejoin with the details we first joined with, picking the game back up once the server agrees
+fn connect_again(mut state: Signal<ClientState>) {
+    // the player may have given up and moved on in the meantime
+    let Some(game_state) = game_in_progress(state) else {
+        return;
+    };
+    let Some((server_url, login)) = LOGIN() else {
         protocol_error(state);
         return;
     };
+    let Ok(mut client) = Client::connect(&server_url, &login) else {
+        reconnect(state, game_state);
+        return;
+    };
+    client.set_onmessage(Some(Box::new(move |message| match message.as_str() {
+        "ok" => {
+            // the server follows up with the current state, which puts us back in the game
+            *RECONNECT_ATTEMPT.write() = 0;
+            *RECONNECTED.write() = true;
+            WEBSOCKET
+                .write()
+                .as_mut()
+                .expect("got message from socket")
+                .set_onmessage(Some(Box::new(move |message| {
+                    dispatch_next_game_state(state, message);
+                })));
+        }
+        "username" | "username taken" => {
+            // the server hasn't noticed our old connection is gone yet
+            if let Some(game_state) = game_in_progress(state) {
+                reconnect(state, game_state);
+            }
+        }
+        "full" | "join code" => {
+            state.set(ClientState::Error(
+                "Connection lost, and the game wouldn't let us back in".to_string(),
+            ));
+            *WEBSOCKET.write() = None;
+        }
+        _ => protocol_error(state),
+    })));
+    client.set_onerror(Some(Box::new(move |err| connection_error(state, err))));
+    client.set_onclose(Some(Box::new(move |frame| handle_close(state, frame))));
+    *WEBSOCKET.write() = Some(client);
+}
+
+fn dispatch_next_game_state(mut state: Signal<ClientState>, message: String) {
+    if let Some(count) = message.strip_prefix("spectators\n") {
+        // informational only - doesn't change what scene we're in
+        let Ok(count) = count.parse() else {
+            protocol_error(state);
+            return;
+        };
+        *SPECTATORS.write() = count;
+        return;
+    }
+    if let Some(standings) = message.strip_prefix("tournament\n") {
+        let Ok(standings) = serde_json::from_str(standings) else {
+            protocol_error(state);
+            return;
+        };
+        *TOURNAMENT.write() = Some(standings);
+        return;
+    }
+    if let Some(players) = message.strip_prefix("game-started\n") {
+        // the seating comes again in every state, so there's nothing to keep from this
+        if serde_json::from_str::<Vec<String>>(players).is_err() {
+            protocol_error(state);
+            return;
+        }
+        // a commitment to this game's deal, if any, comes next
+        *DEAL_COMMITMENT.write() = None;
+        *DEAL_REVEAL.write() = None;
+        *SPECTATED_WINNER.write() = None;
+        *COUNTDOWN.write() = None;
+        *PAUSED.write() = None;
+        *PAUSE_REJECTED.write() = None;
+        *QUEUED_MOVE.write() = None;
+        COACHES.write().clear();
+        return;
+    }
+    if let Some(host) = message.strip_prefix("paused\n") {
+        *PAUSED.write() = Some(host.to_string());
+        *PAUSE_REJECTED.write() = None;
+        return;
+    }
+    if message == "resumed" {
+        *PAUSED.write() = None;
+        *PAUSE_REJECTED.write() = None;
+        return;
+    }
+    if let Some(reason) = message.strip_prefix("pause rejected\n") {
+        *PAUSE_REJECTED.write() = Some(reason.to_string());
+        return;
+    }
+    if let Some(coaches) = message.strip_prefix("coaches\n") {
+        let Ok(coaches) = serde_json::from_str(coaches) else {
+            protocol_error(state);
+            return;
+        };
+        *COACHES.write() = coaches;
+        return;
+    }
+    if message.starts_with("replay\n") {
+        // spectators get the whole game so far on joining, but only the latest state is shown
+        return;
+    }
+    if let Some(recent) = message.strip_prefix("recent\n") {
+        let Ok(recent) = serde_json::from_str(recent) else {
+            protocol_error(state);
+            return;
+        };
+        *AWAY_MOVES.write() = recent;
+        return;
+    }
+    if let Some(hash) = message.strip_prefix("deal-commitment\n") {
+        *DEAL_COMMITMENT.write() = Some(hash.to_string());
+        *DEAL_REVEAL.write() = None;
+        return;
+    }
+    if let Some(reveal) = message.strip_prefix("deal-reveal\n") {
+        *DEAL_REVEAL.write() = Some(reveal.to_string());
+        return;
+    }
+    if let Some(name) = message.strip_prefix("name\n") {
+        *GAME_NAME.write() = Some(name.to_string());
+        return;
+    }
+    if let Some(reaction) = message.strip_prefix("react\n") {
+        let Some((username, reaction)) = reaction.split_once('\n') else {
+            protocol_error(state);
+            return;
+        };
+        let mut recent = RECENT_REACTIONS.write();
+        let id = recent.last().map_or(0, |reaction| reaction.id + 1);
+        recent.push(Reaction {
+            id,
+            username: username.to_string(),
+            reaction: reaction.to_string(),
+        });
+        if recent.len() > MAX_SHOWN_REACTIONS {
+            recent.remove(0);
+        }
+        return;
+    }
+    if let Some(seconds) = message.strip_prefix("countdown\n") {
+        let Ok(seconds) = seconds.parse() else {
+            protocol_error(state);
+            return;
+        };
+        *COUNTDOWN.write() = Some(seconds);
+        return;
+    }
+    if message == "countdown cancelled" {
+        *COUNTDOWN.write() = None;
+        return;
+    }
+    if let Some(reason) = message
+        .strip_prefix("rejected\n")
+        .or_else(|| message.strip_prefix("move-rejected\n"))
+    {
+        *MOVE_REJECTED.write() = Some(reason.to_string());
+        return;
+    }
+    if let Some(captured) = message.strip_prefix("move-ok\n") {
+        let Ok(captured) = captured.parse() else {
+            protocol_error(state);
+            return;
+        };
+        *MOVE_CONFIRMED.write() = Some(captured);
+        return;
+    }
+    if let Some(options) = message.strip_prefix("options\n") {
+        let Ok(options) = serde_json::from_str(options) else {
+            protocol_error(state);
+            return;
+        };
+        *LOBBY_OPTIONS.write() = options;
+        *OPTIONS_REJECTED.write() = None;
+        return;
+    }
+    if let Some(reason) = message.strip_prefix("options rejected\n") {
+        *OPTIONS_REJECTED.write() = Some(reason.to_string());
+        return;
+    }
+
+    let game_state = if let Some(delta) = message.strip_prefix("delta\n") {
+        // deltas apply to the game state we already have
+        let Ok(delta) = serde_json::from_str::<GameStateDelta>(delta) else {
+            protocol_error(state);
+            return;
+        };
+        let mut game_state = match *state.read() {
+            ClientState::NotYourTurn(ref game_state)
+            | ClientState::YourTurn(ref game_state)
+            | ClientState::YouLost(ref game_state)
+            | ClientState::YouWin(ref game_state)
+            | ClientState::Reconnecting(ref game_state)
+            | ClientState::Spectating(ref game_state) => game_state.clone(),
+            ClientState::Error(_) | ClientState::Login | ClientState::WaitingForPlayers => {
+                protocol_error(state);
+                return;
+            }
+        };
+        if !game_state.apply_delta(&delta) {
+            protocol_error(state);
+            return;
+        }
+        if !game_state.board_matches_hash() {
+            // our board has drifted from the server's, so start over from a full state
+            if let Some(websocket) = WEBSOCKET.write().as_mut() {
+                websocket.send("resync");
+            }
+            return;
+        }
+        game_state
+    } else {
+        let Ok(game_state) = serde_json::from_str::<PlayerVisibleGameState>(&message) else {
+            protocol_error(state);
+            return;
+        };
+        game_state
+    };
 
-    let Some((active_player, _)) = game_state.players.get(game_state.turn) else {
+    if game_state.active_player_name().is_none() {
         protocol_error(state);
         return;
+    }
+    let count_changes = match *state.read() {
+        ClientState::NotYourTurn(ref previous)
+        | ClientState::YourTurn(ref previous)
+        | ClientState::YouLost(ref previous)
+        | ClientState::YouWin(ref previous)
+        | ClientState::Reconnecting(ref previous)
+        | ClientState::Spectating(ref previous) => game_state.diff(previous).counts,
+        ClientState::Error(_) | ClientState::Login | ClientState::WaitingForPlayers => Vec::new(),
     };
+    let change_id = COUNT_CHANGES.read().0 + 1;
+    *COUNT_CHANGES.write() = (change_id, count_changes);
+    *MOVE_REJECTED.write() = None;
+    *MOVE_CONFIRMED.write() = None;
 
     WEBSOCKET
         .write()
         .as_mut()
         .expect("state transition guarded")
         .set_onmessage(None);
-    if *active_player == game_state.username {
-        if game_state
-            .players
-            .iter()
-            .all(|(player, cards)| game_state.username == *player || *cards == 0)
-        {
-            // if it's your turn and no-one else has cards, you win instead
+    if SPECTATING() {
+        state.set(ClientState::Spectating(game_state));
+    } else if let Some(winner) = game_state.winner() {
+        // the server decides the same way, so this agrees with the close message to come
+        if winner == game_state.username {
             state.set(ClientState::YouWin(game_state));
         } else {
-            state.set(ClientState::YourTurn(game_state));
+            state.set(ClientState::YouLost(game_state));
+        }
+    } else if game_state.is_my_turn() {
+        if !matches!(*state.read(), ClientState::YourTurn(_)) {
+            notify_turn();
         }
+        state.set(ClientState::YourTurn(game_state));
     } else {
         // cases where you aren't the active player
-        if game_state
-            .players
-            .iter()
-            .any(|(player, cards)| game_state.username == *player && *cards == 0)
-        {
+        *RECONNECTED.write() = false;
+        if game_state.my_card_count() == Some(0) {
             // if it's not your turn and you don't have cards, you lost
             state.set(ClientState::YouLost(game_state));
         } else {
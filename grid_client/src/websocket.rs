@@ -0,0 +1,378 @@
+// Copyright 2025 Justin Hu
+//
+// This file is part of Grid Online.
+//
+// Grid Online is free software: you can redistribute it and/or modify it under
+// the terms of the GNU Affero General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// Grid Online is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU Affero General Public License
+// for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with Grid Online. If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Connection to the game server
+
+use std::{cell::RefCell, collections::VecDeque, fmt::Display, rc::Rc};
+
+use grid_common::PROTOCOL_WITH_DELTAS;
+use wasm_bindgen::{JsCast, closure::Closure};
+use web_sys::{CloseEvent, Event, MessageEvent, WebSocket};
+
+type Handler<T> = Box<dyn FnMut(T)>;
+
+/// Why the connection closed, as sent by the server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloseFrame {
+    pub code: u16,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionError(pub String);
+impl Display for ConnectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// The server URL as typed, made into one a WebSocket can connect to
+///
+/// A URL with no scheme is taken to be `ws://`. It may have a port and a path.
+pub fn normalize_server_url(input: &str) -> Result<String, ConnectionError> {
+    let error = |message: &str| Err(ConnectionError(message.to_string()));
+
+    let input = input.trim();
+    if input.is_empty() {
+        return error("Enter the server's URL");
+    }
+    if input.contains(char::is_whitespace) {
+        return error("URL can't contain spaces");
+    }
+    let url = match input.split_once("://") {
+        Some((scheme, _))
+            if scheme.eq_ignore_ascii_case("ws") || scheme.eq_ignore_ascii_case("wss") =>
+        {
+            input.to_string()
+        }
+        Some(_) => return error("URL must start with ws:// or wss://"),
+        None => format!("ws://{input}"),
+    };
+
+    let (_, rest) = url.split_once("://").expect("scheme was checked or added");
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    // IPv6 addresses are bracketed, since they have colons of their own
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(bracketed) => match bracketed.split_once(']') {
+            Some((host, after)) => match after.strip_prefix(':') {
+                Some(port) => (host, Some(port)),
+                None if after.is_empty() => (host, None),
+                None => return error("URL's address is malformed"),
+            },
+            None => return error("URL's address is malformed"),
+        },
+        None => match authority.rsplit_once(':') {
+            Some((host, _)) if host.contains(':') => {
+                return error("URL's IPv6 address must be in brackets");
+            }
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    if host.is_empty() {
+        return error("URL has no server name or address");
+    }
+    if port.is_some_and(|port| !port.parse::<u16>().is_ok_and(|port| port != 0)) {
+        return error("URL's port must be a number from 1 to 65535");
+    }
+
+    Ok(url)
+}
+
+/// A connection to a Grid Online server
+///
+/// Messages that arrive while there's no message handler are held until one is set, so scenes
+/// can hand the connection over to the next scene without missing anything. Handlers may replace
+/// or remove themselves while running.
+pub trait Connection: Sized {
+    /// Connect to the server, sending the login message once connected
+    fn connect(url: &str, login: &str) -> Result<Self, ConnectionError>;
+    fn send(&mut self, message: &str);
+    fn set_onmessage(&mut self, handler: Option<Handler<String>>);
+    fn set_onerror(&mut self, handler: Option<Handler<ConnectionError>>);
+    fn set_onclose(&mut self, handler: Option<Handler<CloseFrame>>);
+}
+
+/// Connection using the browser's WebSocket
+pub struct WebSocketClient {
+    socket: WebSocket,
+    shared: Rc<RefCell<Shared>>,
+    _onopen: Closure<dyn FnMut(Event)>,
+    _onmessage: Closure<dyn FnMut(MessageEvent)>,
+    _onerror: Closure<dyn FnMut(Event)>,
+    _onclose: Closure<dyn FnMut(CloseEvent)>,
+}
+
+/// State shared between the client and the socket's callbacks
+#[derive(Default)]
+struct Shared {
+    /// Messages sent before the socket opened
+    outgoing: Vec<String>,
+    /// Messages received while there was no message handler
+    incoming: VecDeque<String>,
+    onmessage: Option<Handler<String>>,
+    onerror: Option<Handler<ConnectionError>>,
+    onclose: Option<Handler<CloseFrame>>,
+    /// Bumped whenever a handler is set, so a running handler isn't put back over its replacement
+    generation: u64,
+    /// Whether queued messages are being handed out
+    dispatching: bool,
+}
+
+impl Connection for WebSocketClient {
+    fn connect(url: &str, login: &str) -> Result<Self, ConnectionError> {
+        // the server picks this, so it knows we can apply deltas
+        let socket = WebSocket::new_with_str(url, PROTOCOL_WITH_DELTAS)
+            .map_err(|err| ConnectionError(format!("{err:?}")))?;
+        let shared = Rc::new(RefCell::new(Shared {
+            outgoing: vec![login.to_string()],
+            ..Shared::default()
+        }));
+
+        let onopen = Closure::<dyn FnMut(Event)>::new({
+            let socket = socket.clone();
+            let shared = shared.clone();
+            move |_| {
+                for message in shared.borrow_mut().outgoing.drain(..) {
+                    let _ = socket.send_with_str(&message);
+                }
+            }
+        });
+        let onmessage = Closure::<dyn FnMut(MessageEvent)>::new({
+            let shared = shared.clone();
+            move |event: MessageEvent| {
+                if let Some(message) = event.data().as_string() {
+                    shared.borrow_mut().incoming.push_back(message);
+                    dispatch_messages(&shared);
+                }
+            }
+        });
+        let onerror = Closure::<dyn FnMut(Event)>::new({
+            let shared = shared.clone();
+            move |event: Event| {
+                call_handler(
+                    &shared,
+                    |shared| &mut shared.onerror,
+                    ConnectionError(format!("websocket error: {}", event.type_())),
+                );
+            }
+        });
+        let onclose = Closure::<dyn FnMut(CloseEvent)>::new({
+            let shared = shared.clone();
+            move |event: CloseEvent| {
+                call_handler(
+                    &shared,
+                    |shared| &mut shared.onclose,
+                    CloseFrame {
+                        code: event.code(),
+                        reason: event.reason(),
+                    },
+                );
+            }
+        });
+        socket.set_onopen(Some(onopen.as_ref().unchecked_ref()));
+        socket.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+        socket.set_onerror(Some(onerror.as_ref().unchecked_ref()));
+        socket.set_onclose(Some(onclose.as_ref().unchecked_ref()));
+
+        Ok(Self {
+            socket,
+            shared,
+            _onopen: onopen,
+            _onmessage: onmessage,
+            _onerror: onerror,
+            _onclose: onclose,
+        })
+    }
+
+    fn send(&mut self, message: &str) {
+        if self.socket.ready_state() == WebSocket::CONNECTING {
+            self.shared.borrow_mut().outgoing.push(message.to_string());
+        } else {
+            let _ = self.socket.send_with_str(message);
+        }
+    }
+
+    fn set_onmessage(&mut self, handler: Option<Handler<String>>) {
+        {
+            let mut shared = self.shared.borrow_mut();
+            shared.onmessage = handler;
+            shared.generation += 1;
+        }
+        dispatch_messages(&self.shared);
+    }
+
+    fn set_onerror(&mut self, handler: Option<Handler<ConnectionError>>) {
+        let mut shared = self.shared.borrow_mut();
+        shared.onerror = handler;
+        shared.generation += 1;
+    }
+
+    fn set_onclose(&mut self, handler: Option<Handler<CloseFrame>>) {
+        let mut shared = self.shared.borrow_mut();
+        shared.onclose = handler;
+        shared.generation += 1;
+    }
+}
+
+impl Drop for WebSocketClient {
+    fn drop(&mut self) {
+        // the callbacks are about to be freed, so the socket mustn't call them
+        self.socket.set_onopen(None);
+        self.socket.set_onmessage(None);
+        self.socket.set_onerror(None);
+        self.socket.set_onclose(None);
+        let _ = self.socket.close();
+
+        // and a handler that dropped us shouldn't be put back or given more messages
+        let mut shared = self.shared.borrow_mut();
+        shared.onmessage = None;
+        shared.onerror = None;
+        shared.onclose = None;
+        shared.incoming.clear();
+        shared.generation += 1;
+    }
+}
+
+/// Hand queued messages to the message handler, for as long as there is one
+fn dispatch_messages(shared: &RefCell<Shared>) {
+    {
+        let mut shared = shared.borrow_mut();
+        if shared.dispatching {
+            // the running handler set a new handler, which the loop below will pick up
+            return;
+        }
+        shared.dispatching = true;
+    }
+
+    loop {
+        let (mut handler, message, generation) = {
+            let mut shared = shared.borrow_mut();
+            if shared.onmessage.is_none() || shared.incoming.is_empty() {
+                shared.dispatching = false;
+                return;
+            }
+            (
+                shared.onmessage.take().expect("checked above"),
+                shared.incoming.pop_front().expect("checked above"),
+                shared.generation,
+            )
+        };
+        handler(message);
+        let mut shared = shared.borrow_mut();
+        if shared.generation == generation {
+            shared.onmessage = Some(handler);
+        }
+    }
+}
+
+/// Run a handler without holding the borrow, putting it back unless it was replaced
+fn call_handler<T>(
+    shared: &RefCell<Shared>,
+    slot: fn(&mut Shared) -> &mut Option<Handler<T>>,
+    arg: T,
+) {
+    let (mut handler, generation) = {
+        let mut shared = shared.borrow_mut();
+        let Some(handler) = slot(&mut shared).take() else {
+            return;
+        };
+        (handler, shared.generation)
+    };
+    handler(arg);
+    let mut shared = shared.borrow_mut();
+    if shared.generation == generation {
+        *slot(&mut shared) = Some(handler);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejected(input: &str) -> bool {
+        normalize_server_url(input).is_err()
+    }
+
+    #[test]
+    fn test_normalize_server_url_no_scheme() {
+        assert_eq!(
+            normalize_server_url("localhost:8080"),
+            Ok("ws://localhost:8080".to_string())
+        );
+        assert_eq!(
+            normalize_server_url("  grid.example.com  "),
+            Ok("ws://grid.example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn test_normalize_server_url_secure_with_port_and_path() {
+        assert_eq!(
+            normalize_server_url("wss://grid.example.com:8443/play?room=1"),
+            Ok("wss://grid.example.com:8443/play?room=1".to_string())
+        );
+        assert_eq!(
+            normalize_server_url("WSS://grid.example.com/"),
+            Ok("WSS://grid.example.com/".to_string())
+        );
+    }
+
+    #[test]
+    fn test_normalize_server_url_ipv6() {
+        assert_eq!(
+            normalize_server_url("[::1]:8080"),
+            Ok("ws://[::1]:8080".to_string())
+        );
+        assert_eq!(
+            normalize_server_url("ws://[fe80::1]/"),
+            Ok("ws://[fe80::1]/".to_string())
+        );
+        assert!(rejected("[::1"));
+        assert!(rejected("[::1]8080"));
+        // without brackets, there's no telling the address from the port
+        assert!(rejected("::1"));
+        assert!(rejected("fe80::1:8080"));
+        assert!(rejected("ws://::1/"));
+    }
+
+    #[test]
+    fn test_normalize_server_url_bad_port() {
+        assert!(rejected("localhost:"));
+        assert!(rejected("localhost:0"));
+        assert!(rejected("localhost:65536"));
+        assert!(rejected("localhost:http"));
+        assert!(rejected("[::1]:-1"));
+    }
+
+    #[test]
+    fn test_normalize_server_url_other_scheme() {
+        assert!(rejected("http://localhost:8080"));
+        assert!(rejected("https://grid.example.com"));
+    }
+
+    #[test]
+    fn test_normalize_server_url_missing() {
+        assert!(rejected(""));
+        assert!(rejected("   "));
+        assert!(rejected("ws://"));
+        assert!(rejected("ws://:8080"));
+        assert!(rejected("grid example.com"));
+    }
+}
@@ -18,10 +18,14 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
 //! Common structure definitions for Grid Online
+//!
+//! Peers on different versions should still understand each other. Unknown fields are ignored,
+//! and every field added after the first release is `#[serde(default)]`, so an older peer can
+//! leave it out. Changes an older peer can't live with get a new [`PROTOCOL`] name instead.
 
 #![warn(missing_docs)]
 
-use std::fmt::Display;
+use std::{fmt::Display, ops::Range};
 
 use serde::{Deserialize, Serialize};
 
@@ -29,6 +33,33 @@ use serde::{Deserialize, Serialize};
 pub const BOARD_SIZE: usize = 11;
 /// Hand size
 pub const HAND_SIZE: usize = 5;
+/// WebSocket subprotocol for clients that only take whole states
+pub const PROTOCOL: &str = "grid-online";
+/// WebSocket subprotocol for clients that can also apply deltas to the last state they got
+pub const PROTOCOL_WITH_DELTAS: &str = "grid-online.deltas";
+/// Reactions players may send each other
+pub const REACTIONS: [&str; 6] = ["👍", "👏", "😂", "😮", "😢", "🤔"];
+/// Width of a card on a drawn board, in SVG units
+pub const CARD_WIDTH: usize = 60;
+/// Height of a card on a drawn board, in SVG units
+pub const CARD_HEIGHT: usize = 84;
+/// Space around each card on a drawn board, in SVG units
+pub const CARD_GAP: usize = 6;
+
+/// Width and height, in SVG units, of a drawn board showing some rows and columns of cells
+pub fn drawn_size(rows: &Range<usize>, cols: &Range<usize>) -> (usize, usize) {
+    (
+        cols.len() * (CARD_WIDTH + CARD_GAP) + CARD_GAP,
+        rows.len() * (CARD_HEIGHT + CARD_GAP) + CARD_GAP,
+    )
+}
+
+/// 64-bit FNV-1a hash, which comes out the same on every platform and build
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
 
 /// Game state visible to a player
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -40,15 +71,562 @@ pub struct PlayerVisibleGameState {
     pub username: String,
     pub players: Vec<(String, u32)>,
     pub turn: usize,
+    /// This player's own seat, indexing into `players`, or None for spectators
+    ///
+    /// Seats don't change during a game, so deltas leave this alone
+    #[serde(default)]
+    pub my_index: Option<usize>,
+    /// The next card this player will draw, if the game lets players peek at it
+    #[serde(default)]
+    pub top_card: Option<Card>,
+    /// Whether the board edges wrap around to the opposite edge
+    #[serde(default)]
+    pub wrap: bool,
+    /// Which cards a played card takes
+    #[serde(default)]
+    pub taking_variant: TakingVariant,
+    /// Which values are higher than which, when taking by suit
+    #[serde(default)]
+    pub rank_order: RankOrder,
+    /// Where the first card of the game may go
+    #[serde(default)]
+    pub first_move: FirstMove,
+    /// Each player's face-up reserve card, in turn order, if the game deals them
+    #[serde(default)]
+    pub reserves: Vec<Option<Card>>,
+    /// The most recent move, if there's been one
+    #[serde(default)]
+    pub last_move: Option<LastMove>,
+    /// Cards left in the deck everyone draws from, if the game has one
+    #[serde(default)]
+    pub shared_deck: Option<usize>,
+    /// Cards in this player's own deck, if the game hides which ones they are
+    ///
+    /// The deck itself is left empty when this is set
+    #[serde(default)]
+    pub hidden_deck: Option<usize>,
+    /// Whether any card has been played yet this game
+    ///
+    /// The board can be empty after this, if every card on it is taken
+    #[serde(default)]
+    pub started: bool,
+    /// Positions in the hand of cards drawn since this player last played, if the game says
+    #[serde(default)]
+    pub drawn: Vec<usize>,
+    /// Whether each player has picked their move this round, in turn order, if everyone moves at
+    /// once
+    #[serde(default)]
+    pub submitted: Option<Vec<bool>>,
+    /// How many moves have been played this game
+    #[serde(default)]
+    pub move_count: usize,
+    /// The server's [`Board::digest`] of the board, if it sends one
+    #[serde(default)]
+    pub board_hash: Option<u64>,
+}
+
+impl PlayerVisibleGameState {
+    /// Name of the player whose turn it is
+    ///
+    /// None if the turn doesn't refer to a player
+    pub fn active_player_name(&self) -> Option<&str> {
+        self.players.get(self.turn).map(|(name, _)| name.as_str())
+    }
+
+    /// Check if it's this player's turn
+    ///
+    /// When everyone moves at once, that's until they've picked their move for the round
+    pub fn is_my_turn(&self) -> bool {
+        match &self.submitted {
+            Some(submitted) => self.my_index.is_some_and(|my_index| {
+                submitted.get(my_index) == Some(&false) && self.my_card_count() != Some(0)
+            }),
+            None => self.my_index == Some(self.turn),
+        }
+    }
+
+    /// This player's own reserve card, if they have one
+    pub fn my_reserve(&self) -> Option<Card> {
+        self.reserves.get(self.my_index?).copied().flatten()
+    }
+
+    /// How many cards this player has left, or None for spectators
+    pub fn my_card_count(&self) -> Option<u32> {
+        self.players.get(self.my_index?).map(|(_, count)| *count)
+    }
+
+    /// Name of the player who won, if the game is over
+    ///
+    /// The winner is the only player left with cards. Playing your last card never wins - if that
+    /// leaves one other player with cards, they win instead.
+    pub fn winner(&self) -> Option<&str> {
+        let mut with_cards = self.players.iter().filter(|(_, count)| *count > 0);
+        match (with_cards.next(), with_cards.next()) {
+            (Some((username, _)), None) => Some(username),
+            _ => None,
+        }
+    }
+
+    /// Cards this player can't see - those held by the other players or out of play
+    ///
+    /// Each card seen accounts for one copy, so a card shows up once per copy of it that's still
+    /// unaccounted for
+    pub fn unseen_cards(&self) -> Vec<Card> {
+        let mut unseen = Suit::ALL
+            .into_iter()
+            .flat_map(|suit| Value::ALL.map(|value| Card(suit, value)))
+            .collect::<Vec<_>>();
+        let seen = self
+            .board
+            .0
+            .iter()
+            .flatten()
+            .flatten()
+            .chain(&self.hand.0)
+            .chain(&self.deck.0)
+            .chain(self.reserves.iter().flatten());
+        for card in seen {
+            if let Some(index) = unseen.iter().position(|unseen| unseen == card) {
+                unseen.remove(index);
+            }
+        }
+        unseen
+    }
+
+    /// What decides where this player can play, besides the cards on the board
+    pub fn play_context(&self) -> PlayContext {
+        PlayContext {
+            wrap: self.wrap,
+            started: self.started,
+            first_move: self.first_move,
+        }
+    }
+
+    /// Every move this player could make now, cards in hand first, then their reserve
+    ///
+    /// Each is checked as [`PlayerMove::new_checked`] does, so rules the state doesn't show are
+    /// left to the server
+    pub fn legal_moves(&self) -> Vec<PlayerMove> {
+        let positions = self.board.playable_positions(self.play_context());
+        (0..self.hand.0.len())
+            .map(|card| (card, MoveSource::Hand))
+            .chain(self.my_reserve().map(|_| (0, MoveSource::Reserve)))
+            .flat_map(|(card, from)| {
+                positions.iter().filter_map(move |&location| {
+                    PlayerMove::new_checked(card, location, from, self)
+                })
+            })
+            .collect()
+    }
+
+    /// How many cards a move would take, not counting the card played
+    ///
+    /// Zero if this player doesn't hold the card
+    pub fn cards_taken_by(&self, player_move: &PlayerMove) -> usize {
+        let card = match player_move.from {
+            MoveSource::Hand => self.hand.0.get(player_move.card).copied(),
+            MoveSource::Reserve => self.my_reserve(),
+        };
+        card.map_or(0, |card| {
+            find_captures(
+                &self.board,
+                card,
+                player_move.location,
+                self.taking_variant,
+                self.rank_order,
+                self.wrap,
+            )
+            .len()
+            .saturating_sub(1)
+        })
+    }
+
+    /// The legal move that takes the most cards, the first of them in [`Self::legal_moves`] order
+    /// if there's a tie
+    pub fn best_move(&self) -> Option<PlayerMove> {
+        let moves = self.legal_moves();
+        let most = moves
+            .iter()
+            .map(|player_move| self.cards_taken_by(player_move))
+            .max()?;
+        moves
+            .into_iter()
+            .find(|player_move| self.cards_taken_by(player_move) == most)
+    }
+
+    /// Apply a delta computed against this state
+    ///
+    /// If the delta doesn't fit this state, returns false and leaves the state unchanged
+    pub fn apply_delta(&mut self, delta: &GameStateDelta) -> bool {
+        if delta.deck_drawn > self.deck.0.len()
+            || delta.counts.len() != self.players.len()
+            || delta
+                .board
+                .iter()
+                .any(|&((row, col), _)| row >= BOARD_SIZE || col >= BOARD_SIZE)
+        {
+            return false;
+        }
+
+        for &((row, col), card) in &delta.board {
+            self.board.0[row][col] = card;
+        }
+        self.hand = delta.hand.clone();
+        self.deck.0.drain(..delta.deck_drawn);
+        self.deck.0.extend(delta.deck_added.iter().copied());
+        for ((_, count), &new_count) in self.players.iter_mut().zip(&delta.counts) {
+            *count = new_count;
+        }
+        self.turn = delta.turn;
+        self.top_card = delta.top_card;
+        self.reserves = delta.reserves.clone();
+        self.last_move = delta.last_move.clone();
+        self.shared_deck = delta.shared_deck;
+        self.hidden_deck = delta.hidden_deck;
+        self.started = delta.started;
+        self.drawn = delta.drawn.clone();
+        self.submitted = delta.submitted.clone();
+        self.move_count = delta.move_count;
+        self.board_hash = delta.board_hash;
+
+        true
+    }
+
+    /// Whether the board is the one the server hashed, or there's no hash to check against
+    ///
+    /// A mismatch after applying deltas means this copy of the board has drifted from the
+    /// server's
+    pub fn board_matches_hash(&self) -> bool {
+        self.board_hash
+            .is_none_or(|board_hash| board_hash == self.board.digest())
+    }
+
+    /// What changed since `prev`, for reacting to each new state as it arrives
+    ///
+    /// Both states must be for the same player in the same game
+    pub fn diff(&self, prev: &Self) -> StateDiff {
+        let mut board = Vec::new();
+        for row in 0..BOARD_SIZE {
+            for col in 0..BOARD_SIZE {
+                let (before, after) = (prev.board.0[row][col], self.board.0[row][col]);
+                if before != after {
+                    board.push(CellChange {
+                        location: (row, col),
+                        before,
+                        after,
+                    });
+                }
+            }
+        }
+
+        StateDiff {
+            board,
+            hand_added: cards_missing_from(&self.hand.0, &prev.hand.0),
+            hand_removed: cards_missing_from(&prev.hand.0, &self.hand.0),
+            deck_added: cards_missing_from(&self.deck.0, &prev.deck.0),
+            deck_removed: cards_missing_from(&prev.deck.0, &self.deck.0),
+            counts: self
+                .players
+                .iter()
+                .zip(&prev.players)
+                .filter(|((_, after), (_, before))| after != before)
+                .map(|((username, after), (_, before))| {
+                    (username.clone(), i64::from(*after) - i64::from(*before))
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Cards in `cards` that aren't in `other`, counting each copy separately
+fn cards_missing_from(cards: &[Card], other: &[Card]) -> Vec<Card> {
+    let mut other = other.to_vec();
+    cards
+        .iter()
+        .filter(|card| match other.iter().position(|other| other == *card) {
+            Some(index) => {
+                other.remove(index);
+                false
+            }
+            None => true,
+        })
+        .copied()
+        .collect()
+}
+
+/// What changed between two successive game states, as seen by the same player
+///
+/// Unlike [`GameStateDelta`], this describes the changes rather than how to reproduce them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateDiff {
+    /// Board cells whose contents changed
+    pub board: Vec<CellChange>,
+    /// Cards now in this player's hand that weren't before
+    pub hand_added: Vec<Card>,
+    /// Cards no longer in this player's hand
+    pub hand_removed: Vec<Card>,
+    /// Cards now in this player's deck that weren't before
+    pub deck_added: Vec<Card>,
+    /// Cards no longer in this player's deck
+    pub deck_removed: Vec<Card>,
+    /// How much each player's card count changed by, for players whose count changed
+    pub counts: Vec<(String, i64)>,
+}
+
+impl StateDiff {
+    /// Check if nothing changed
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// A board cell whose contents changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellChange {
+    /// Where the cell is, as indexes into the board position
+    pub location: (usize, usize),
+    /// What was there before
+    pub before: Option<Card>,
+    /// What's there now
+    pub after: Option<Card>,
+}
+
+/// The changes between two successive game states visible to the same player
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameStateDelta {
+    /// Board cells whose contents changed, with their new contents
+    pub board: Vec<((usize, usize), Option<Card>)>,
+    /// The new hand
+    pub hand: Hand,
+    /// How many cards were drawn from the top of the deck
+    pub deck_drawn: usize,
+    /// Cards added to the bottom of the deck
+    pub deck_added: Vec<Card>,
+    /// The new card counts of each player, in turn order
+    pub counts: Vec<u32>,
+    /// The new turn
+    pub turn: usize,
+    /// The new next card to draw, if the game lets players peek at it
+    #[serde(default)]
+    pub top_card: Option<Card>,
+    /// The new reserve cards
+    #[serde(default)]
+    pub reserves: Vec<Option<Card>>,
+    /// The new most recent move
+    #[serde(default)]
+    pub last_move: Option<LastMove>,
+    /// The new number of cards in the shared deck
+    #[serde(default)]
+    pub shared_deck: Option<usize>,
+    /// The new number of cards in this player's hidden deck
+    #[serde(default)]
+    pub hidden_deck: Option<usize>,
+    /// Whether any card has been played yet this game
+    #[serde(default)]
+    pub started: bool,
+    /// The new positions of freshly drawn cards in the hand
+    #[serde(default)]
+    pub drawn: Vec<usize>,
+    /// Who has picked their move this round, if everyone moves at once
+    #[serde(default)]
+    pub submitted: Option<Vec<bool>>,
+    /// The new number of moves played
+    #[serde(default)]
+    pub move_count: usize,
+    /// The server's hash of the new board, if it sends one
+    #[serde(default)]
+    pub board_hash: Option<u64>,
+}
+
+impl GameStateDelta {
+    /// Compute the delta turning `prev` into `next`
+    ///
+    /// Both states must be for the same player in the same game
+    pub fn between(prev: &PlayerVisibleGameState, next: &PlayerVisibleGameState) -> Self {
+        let mut board = Vec::new();
+        for row in 0..BOARD_SIZE {
+            for col in 0..BOARD_SIZE {
+                if prev.board.0[row][col] != next.board.0[row][col] {
+                    board.push(((row, col), next.board.0[row][col]));
+                }
+            }
+        }
+
+        // Find the fewest cards drawn such that the rest of the old deck starts the new deck
+        let deck_drawn = (0..=prev.deck.0.len())
+            .find(|&drawn| next.deck.0.starts_with(&prev.deck.0[drawn..]))
+            .expect("drawing the whole deck always matches");
+        let deck_added = next.deck.0[(prev.deck.0.len() - deck_drawn)..].to_vec();
+
+        Self {
+            board,
+            hand: next.hand.clone(),
+            deck_drawn,
+            deck_added,
+            counts: next.players.iter().map(|(_, count)| *count).collect(),
+            turn: next.turn,
+            top_card: next.top_card,
+            reserves: next.reserves.clone(),
+            last_move: next.last_move.clone(),
+            shared_deck: next.shared_deck,
+            hidden_deck: next.hidden_deck,
+            started: next.started,
+            drawn: next.drawn.clone(),
+            submitted: next.submitted.clone(),
+            move_count: next.move_count,
+            board_hash: next.board_hash,
+        }
+    }
 }
 
 /// A move a player can make
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PlayerMove {
     /// Which card, indexed from their hand
     pub card: usize,
     /// Where, as indexes into the board position
     pub location: (usize, usize),
+    /// Where the card comes from - the card index is only used for the hand
+    #[serde(default)]
+    pub from: MoveSource,
+    /// Directions, from `DIRECTIONS`, not to take cards in, if the game lets players choose
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub declined: Vec<(i32, i32)>,
+}
+
+impl PlayerMove {
+    /// A move that takes cards in every direction it can
+    pub fn new(card: usize, location: (usize, usize), from: MoveSource) -> Self {
+        PlayerMove {
+            card,
+            location,
+            from,
+            declined: Vec::new(),
+        }
+    }
+
+    /// The move, if the player could make it in the given state
+    ///
+    /// Checks everything the state shows - that it's their turn, they hold the card, and it can go
+    /// there. Rules the state doesn't show, like having to take cards, are left to the server.
+    pub fn new_checked(
+        card: usize,
+        (row, col): (usize, usize),
+        from: MoveSource,
+        state: &PlayerVisibleGameState,
+    ) -> Option<PlayerMove> {
+        let held = match from {
+            MoveSource::Hand => card < state.hand.0.len(),
+            MoveSource::Reserve => state.my_reserve().is_some(),
+        };
+        let placeable = state.board.can_play_at(row, col, state.play_context());
+        (state.is_my_turn() && held && placeable).then(|| PlayerMove::new(card, (row, col), from))
+    }
+}
+
+/// A move that has been made, as everyone saw it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LastMove {
+    /// Who made the move
+    pub player: String,
+    /// The card played
+    pub card: Card,
+    /// Where it was played
+    pub location: (usize, usize),
+    /// Positions of the cards it took, including itself - empty if it took nothing
+    pub taken: Vec<(usize, usize)>,
+    /// Where the cards it took went
+    #[serde(default, skip_serializing_if = "TakenTo::is_mover_deck")]
+    pub taken_to: TakenTo,
+}
+
+impl LastMove {
+    /// Where the card taken from a position went, or None if the move didn't take from there
+    pub fn destination(&self, position: (usize, usize)) -> Option<CardDestination> {
+        match &self.taken_to {
+            TakenTo::MoverDeck => self
+                .taken
+                .contains(&position)
+                .then(|| CardDestination::Deck(self.player.clone())),
+            TakenTo::Each(destinations) => destinations
+                .iter()
+                .find(|(taken, _)| *taken == position)
+                .map(|(_, destination)| destination.clone()),
+        }
+    }
+}
+
+/// Where the cards a move took went
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TakenTo {
+    /// All into the deck of the player who made the move
+    #[default]
+    MoverDeck,
+    /// Each card somewhere of its own, by the position it was taken from
+    Each(Vec<((usize, usize), CardDestination)>),
+}
+impl TakenTo {
+    /// Whether this is the usual case, where the mover keeps everything they take
+    pub fn is_mover_deck(&self) -> bool {
+        *self == TakenTo::MoverDeck
+    }
+}
+
+/// Where one taken card went
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CardDestination {
+    /// The deck of the player with this name
+    Deck(String),
+    /// The hand of the player with this name
+    Hand(String),
+    /// Out of the game
+    Discard,
+}
+
+/// Where a played card comes from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MoveSource {
+    /// The player's hand
+    #[default]
+    Hand,
+    /// The player's face-up reserve card
+    Reserve,
+}
+
+/// Wins so far in a tournament of several games in a row
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TournamentStandings {
+    /// Games played so far
+    pub played: usize,
+    /// Games in the whole tournament
+    pub rounds: usize,
+    /// Each winner's number of wins, most first
+    pub wins: Vec<(String, usize)>,
+}
+
+impl TournamentStandings {
+    /// Check if every game in the tournament has been played
+    pub fn is_over(&self) -> bool {
+        self.played >= self.rounds
+    }
+
+    /// Overall winner, once the tournament is over
+    ///
+    /// None if the lead is shared
+    pub fn champion(&self) -> Option<&str> {
+        if !self.is_over() {
+            return None;
+        }
+        match self.wins.as_slice() {
+            [(leader, most), rest @ ..] if rest.iter().all(|(_, wins)| wins < most) => {
+                Some(leader.as_str())
+            }
+            _ => None,
+        }
+    }
 }
 
 /// The game board
@@ -59,11 +637,94 @@ pub struct PlayerMove {
 pub struct Board(pub [[Option<Card>; BOARD_SIZE]; BOARD_SIZE]);
 
 impl Board {
+    /// Board with just the given cards on it
+    ///
+    /// # Panics
+    ///
+    /// Panics if a position is off the board
+    pub fn from_positions(cards: &[((usize, usize), Card)]) -> Self {
+        let mut board = Board([[None; BOARD_SIZE]; BOARD_SIZE]);
+        for &((row, col), card) in cards {
+            board.0[row][col] = Some(card);
+        }
+        board
+    }
+
+    /// A short hash of the board, for checking two copies of it agree
+    ///
+    /// This is FNV-1a over the board's JSON, so it's the same on every platform and build
+    pub fn digest(&self) -> u64 {
+        let json = serde_json::to_string(self).expect("boards should always serialize");
+        fnv1a(json.as_bytes())
+    }
+
+    /// Number of cards on the board
+    pub fn played_card_count(&self) -> usize {
+        self.0.iter().flatten().flatten().count()
+    }
+
+    /// Check if there are no cards on the board
+    pub fn is_empty(&self) -> bool {
+        self.played_card_count() == 0
+    }
+
+    /// Smallest rectangle holding every card on the board, as its top left and bottom right cells
+    ///
+    /// None if the board is empty
+    pub fn bounding_box(&self) -> Option<((usize, usize), (usize, usize))> {
+        let mut cards = self.0.iter().enumerate().flat_map(|(row, cells)| {
+            cells
+                .iter()
+                .enumerate()
+                .filter(|(_, card)| card.is_some())
+                .map(move |(col, _)| (row, col))
+        });
+        let first = cards.next()?;
+        Some(cards.fold(
+            (first, first),
+            |((top, left), (bottom, right)), (row, col)| {
+                (
+                    (top.min(row), left.min(col)),
+                    (bottom.max(row), right.max(col)),
+                )
+            },
+        ))
+    }
+
+    /// Rows and columns holding the cards played and a spot either side of them
+    ///
+    /// On a board that doesn't wrap, that's everywhere the next card can go. An empty board is
+    /// taken whole.
+    pub fn occupied_area(&self) -> (Range<usize>, Range<usize>) {
+        match self.bounding_box() {
+            Some(((top, left), (bottom, right))) => (
+                top.saturating_sub(1)..(bottom + 2).min(BOARD_SIZE),
+                left.saturating_sub(1)..(right + 2).min(BOARD_SIZE),
+            ),
+            None => (0..BOARD_SIZE, 0..BOARD_SIZE),
+        }
+    }
+
+    /// Position the first card of the game goes in, from the board's own dimensions
+    pub fn center(&self) -> (usize, usize) {
+        let rows = self.0.len();
+        let cols = self.0.first().map_or(0, |row| row.len());
+        (rows / 2, cols / 2)
+    }
+
     /// Check if a card can be played at the given position
     /// Returns true if the position is valid according to game rules:
-    /// - If board is empty, only center position is valid
-    /// - If board has cards, position must be adjacent to an existing card
-    pub fn can_play_at(&self, row: usize, col: usize) -> bool {
+    /// - If board is empty, only where the first move may go is valid, or anywhere once play has
+    ///   started and every card has been taken since
+    /// - If board has cards, position must be adjacent to an existing card, across the edges if
+    ///   the board wraps
+    pub fn can_play_at(&self, row: usize, col: usize, context: PlayContext) -> bool {
+        let PlayContext {
+            wrap,
+            started,
+            first_move,
+        } = context;
+
         // Check bounds
         if row >= BOARD_SIZE || col >= BOARD_SIZE {
             return false;
@@ -74,15 +735,9 @@ impl Board {
             return false;
         }
 
-        // Check if board is empty
-        let is_board_empty = self
-            .0
-            .iter()
-            .all(|board_row| board_row.iter().all(|cell| cell.is_none()));
-
-        if is_board_empty {
-            // First move must be in center
-            return row == BOARD_SIZE / 2 && col == BOARD_SIZE / 2;
+        if self.is_empty() {
+            // First move must be where the rules say
+            return started || first_move.allows(self.center(), (row, col));
         }
 
         // Board is not empty, check if position is adjacent to an existing card
@@ -91,8 +746,12 @@ impl Board {
                 if dr == 0 && dc == 0 {
                     continue; // Skip the current position
                 }
-                let adj_row = row as i32 + dr;
-                let adj_col = col as i32 + dc;
+                let mut adj_row = row as i32 + dr;
+                let mut adj_col = col as i32 + dc;
+                if wrap {
+                    adj_row = adj_row.rem_euclid(BOARD_SIZE as i32);
+                    adj_col = adj_col.rem_euclid(BOARD_SIZE as i32);
+                }
 
                 // Check bounds and if there's a card at this adjacent position
                 if adj_row >= 0
@@ -108,6 +767,327 @@ impl Board {
 
         false
     }
+
+    /// Every position a card could be played at, in board order
+    ///
+    /// The same positions [`Board::can_play_at`] allows, in one pass over the board
+    pub fn playable_positions(&self, context: PlayContext) -> Vec<(usize, usize)> {
+        let PlayContext {
+            wrap,
+            started,
+            first_move,
+        } = context;
+        let positions = (0..BOARD_SIZE).flat_map(|row| (0..BOARD_SIZE).map(move |col| (row, col)));
+        if self.is_empty() {
+            let center = self.center();
+            return positions
+                .filter(|&position| started || first_move.allows(center, position))
+                .collect();
+        }
+
+        let mut playable = [[false; BOARD_SIZE]; BOARD_SIZE];
+        for (row, col) in positions.clone() {
+            if self.0[row][col].is_none() {
+                continue;
+            }
+            for dr in -1..=1 {
+                for dc in -1..=1 {
+                    let mut adj_row = row as i32 + dr;
+                    let mut adj_col = col as i32 + dc;
+                    if wrap {
+                        adj_row = adj_row.rem_euclid(BOARD_SIZE as i32);
+                        adj_col = adj_col.rem_euclid(BOARD_SIZE as i32);
+                    }
+                    if (0..BOARD_SIZE as i32).contains(&adj_row)
+                        && (0..BOARD_SIZE as i32).contains(&adj_col)
+                    {
+                        playable[adj_row as usize][adj_col as usize] = true;
+                    }
+                }
+            }
+        }
+        positions
+            .filter(|&(row, col)| playable[row][col] && self.0[row][col].is_none())
+            .collect()
+    }
+
+    /// Positions of every card connected to the one at the given position, including itself
+    ///
+    /// Cards are connected if they're beside, above, below, or diagonal to each other, or through
+    /// a chain of such cards. Positions are in board order, and empty if there's no card there.
+    pub fn connected_group(&self, row: usize, col: usize) -> Vec<(usize, usize)> {
+        self.connected_group_wrapping(row, col, false)
+    }
+
+    /// Positions of every card connected to the one at the given position, on a board whose
+    /// edges may wrap
+    ///
+    /// If the board wraps, cards on opposite edges are adjacent
+    pub fn connected_group_wrapping(
+        &self,
+        row: usize,
+        col: usize,
+        wrap: bool,
+    ) -> Vec<(usize, usize)> {
+        if row >= BOARD_SIZE || col >= BOARD_SIZE || self.0[row][col].is_none() {
+            return Vec::new();
+        }
+
+        let mut in_group = [[false; BOARD_SIZE]; BOARD_SIZE];
+        in_group[row][col] = true;
+        let mut to_visit = vec![(row, col)];
+        while let Some((row, col)) = to_visit.pop() {
+            for dr in -1..=1 {
+                for dc in -1..=1 {
+                    let mut adj_row = row as i32 + dr;
+                    let mut adj_col = col as i32 + dc;
+                    if wrap {
+                        adj_row = adj_row.rem_euclid(BOARD_SIZE as i32);
+                        adj_col = adj_col.rem_euclid(BOARD_SIZE as i32);
+                    }
+                    if !(0..BOARD_SIZE as i32).contains(&adj_row)
+                        || !(0..BOARD_SIZE as i32).contains(&adj_col)
+                    {
+                        continue;
+                    }
+
+                    let (adj_row, adj_col) = (adj_row as usize, adj_col as usize);
+                    if self.0[adj_row][adj_col].is_some() && !in_group[adj_row][adj_col] {
+                        in_group[adj_row][adj_col] = true;
+                        to_visit.push((adj_row, adj_col));
+                    }
+                }
+            }
+        }
+
+        (0..BOARD_SIZE)
+            .flat_map(|row| (0..BOARD_SIZE).map(move |col| (row, col)))
+            .filter(|&(row, col)| in_group[row][col])
+            .collect()
+    }
+}
+
+/// What decides where a card can be played, besides the cards on the board
+///
+/// The default is a board that doesn't wrap, before the first card, which goes in the center
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlayContext {
+    /// Whether cards on opposite edges of the board are adjacent
+    pub wrap: bool,
+    /// Whether play has started, so an empty board has been cleared and can be played on anywhere
+    pub started: bool,
+    /// Where the first card of the game may go
+    pub first_move: FirstMove,
+}
+
+/// Where the first card of the game may go
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum FirstMove {
+    /// Only in the center of the board
+    #[default]
+    Center,
+    /// Anywhere in the center row or column
+    CenterLine,
+    /// Anywhere at all
+    Anywhere,
+}
+impl FirstMove {
+    /// Whether the first card may go at a position, given the board's center
+    pub fn allows(self, center: (usize, usize), (row, col): (usize, usize)) -> bool {
+        match self {
+            FirstMove::Center => (row, col) == center,
+            FirstMove::CenterLine => row == center.0 || col == center.1,
+            FirstMove::Anywhere => true,
+        }
+    }
+}
+
+/// Which cards a played card takes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum TakingVariant {
+    /// Cards of the same value
+    #[default]
+    SameNumber,
+    /// Cards of the same value, or of the same suit and a lesser value
+    SameNumberOrSuitRanked,
+}
+
+/// Which values are higher than which, when a card takes lesser cards of its suit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum RankOrder {
+    /// Ace, two up to ten, then jack, queen, and king
+    #[default]
+    AceLow,
+    /// Two up to ten, then jack, queen, king, and ace
+    AceHigh,
+}
+impl RankOrder {
+    /// Where a value ranks, from 1 for the lowest up to 13
+    pub fn rank(self, value: Value) -> u8 {
+        const ACE_LOW: [u8; 13] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13];
+        const ACE_HIGH: [u8; 13] = [13, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12];
+        let table = match self {
+            RankOrder::AceLow => &ACE_LOW,
+            RankOrder::AceHigh => &ACE_HIGH,
+        };
+        table[value as usize - 1]
+    }
+}
+
+/// Directions cards are taken in, as row and column steps: 4 orthogonal, then 4 diagonal
+pub const DIRECTIONS: [(i32, i32); 8] = [
+    // orthogonal
+    (-1, 0),
+    (1, 0),
+    (0, -1),
+    (0, 1),
+    // diagonal
+    (-1, -1),
+    (-1, 1),
+    (1, -1),
+    (1, 1),
+];
+
+/// Positions of the cards taken by playing a card at a position, including the played card itself
+///
+/// The played card doesn't need to be on the board yet. Positions are in board order, with no
+/// duplicates, and empty if nothing is taken.
+pub fn find_captures(
+    board: &Board,
+    card: Card,
+    location: (usize, usize),
+    taking_variant: TakingVariant,
+    rank_order: RankOrder,
+    wrap: bool,
+) -> Vec<(usize, usize)> {
+    find_captures_in(
+        board,
+        card,
+        location,
+        taking_variant,
+        rank_order,
+        wrap,
+        &DIRECTIONS,
+    )
+}
+
+/// Positions of the cards taken by playing a card at a position, looking only in some directions
+///
+/// Otherwise the same as `find_captures`
+pub fn find_captures_in(
+    board: &Board,
+    card: Card,
+    (row, col): (usize, usize),
+    taking_variant: TakingVariant,
+    rank_order: RankOrder,
+    wrap: bool,
+    directions: &[(i32, i32)],
+) -> Vec<(usize, usize)> {
+    let mut captures = match taking_variant {
+        TakingVariant::SameNumber => {
+            // Find furthest-away cards orthogonally and diagonally with the same value
+            find_taking_cards(board, row, col, wrap, directions, |target_card| {
+                target_card.1 == card.1
+            })
+        }
+        TakingVariant::SameNumberOrSuitRanked => {
+            // Find furthest-away cards orthogonally and diagonally with either the same value or the same suit and a lesser value
+            find_taking_cards(board, row, col, wrap, directions, |target_card| {
+                target_card.1 == card.1
+                    || (target_card.0 == card.0
+                        && rank_order.rank(target_card.1) < rank_order.rank(card.1))
+            })
+        }
+    };
+    // lines that wrap around the board can meet each other, listing a cell twice
+    captures.sort();
+    captures.dedup();
+    captures
+}
+
+/// Whether any of the cards would take something if played in some legal spot on the board
+pub fn can_take_any(
+    board: &Board,
+    cards: &[Card],
+    taking_variant: TakingVariant,
+    rank_order: RankOrder,
+    wrap: bool,
+    started: bool,
+    first_move: FirstMove,
+) -> bool {
+    let context = PlayContext {
+        wrap,
+        started,
+        first_move,
+    };
+    board
+        .playable_positions(context)
+        .into_iter()
+        .any(|position| {
+            cards.iter().any(|&card| {
+                !find_captures(board, card, position, taking_variant, rank_order, wrap).is_empty()
+            })
+        })
+}
+
+/// Find cards that can be taken based on the given predicate
+///
+/// Returns positions of cards to be taken
+fn find_taking_cards(
+    board: &Board,
+    card_row: usize,
+    card_col: usize,
+    wrap: bool,
+    directions: &[(i32, i32)],
+    predicate: impl Fn(Card) -> bool,
+) -> Vec<(usize, usize)> {
+    let mut to_take = Vec::new();
+
+    for &(dr, dc) in directions {
+        // Position some number of steps away in this direction, if it's on the board
+        let position = |step: i32| {
+            let row = card_row as i32 + dr * step;
+            let col = card_col as i32 + dc * step;
+            if wrap {
+                Some((
+                    row.rem_euclid(BOARD_SIZE as i32) as usize,
+                    col.rem_euclid(BOARD_SIZE as i32) as usize,
+                ))
+            } else if (0..BOARD_SIZE as i32).contains(&row) && (0..BOARD_SIZE as i32).contains(&col)
+            {
+                Some((row as usize, col as usize))
+            } else {
+                None
+            }
+        };
+
+        // Search in this direction for the last matching card, stopping before a wrapping
+        // board brings us back around to the played card
+        let mut found = None;
+        for step in 1..BOARD_SIZE as i32 {
+            let Some((row, col)) = position(step) else {
+                break;
+            };
+            if let Some(board_card) = board.0[row][col]
+                && predicate(board_card)
+            {
+                found = Some(step)
+            }
+        }
+
+        // Take everything from the played card up to and including the final matching card
+        if let Some(end) = found {
+            to_take.extend((0..=end).filter_map(position));
+        }
+    }
+
+    to_take
 }
 
 /// A hand of cards
@@ -115,14 +1095,44 @@ impl Board {
 #[repr(transparent)]
 pub struct Hand(pub Vec<Card>);
 
+impl Hand {
+    /// Hand holding the given cards, in order
+    pub fn from_cards(cards: &[Card]) -> Self {
+        Hand(cards.to_vec())
+    }
+}
+
 /// A deck of cards
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[repr(transparent)]
 pub struct Deck(pub Vec<Card>);
 
+impl Deck {
+    /// Deck holding the given cards, top card first
+    pub fn from_cards(cards: &[Card]) -> Self {
+        Deck(cards.to_vec())
+    }
+}
+
 /// A card
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Card(pub Suit, pub Value);
+impl Card {
+    /// Whether this is a jack, queen, or king
+    pub fn is_face(&self) -> bool {
+        self.1.is_face()
+    }
+
+    /// Whether this is an ace
+    pub fn is_ace(&self) -> bool {
+        self.1.is_ace()
+    }
+
+    /// Whether this is a two through ten
+    pub fn is_number(&self) -> bool {
+        self.1.is_number()
+    }
+}
 impl Display for Card {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut character = match self.0 {
@@ -169,6 +1179,9 @@ pub enum Suit {
     Spades,
 }
 impl Suit {
+    /// Every suit, in the order a fresh deck is built in
+    pub const ALL: [Suit; 4] = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+
     /// Get the display colour of this suit
     pub fn colour(&self) -> &'static str {
         match *self {
@@ -176,6 +1189,16 @@ impl Suit {
             Suit::Diamonds | Suit::Hearts => "#ff0000",
         }
     }
+
+    /// Symbol this suit is drawn with
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Suit::Clubs => "♣",
+            Suit::Diamonds => "♦",
+            Suit::Hearts => "♥",
+            Suit::Spades => "♠",
+        }
+    }
 }
 
 /// The value of a card
@@ -210,6 +1233,63 @@ pub enum Value {
     #[serde(rename = "K")]
     King,
 }
+impl Value {
+    /// Every value, from ace up to king
+    pub const ALL: [Value; 13] = [
+        Value::Ace,
+        Value::Two,
+        Value::Three,
+        Value::Four,
+        Value::Five,
+        Value::Six,
+        Value::Seven,
+        Value::Eight,
+        Value::Nine,
+        Value::Ten,
+        Value::Jack,
+        Value::Queen,
+        Value::King,
+    ];
+
+    /// Short name of this value, as printed in a card's corner
+    pub fn label(self) -> &'static str {
+        match self {
+            Value::Ace => "A",
+            Value::Two => "2",
+            Value::Three => "3",
+            Value::Four => "4",
+            Value::Five => "5",
+            Value::Six => "6",
+            Value::Seven => "7",
+            Value::Eight => "8",
+            Value::Nine => "9",
+            Value::Ten => "10",
+            Value::Jack => "J",
+            Value::Queen => "Q",
+            Value::King => "K",
+        }
+    }
+
+    /// Points this card is worth, from 1 for an ace up to 13 for a king
+    pub fn points(self) -> u32 {
+        self as u32
+    }
+
+    /// Whether this is a jack, queen, or king
+    pub fn is_face(self) -> bool {
+        matches!(self, Value::Jack | Value::Queen | Value::King)
+    }
+
+    /// Whether this is an ace
+    pub fn is_ace(self) -> bool {
+        self == Value::Ace
+    }
+
+    /// Whether this is a two through ten
+    pub fn is_number(self) -> bool {
+        !self.is_face() && !self.is_ace()
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -220,91 +1300,886 @@ mod tests {
     }
 
     fn create_board_with_center_card() -> Board {
+        Board::from_positions(&[(
+            (BOARD_SIZE / 2, BOARD_SIZE / 2),
+            Card(Suit::Hearts, Value::Ace),
+        )])
+    }
+
+    fn create_game_state() -> PlayerVisibleGameState {
+        PlayerVisibleGameState {
+            board: create_board_with_center_card(),
+            hand: Hand::from_cards(&[
+                Card(Suit::Clubs, Value::Two),
+                Card(Suit::Spades, Value::Five),
+            ]),
+            deck: Deck::from_cards(&[
+                Card(Suit::Diamonds, Value::King),
+                Card(Suit::Hearts, Value::Three),
+                Card(Suit::Clubs, Value::Nine),
+            ]),
+            username: "Alice".to_string(),
+            players: vec![("Alice".to_string(), 5), ("Bob".to_string(), 6)],
+            turn: 0,
+            my_index: Some(0),
+            top_card: None,
+            wrap: false,
+            taking_variant: TakingVariant::SameNumber,
+            rank_order: RankOrder::AceLow,
+            first_move: FirstMove::Center,
+            reserves: Vec::new(),
+            last_move: None,
+            shared_deck: None,
+            hidden_deck: None,
+            started: true,
+            drawn: Vec::new(),
+            submitted: None,
+            move_count: 0,
+            board_hash: None,
+        }
+    }
+
+    #[test]
+    fn test_all_suits_and_values() {
+        assert_eq!(Suit::ALL.len(), 4);
+        assert_eq!(Value::ALL.len(), 13);
+        for (index, suit) in Suit::ALL.iter().enumerate() {
+            assert!(!Suit::ALL[..index].contains(suit));
+        }
+        for (index, value) in Value::ALL.iter().enumerate() {
+            assert!(!Value::ALL[..index].contains(value));
+            assert_eq!(value.points(), index as u32 + 1);
+        }
+    }
+
+    #[test]
+    fn test_value_classes() {
+        for value in Value::ALL {
+            let card = Card(Suit::Clubs, value);
+            // every value is exactly one of these
+            assert_eq!(
+                [card.is_ace(), card.is_number(), card.is_face()]
+                    .iter()
+                    .filter(|&&is| is)
+                    .count(),
+                1
+            );
+            assert_eq!(card.is_ace(), value.points() == 1);
+            assert_eq!(card.is_number(), (2..=10).contains(&value.points()));
+            assert_eq!(card.is_face(), value.points() > 10);
+        }
+    }
+
+    #[test]
+    fn test_tournament_champion() {
+        let mut standings = TournamentStandings {
+            played: 2,
+            rounds: 3,
+            wins: vec![("Alice".to_string(), 2)],
+        };
+        assert!(!standings.is_over());
+        assert_eq!(standings.champion(), None);
+
+        standings.played = 3;
+        standings.wins.push(("Bob".to_string(), 1));
+        assert!(standings.is_over());
+        assert_eq!(standings.champion(), Some("Alice"));
+
+        // a shared lead has no champion
+        standings.rounds = 4;
+        standings.played = 4;
+        standings.wins[1].1 = 2;
+        assert_eq!(standings.champion(), None);
+    }
+
+    #[test]
+    fn test_active_player() {
+        let mut state = create_game_state();
+        assert_eq!(state.active_player_name(), Some("Alice"));
+        assert!(state.is_my_turn());
+
+        state.turn = 1;
+        assert_eq!(state.active_player_name(), Some("Bob"));
+        assert!(!state.is_my_turn());
+
+        // a turn past the end of the players is no-one's
+        state.turn = 2;
+        assert_eq!(state.active_player_name(), None);
+        assert!(!state.is_my_turn());
+
+        // when everyone moves at once, it's our turn until we've picked a move
+        state.submitted = Some(vec![false, true]);
+        assert!(state.is_my_turn());
+        state.submitted = Some(vec![true, false]);
+        assert!(!state.is_my_turn());
+    }
+
+    #[test]
+    fn test_new_checked_move() {
+        let mut state = create_game_state();
+        let center = BOARD_SIZE / 2;
+        let beside = (center, center + 1);
+
+        assert_eq!(
+            PlayerMove::new_checked(1, beside, MoveSource::Hand, &state),
+            Some(PlayerMove::new(1, beside, MoveSource::Hand))
+        );
+
+        // no such card, and no reserve to play
+        assert_eq!(
+            PlayerMove::new_checked(2, beside, MoveSource::Hand, &state),
+            None
+        );
+        assert_eq!(
+            PlayerMove::new_checked(0, beside, MoveSource::Reserve, &state),
+            None
+        );
+
+        // taken, out of reach, and off the board
+        for location in [(center, center), (0, 0), (BOARD_SIZE, center)] {
+            assert_eq!(
+                PlayerMove::new_checked(0, location, MoveSource::Hand, &state),
+                None
+            );
+        }
+
+        state.reserves = vec![Some(Card(Suit::Spades, Value::Ace)), None];
+        assert!(PlayerMove::new_checked(0, beside, MoveSource::Reserve, &state).is_some());
+
+        // not our turn, or not playing at all
+        state.turn = 1;
+        assert_eq!(
+            PlayerMove::new_checked(0, beside, MoveSource::Hand, &state),
+            None
+        );
+        state.turn = 0;
+        state.my_index = None;
+        assert_eq!(
+            PlayerMove::new_checked(0, beside, MoveSource::Hand, &state),
+            None
+        );
+    }
+
+    #[test]
+    fn test_winner() {
+        let mut state = create_game_state();
+        assert_eq!(state.winner(), None);
+
+        // Alice played her last card, so Bob wins even though it's still Alice's turn
+        state.players[0].1 = 0;
+        assert_eq!(state.winner(), Some("Bob"));
+
+        // no-one holding cards means no-one won
+        state.players[1].1 = 0;
+        assert_eq!(state.winner(), None);
+    }
+
+    #[test]
+    fn test_unseen_cards() {
+        let state = create_game_state();
+        let unseen = state.unseen_cards();
+        assert_eq!(unseen.len(), 52 - 6);
+        assert!(!unseen.contains(&Card(Suit::Hearts, Value::Ace)));
+        assert!(!unseen.contains(&Card(Suit::Clubs, Value::Two)));
+        assert!(!unseen.contains(&Card(Suit::Diamonds, Value::King)));
+        assert!(unseen.contains(&Card(Suit::Spades, Value::Ace)));
+
+        // a spectator only sees the board
+        let spectator = PlayerVisibleGameState {
+            hand: Hand(Vec::new()),
+            deck: Deck(Vec::new()),
+            ..create_game_state()
+        };
+        assert_eq!(spectator.unseen_cards().len(), 51);
+
+        // everyone's reserve cards are face up
+        let with_reserves = PlayerVisibleGameState {
+            reserves: vec![None, Some(Card(Suit::Spades, Value::Ace))],
+            ..create_game_state()
+        };
+        assert_eq!(with_reserves.unseen_cards().len(), 52 - 7);
+        assert_eq!(with_reserves.my_reserve(), None);
+    }
+
+    #[test]
+    fn test_legal_and_best_moves() {
+        let center = BOARD_SIZE / 2;
+        let mut state = create_game_state();
+        // a two to the right of the center nine
+        state.board = Board::from_positions(&[
+            ((center, center), Card(Suit::Hearts, Value::Nine)),
+            ((center, center + 1), Card(Suit::Hearts, Value::Two)),
+        ]);
+        state.hand = Hand::from_cards(&[
+            Card(Suit::Clubs, Value::Two),
+            Card(Suit::Spades, Value::Five),
+        ]);
+
+        // ten spots around the two cards, for each card
+        let moves = state.legal_moves();
+        assert_eq!(moves.len(), 20);
+        assert!(moves[..10].iter().all(|player_move| player_move.card == 0));
+
+        // the two on the far side of the nine takes it and the other two
+        let best = state.best_move().unwrap();
+        assert_eq!(best.card, 0);
+        assert_eq!(best.location, (center, center - 1));
+        assert_eq!(state.cards_taken_by(&best), 2);
+
+        // a reserve card can be played too
+        state.reserves = vec![Some(Card(Suit::Diamonds, Value::Nine)), None];
+        assert_eq!(state.legal_moves().len(), 30);
+
+        // and nothing out of turn
+        state.turn = 1;
+        assert!(state.legal_moves().is_empty());
+        assert_eq!(state.best_move(), None);
+    }
+
+    #[test]
+    fn test_state_from_newer_peer() {
+        let state = create_game_state();
+        let mut json = serde_json::to_value(&state).unwrap();
+        json["some_future_field"] = serde_json::json!({"nested": [1, 2, 3]});
+        assert_eq!(
+            serde_json::from_value::<PlayerVisibleGameState>(json).unwrap(),
+            state
+        );
+
+        let delta = GameStateDelta::between(&state, &state);
+        let mut json = serde_json::to_value(&delta).unwrap();
+        json["some_future_field"] = serde_json::json!(true);
+        assert_eq!(
+            serde_json::from_value::<GameStateDelta>(json).unwrap(),
+            delta
+        );
+
+        let player_move = PlayerMove::new(1, (2, 3), MoveSource::Hand);
+        let mut json = serde_json::to_value(&player_move).unwrap();
+        json["some_future_field"] = serde_json::json!("hello");
+        assert_eq!(
+            serde_json::from_value::<PlayerMove>(json).unwrap(),
+            player_move
+        );
+    }
+
+    #[test]
+    fn test_taken_to() {
+        let spread = LastMove {
+            player: "Alice".to_string(),
+            card: Card(Suit::Clubs, Value::Seven),
+            location: (5, 6),
+            taken: vec![(5, 4), (5, 5), (5, 6)],
+            taken_to: TakenTo::Each(vec![
+                ((5, 4), CardDestination::Hand("Bob".to_string())),
+                ((5, 6), CardDestination::Discard),
+            ]),
+        };
+        assert_eq!(
+            spread.destination((5, 4)),
+            Some(CardDestination::Hand("Bob".to_string()))
+        );
+        assert_eq!(spread.destination((5, 6)), Some(CardDestination::Discard));
+        // an empty gap between taken cards goes nowhere
+        assert_eq!(spread.destination((5, 5)), None);
+
+        let json = serde_json::to_string(&spread).unwrap();
+        assert!(json.contains(r#""taken_to":{"each":[[[5,4],{"hand":"Bob"}],[[5,6],"discard"]]}"#));
+        assert_eq!(serde_json::from_str::<LastMove>(&json).unwrap(), spread);
+    }
+
+    #[test]
+    fn test_state_from_older_peer() {
+        // only the fields the first release sent
+        let state = create_game_state();
+        let mut json = serde_json::to_value(&state).unwrap();
+        json.as_object_mut().unwrap().retain(|key, _| {
+            ["board", "hand", "deck", "username", "players", "turn"].contains(&key.as_str())
+        });
+        let old = serde_json::from_value::<PlayerVisibleGameState>(json).unwrap();
+        assert_eq!(old.board, state.board);
+        assert_eq!(old.hand, state.hand);
+        assert_eq!(old.my_index, None);
+        assert_eq!(old.taking_variant, TakingVariant::default());
+        assert_eq!(old.first_move, FirstMove::default());
+        assert!(!old.started);
+        assert_eq!(old.submitted, None);
+        assert_eq!(old.move_count, 0);
+        assert_eq!(old.board_hash, None);
+
+        let old = serde_json::from_str::<GameStateDelta>(
+            r#"{"board":[],"hand":[],"deck_drawn":0,"deck_added":[],"counts":[5,6],"turn":1}"#,
+        )
+        .unwrap();
+        assert_eq!(old.turn, 1);
+        assert_eq!(old.last_move, None);
+        assert!(old.drawn.is_empty());
+
+        let old = serde_json::from_str::<PlayerMove>(r#"{"card":1,"location":[2,3]}"#).unwrap();
+        assert_eq!(old.from, MoveSource::Hand);
+        assert!(old.declined.is_empty());
+    }
+
+    #[test]
+    fn test_delta_round_trip() {
+        let prev = create_game_state();
+        let center = BOARD_SIZE / 2;
+
+        // Alice plays the two of clubs next to the center card, then draws the king of diamonds
+        let mut next = prev.clone();
+        next.board.0[center][center + 1] = Some(Card(Suit::Clubs, Value::Two));
+        next.hand = Hand(vec![
+            Card(Suit::Spades, Value::Five),
+            Card(Suit::Diamonds, Value::King),
+        ]);
+        next.deck = Deck(vec![
+            Card(Suit::Hearts, Value::Three),
+            Card(Suit::Clubs, Value::Nine),
+        ]);
+        next.players[0].1 = 4;
+        next.turn = 1;
+        next.top_card = Some(Card(Suit::Hearts, Value::Three));
+        next.drawn = vec![1];
+
+        let delta = GameStateDelta::between(&prev, &next);
+        assert_eq!(
+            delta.board,
+            vec![((center, center + 1), Some(Card(Suit::Clubs, Value::Two)))]
+        );
+        assert_eq!(delta.deck_drawn, 1);
+        assert!(delta.deck_added.is_empty());
+
+        let mut applied = prev.clone();
+        assert!(applied.apply_delta(&delta));
+        assert_eq!(applied, next);
+    }
+
+    #[test]
+    fn test_delta_round_trip_with_capture() {
+        let prev = create_game_state();
+        let center = BOARD_SIZE / 2;
+
+        // A capture clears the center card and adds it and the played card to the deck
+        let mut next = prev.clone();
+        next.board.0[center][center] = None;
+        next.hand = Hand(vec![
+            Card(Suit::Spades, Value::Five),
+            Card(Suit::Diamonds, Value::King),
+        ]);
+        next.deck = Deck(vec![
+            Card(Suit::Hearts, Value::Three),
+            Card(Suit::Clubs, Value::Nine),
+            Card(Suit::Hearts, Value::Ace),
+            Card(Suit::Clubs, Value::Two),
+        ]);
+        next.players[0].1 = 6;
+
+        let delta = GameStateDelta::between(&prev, &next);
+        assert_eq!(delta.board, vec![((center, center), None)]);
+        assert_eq!(delta.deck_drawn, 1);
+        assert_eq!(
+            delta.deck_added,
+            vec![
+                Card(Suit::Hearts, Value::Ace),
+                Card(Suit::Clubs, Value::Two)
+            ]
+        );
+
+        let mut applied = prev.clone();
+        assert!(applied.apply_delta(&delta));
+        assert_eq!(applied, next);
+    }
+
+    #[test]
+    fn test_board_digest() {
+        let center = BOARD_SIZE / 2;
+        assert_eq!(
+            create_board_with_center_card().digest(),
+            create_board_with_center_card().digest()
+        );
+        assert_ne!(
+            create_board_with_center_card().digest(),
+            create_empty_board().digest()
+        );
+
+        // the same card somewhere else
+        let moved =
+            Board::from_positions(&[((center, center + 1), Card(Suit::Hearts, Value::Ace))]);
+        assert_ne!(moved.digest(), create_board_with_center_card().digest());
+        // a different card in the same place
+        let changed = Board::from_positions(&[((center, center), Card(Suit::Spades, Value::Ace))]);
+        assert_ne!(changed.digest(), create_board_with_center_card().digest());
+
+        // a board that drifted from the hashed one is caught
+        let mut state = create_game_state();
+        assert!(state.board_matches_hash());
+        state.board_hash = Some(state.board.digest());
+        assert!(state.board_matches_hash());
+        state.board = create_empty_board();
+        assert!(!state.board_matches_hash());
+    }
+
+    #[test]
+    fn test_delta_that_does_not_fit() {
+        let mut state = create_game_state();
+        let delta = GameStateDelta {
+            board: Vec::new(),
+            hand: state.hand.clone(),
+            deck_drawn: 4,
+            deck_added: Vec::new(),
+            counts: vec![5, 6],
+            turn: 1,
+            top_card: None,
+            reserves: Vec::new(),
+            last_move: None,
+            shared_deck: None,
+            hidden_deck: None,
+            started: true,
+            drawn: Vec::new(),
+            submitted: None,
+            move_count: 0,
+            board_hash: None,
+        };
+
+        // Can't draw more cards than are in the deck
+        assert!(!state.apply_delta(&delta));
+        assert_eq!(state, create_game_state());
+    }
+
+    #[test]
+    fn test_diff_after_capture() {
+        let prev = create_game_state();
+        let center = BOARD_SIZE / 2;
+
+        // Alice plays the ace of clubs on the ace of hearts, takes both, and draws the king
+        let mut next = prev.clone();
+        next.board.0[center][center] = None;
+        next.hand = Hand(vec![
+            Card(Suit::Clubs, Value::Two),
+            Card(Suit::Diamonds, Value::King),
+        ]);
+        next.deck = Deck(vec![
+            Card(Suit::Hearts, Value::Three),
+            Card(Suit::Clubs, Value::Nine),
+            Card(Suit::Hearts, Value::Ace),
+            Card(Suit::Spades, Value::Five),
+        ]);
+        next.players[0].1 = 6;
+        next.turn = 1;
+
+        let diff = next.diff(&prev);
+        assert_eq!(
+            diff.board,
+            vec![CellChange {
+                location: (center, center),
+                before: Some(Card(Suit::Hearts, Value::Ace)),
+                after: None,
+            }]
+        );
+        assert_eq!(diff.hand_added, vec![Card(Suit::Diamonds, Value::King)]);
+        assert_eq!(diff.hand_removed, vec![Card(Suit::Spades, Value::Five)]);
+        assert_eq!(
+            diff.deck_added,
+            vec![
+                Card(Suit::Hearts, Value::Ace),
+                Card(Suit::Spades, Value::Five)
+            ]
+        );
+        assert_eq!(diff.deck_removed, vec![Card(Suit::Diamonds, Value::King)]);
+        assert_eq!(diff.counts, vec![("Alice".to_string(), 1)]);
+
+        assert!(next.diff(&next).is_empty());
+    }
+
+    #[test]
+    fn test_diff_counts_duplicate_cards() {
+        let mut prev = create_game_state();
+        prev.hand = Hand(vec![Card(Suit::Clubs, Value::Two)]);
+
+        // Drawing a second copy of a card only adds that one copy
+        let mut next = prev.clone();
+        next.hand.0.push(Card(Suit::Clubs, Value::Two));
+
+        let diff = next.diff(&prev);
+        assert_eq!(diff.hand_added, vec![Card(Suit::Clubs, Value::Two)]);
+        assert!(diff.hand_removed.is_empty());
+    }
+
+    #[test]
+    fn test_played_card_count() {
         let mut board = create_empty_board();
-        board.0[BOARD_SIZE / 2][BOARD_SIZE / 2] = Some(Card(Suit::Hearts, Value::Ace));
-        board
+        assert_eq!(board.played_card_count(), 0);
+        assert!(board.is_empty());
+
+        board.0[0][0] = Some(Card(Suit::Clubs, Value::Ace));
+        board.0[BOARD_SIZE - 1][3] = Some(Card(Suit::Clubs, Value::Two));
+        assert_eq!(board.played_card_count(), 2);
+        assert!(!board.is_empty());
+        assert_eq!(create_board_with_center_card().played_card_count(), 1);
+    }
+
+    #[test]
+    fn test_from_positions() {
+        assert_eq!(Board::from_positions(&[]), create_empty_board());
+
+        let board = Board::from_positions(&[
+            ((0, 0), Card(Suit::Clubs, Value::Ace)),
+            ((BOARD_SIZE - 1, 3), Card(Suit::Clubs, Value::Two)),
+        ]);
+        assert_eq!(board.played_card_count(), 2);
+        assert_eq!(board.0[0][0], Some(Card(Suit::Clubs, Value::Ace)));
+        assert_eq!(
+            board.0[BOARD_SIZE - 1][3],
+            Some(Card(Suit::Clubs, Value::Two))
+        );
+
+        let cards = [
+            Card(Suit::Hearts, Value::Three),
+            Card(Suit::Spades, Value::King),
+        ];
+        assert_eq!(Hand::from_cards(&cards).0, cards);
+        assert_eq!(Deck::from_cards(&cards).0, cards);
+        assert!(Hand::from_cards(&[]).0.is_empty());
+    }
+
+    #[test]
+    fn test_can_play_at_cleared_board() {
+        let board = create_empty_board();
+        let context = PlayContext::default();
+        let center = BOARD_SIZE / 2;
+        let started = PlayContext {
+            started: true,
+            ..context
+        };
+
+        // Before the first card, only the center
+        assert!(!board.can_play_at(0, 0, context));
+        assert!(board.can_play_at(center, center, context));
+
+        // Once every card has been taken, anywhere
+        assert!(board.can_play_at(0, 0, started));
+        assert!(board.can_play_at(center, center, started));
+        assert!(!board.can_play_at(BOARD_SIZE, 0, started));
+
+        // With cards down, adjacency rules as usual
+        let board = create_board_with_center_card();
+        assert!(!board.can_play_at(0, 0, started));
+        assert!(board.can_play_at(center, center + 1, started));
+    }
+
+    #[test]
+    fn test_can_play_at_first_move() {
+        let board = create_empty_board();
+        let center = BOARD_SIZE / 2;
+        let playable = |first_move| {
+            let context = PlayContext {
+                first_move,
+                ..PlayContext::default()
+            };
+            (0..BOARD_SIZE)
+                .flat_map(|row| (0..BOARD_SIZE).map(move |col| (row, col)))
+                .filter(|&(row, col)| board.can_play_at(row, col, context))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(playable(FirstMove::Center), vec![(center, center)]);
+        let center_line = playable(FirstMove::CenterLine);
+        assert_eq!(center_line.len(), 2 * BOARD_SIZE - 1);
+        assert!(center_line.contains(&(0, center)) && center_line.contains(&(center, 0)));
+        assert!(!center_line.contains(&(0, 0)));
+        assert_eq!(playable(FirstMove::Anywhere).len(), BOARD_SIZE * BOARD_SIZE);
+
+        // once there's a card down, adjacency rules whatever the first move was
+        let board = create_board_with_center_card();
+        let anywhere = PlayContext {
+            first_move: FirstMove::Anywhere,
+            ..PlayContext::default()
+        };
+        assert!(!board.can_play_at(0, center, anywhere));
+        assert!(board.can_play_at(center, center + 1, anywhere));
     }
 
     #[test]
     fn test_can_play_at_empty_board_center() {
         let board = create_empty_board();
+        let context = PlayContext::default();
         let center = BOARD_SIZE / 2;
 
         // Center position should be valid on empty board
-        assert!(board.can_play_at(center, center));
+        assert!(board.can_play_at(center, center, context));
+    }
+
+    #[test]
+    fn test_center_is_only_first_move() {
+        let board = create_empty_board();
+        let context = PlayContext::default();
+
+        // the center comes from the board's own dimensions
+        assert_eq!(board.center(), (BOARD_SIZE / 2, BOARD_SIZE / 2));
+
+        // and it's the only place the first card can go
+        let playable = (0..BOARD_SIZE)
+            .flat_map(|row| (0..BOARD_SIZE).map(move |col| (row, col)))
+            .filter(|&(row, col)| board.can_play_at(row, col, context))
+            .collect::<Vec<_>>();
+        assert_eq!(playable, vec![board.center()]);
     }
 
     #[test]
     fn test_can_play_at_empty_board_non_center() {
         let board = create_empty_board();
+        let context = PlayContext::default();
 
         // Non-center positions should be invalid on empty board
-        assert!(!board.can_play_at(0, 0)); // Corner
-        assert!(!board.can_play_at(1, 1)); // Near corner
-        assert!(!board.can_play_at(BOARD_SIZE / 2, BOARD_SIZE / 2 + 1)); // Adjacent to center
-        assert!(!board.can_play_at(BOARD_SIZE / 2 + 1, BOARD_SIZE / 2)); // Adjacent to center
+        assert!(!board.can_play_at(0, 0, context)); // Corner
+        assert!(!board.can_play_at(1, 1, context)); // Near corner
+        assert!(!board.can_play_at(BOARD_SIZE / 2, BOARD_SIZE / 2 + 1, context)); // Adjacent to center
+        assert!(!board.can_play_at(BOARD_SIZE / 2 + 1, BOARD_SIZE / 2, context)); // Adjacent to center
     }
 
     #[test]
     fn test_can_play_at_out_of_bounds() {
         let board = create_empty_board();
+        let context = PlayContext::default();
 
         // Out of bounds positions should be invalid
-        assert!(!board.can_play_at(BOARD_SIZE, BOARD_SIZE));
-        assert!(!board.can_play_at(BOARD_SIZE + 1, 0));
-        assert!(!board.can_play_at(0, BOARD_SIZE + 1));
+        assert!(!board.can_play_at(BOARD_SIZE, BOARD_SIZE, context));
+        assert!(!board.can_play_at(BOARD_SIZE + 1, 0, context));
+        assert!(!board.can_play_at(0, BOARD_SIZE + 1, context));
     }
 
     #[test]
     fn test_can_play_at_occupied_position() {
         let board = create_board_with_center_card();
+        let context = PlayContext::default();
         let center = BOARD_SIZE / 2;
 
         // Occupied position should be invalid
-        assert!(!board.can_play_at(center, center));
+        assert!(!board.can_play_at(center, center, context));
     }
 
     #[test]
     fn test_can_play_at_orthogonal_adjacency() {
         let board = create_board_with_center_card();
+        let context = PlayContext::default();
         let center = BOARD_SIZE / 2;
 
         // Orthogonally adjacent positions should be valid
-        assert!(board.can_play_at(center - 1, center)); // North
-        assert!(board.can_play_at(center + 1, center)); // South
-        assert!(board.can_play_at(center, center - 1)); // West
-        assert!(board.can_play_at(center, center + 1)); // East
+        assert!(board.can_play_at(center - 1, center, context)); // North
+        assert!(board.can_play_at(center + 1, center, context)); // South
+        assert!(board.can_play_at(center, center - 1, context)); // West
+        assert!(board.can_play_at(center, center + 1, context)); // East
     }
 
     #[test]
     fn test_can_play_at_diagonal_adjacency() {
         let board = create_board_with_center_card();
+        let context = PlayContext::default();
         let center = BOARD_SIZE / 2;
 
         // Diagonally adjacent positions should be valid
-        assert!(board.can_play_at(center - 1, center - 1)); // Northwest
-        assert!(board.can_play_at(center - 1, center + 1)); // Northeast
-        assert!(board.can_play_at(center + 1, center - 1)); // Southwest
-        assert!(board.can_play_at(center + 1, center + 1)); // Southeast
+        assert!(board.can_play_at(center - 1, center - 1, context)); // Northwest
+        assert!(board.can_play_at(center - 1, center + 1, context)); // Northeast
+        assert!(board.can_play_at(center + 1, center - 1, context)); // Southwest
+        assert!(board.can_play_at(center + 1, center + 1, context)); // Southeast
     }
 
     #[test]
     fn test_can_play_at_non_adjacent() {
         let board = create_board_with_center_card();
+        let context = PlayContext::default();
         let center = BOARD_SIZE / 2;
 
         // Non-adjacent positions should be invalid
-        assert!(!board.can_play_at(0, 0)); // Far corner
-        assert!(!board.can_play_at(center - 2, center)); // Two spaces north
-        assert!(!board.can_play_at(center + 2, center)); // Two spaces south
-        assert!(!board.can_play_at(center, center - 2)); // Two spaces west
-        assert!(!board.can_play_at(center, center + 2)); // Two spaces east
-        assert!(!board.can_play_at(center - 2, center + 1)); // Knight's move pattern
+        assert!(!board.can_play_at(0, 0, context)); // Far corner
+        assert!(!board.can_play_at(center - 2, center, context)); // Two spaces north
+        assert!(!board.can_play_at(center + 2, center, context)); // Two spaces south
+        assert!(!board.can_play_at(center, center - 2, context)); // Two spaces west
+        assert!(!board.can_play_at(center, center + 2, context)); // Two spaces east
+        assert!(!board.can_play_at(center - 2, center + 1, context)); // Knight's move pattern
+    }
+
+    #[test]
+    fn test_can_play_at_wrapping() {
+        let mut board = create_empty_board();
+        board.0[0][BOARD_SIZE - 1] = Some(Card(Suit::Spades, Value::Two));
+        let context = PlayContext::default();
+        let wrapping = PlayContext {
+            wrap: true,
+            ..context
+        };
+
+        // Opposite edges aren't adjacent on a normal board
+        assert!(!board.can_play_at(0, 0, context));
+        assert!(!board.can_play_at(BOARD_SIZE - 1, BOARD_SIZE - 1, context));
+
+        // But they are when the board wraps, including diagonally across the corner
+        assert!(board.can_play_at(0, 0, wrapping));
+        assert!(board.can_play_at(BOARD_SIZE - 1, BOARD_SIZE - 1, wrapping));
+        assert!(board.can_play_at(BOARD_SIZE - 1, 0, wrapping));
+        assert!(!board.can_play_at(BOARD_SIZE / 2, BOARD_SIZE / 2, wrapping));
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        let center = BOARD_SIZE / 2;
+        assert_eq!(create_empty_board().bounding_box(), None);
+
+        let mut board = create_board_with_center_card();
+        assert_eq!(
+            board.bounding_box(),
+            Some(((center, center), (center, center)))
+        );
+
+        board.0[center - 2][center + 1] = Some(Card(Suit::Clubs, Value::Two));
+        board.0[center + 1][0] = Some(Card(Suit::Hearts, Value::Three));
+        assert_eq!(
+            board.bounding_box(),
+            Some(((center - 2, 0), (center + 1, center + 1)))
+        );
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn test_occupied_area() {
+        let center = BOARD_SIZE / 2;
+        assert_eq!(
+            create_empty_board().occupied_area(),
+            (0..BOARD_SIZE, 0..BOARD_SIZE)
+        );
+
+        let mut board = create_board_with_center_card();
+        assert_eq!(
+            board.occupied_area(),
+            (center - 1..center + 2, center - 1..center + 2)
+        );
+
+        // the spots either side stop at the edges
+        board.0[0][BOARD_SIZE - 1] = Some(Card(Suit::Clubs, Value::Two));
+        assert_eq!(
+            board.occupied_area(),
+            (0..center + 2, center - 1..BOARD_SIZE)
+        );
+
+        // a gap around every card, shared between neighbours
+        assert_eq!(drawn_size(&(0..1), &(0..2)), (138, 96));
+    }
+
+    #[test]
+    fn test_playable_positions_empty_board() {
+        let center = BOARD_SIZE / 2;
+        let board = create_empty_board();
+        assert_eq!(
+            board.playable_positions(PlayContext::default()),
+            vec![(center, center)]
+        );
+
+        let center_line = board.playable_positions(PlayContext {
+            first_move: FirstMove::CenterLine,
+            ..PlayContext::default()
+        });
+        assert_eq!(center_line.len(), 2 * BOARD_SIZE - 1);
+        assert!(center_line.contains(&(center, 0)) && center_line.contains(&(0, center)));
+
+        // Once play has started, an emptied board can be played on anywhere
+        assert_eq!(
+            board
+                .playable_positions(PlayContext {
+                    started: true,
+                    ..PlayContext::default()
+                })
+                .len(),
+            BOARD_SIZE * BOARD_SIZE
+        );
+    }
+
+    #[test]
+    fn test_playable_positions() {
+        let center = BOARD_SIZE / 2;
+        let mut board = create_board_with_center_card();
+        board.0[center][center + 1] = Some(Card(Suit::Clubs, Value::Two));
+
+        // Every empty cell around the two cards, in board order
+        let mut expected = Vec::new();
+        for row in center - 1..=center + 1 {
+            for col in center - 1..=center + 2 {
+                if board.0[row][col].is_none() {
+                    expected.push((row, col));
+                }
+            }
+        }
+        assert_eq!(board.playable_positions(PlayContext::default()), expected);
+
+        // Always agrees with checking each cell, including across wrapped edges
+        board.0[0][BOARD_SIZE - 1] = Some(Card(Suit::Spades, Value::Three));
+        for wrap in [false, true] {
+            let context = PlayContext {
+                wrap,
+                started: true,
+                ..PlayContext::default()
+            };
+            let checked = (0..BOARD_SIZE)
+                .flat_map(|row| (0..BOARD_SIZE).map(move |col| (row, col)))
+                .filter(|&(row, col)| board.can_play_at(row, col, context))
+                .collect::<Vec<_>>();
+            assert_eq!(board.playable_positions(context), checked);
+        }
+    }
+
+    #[test]
+    fn test_connected_group() {
+        let center = BOARD_SIZE / 2;
+        let board = create_board_with_center_card();
+        assert_eq!(
+            board.connected_group(center, center),
+            vec![(center, center)]
+        );
+        assert!(board.connected_group(0, 0).is_empty());
+        assert!(board.connected_group(BOARD_SIZE, 0).is_empty());
+
+        // A chain running beside and diagonally from the center card, and a card two cells away
+        let mut board = create_board_with_center_card();
+        board.0[center][center + 1] = Some(Card(Suit::Clubs, Value::Two));
+        board.0[center + 1][center + 2] = Some(Card(Suit::Spades, Value::Three));
+        board.0[center - 2][center] = Some(Card(Suit::Hearts, Value::Four));
+
+        let group = vec![
+            (center, center),
+            (center, center + 1),
+            (center + 1, center + 2),
+        ];
+        assert_eq!(board.connected_group(center, center), group);
+        assert_eq!(board.connected_group(center + 1, center + 2), group);
+        assert_eq!(
+            board.connected_group(center - 2, center),
+            vec![(center - 2, center)]
+        );
+    }
+
+    #[test]
+    fn test_connected_group_wrapping() {
+        let mut board = create_empty_board();
+        board.0[0][0] = Some(Card(Suit::Spades, Value::Two));
+        board.0[BOARD_SIZE - 1][BOARD_SIZE - 1] = Some(Card(Suit::Hearts, Value::Two));
+
+        // Opposite corners only touch when the board wraps
+        assert_eq!(board.connected_group(0, 0), vec![(0, 0)]);
+        assert_eq!(
+            board.connected_group_wrapping(0, 0, true),
+            vec![(0, 0), (BOARD_SIZE - 1, BOARD_SIZE - 1)]
+        );
     }
 
     #[test]
     fn test_can_play_at_chaining() {
         let mut board = create_board_with_center_card();
+        let context = PlayContext::default();
         let center = BOARD_SIZE / 2;
 
         // Add a second card adjacent to center
@@ -312,11 +2187,11 @@ mod tests {
 
         // Now positions adjacent to the second card should be valid
         // even if they're not adjacent to the center
-        assert!(board.can_play_at(center, center + 2)); // East of second card
-        assert!(board.can_play_at(center - 1, center + 1)); // North of second card
-        assert!(board.can_play_at(center + 1, center + 1)); // South of second card
+        assert!(board.can_play_at(center, center + 2, context)); // East of second card
+        assert!(board.can_play_at(center - 1, center + 1, context)); // North of second card
+        assert!(board.can_play_at(center + 1, center + 1, context)); // South of second card
 
         // But positions not adjacent to any card should still be invalid
-        assert!(!board.can_play_at(center - 3, center - 3)); // Isolated position
+        assert!(!board.can_play_at(center - 3, center - 3, context)); // Isolated position
     }
 }
@@ -0,0 +1,88 @@
+// Copyright 2025 Justin Hu
+//
+// This file is part of Grid Online.
+//
+// Grid Online is free software: you can redistribute it and/or modify it under
+// the terms of the GNU Affero General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// Grid Online is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU Affero General Public License
+// for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with Grid Online. If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! The board drawn as an SVG image, for sharing outside the game
+
+use std::fmt::Write;
+
+use grid_common::{Board, CARD_GAP, CARD_HEIGHT, CARD_WIDTH, drawn_size};
+
+/// Draw the cards on the board, and a spot either side of them
+///
+/// An empty board is drawn whole
+pub fn render(board: &Board) -> String {
+    let (rows, cols) = board.occupied_area();
+    let (width, height) = drawn_size(&rows, &cols);
+
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="sans-serif">"#
+    );
+    for (y_index, row) in rows.enumerate() {
+        for (x_index, col) in cols.clone().enumerate() {
+            let x = x_index * (CARD_WIDTH + CARD_GAP) + CARD_GAP;
+            let y = y_index * (CARD_HEIGHT + CARD_GAP) + CARD_GAP;
+            let _ = match board.0[row][col] {
+                Some(card) => write!(
+                    svg,
+                    r##"<rect x="{x}" y="{y}" width="{CARD_WIDTH}" height="{CARD_HEIGHT}" rx="6" fill="#ffffff" stroke="#333333" stroke-width="1.5"/><text x="{}" y="{}" fill="{colour}" font-size="18" font-weight="bold">{}</text><text x="{}" y="{}" fill="{colour}" font-size="36" text-anchor="middle">{}</text>"##,
+                    x + 6,
+                    y + 20,
+                    card.1.label(),
+                    x + CARD_WIDTH / 2,
+                    y + CARD_HEIGHT * 3 / 4,
+                    card.0.symbol(),
+                    colour = card.0.colour(),
+                ),
+                None => write!(
+                    svg,
+                    r##"<rect x="{x}" y="{y}" width="{CARD_WIDTH}" height="{CARD_HEIGHT}" rx="6" fill="#f8f8f8"/>"##
+                ),
+            };
+        }
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use grid_common::{BOARD_SIZE, Card, Suit, Value};
+
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let empty = render(&Board::from_positions(&[]));
+        assert!(empty.starts_with("<svg") && empty.ends_with("</svg>"));
+        assert_eq!(empty.matches("<rect").count(), BOARD_SIZE * BOARD_SIZE);
+
+        // two cards side by side in a corner, and the spots around them
+        let board = Board::from_positions(&[
+            ((0, 0), Card(Suit::Hearts, Value::Queen)),
+            ((0, 1), Card(Suit::Clubs, Value::Ten)),
+        ]);
+        let svg = render(&board);
+        assert_eq!(svg.matches("<rect").count(), 2 * 3);
+        assert!(svg.contains(">Q</text>") && svg.contains(">♥</text>"));
+        assert!(svg.contains(">10</text>") && svg.contains(">♣</text>"));
+        assert!(svg.contains(r##"fill="#ff0000""##));
+        let (width, _) = drawn_size(&(0..2), &(0..3));
+        assert!(svg.contains(&format!(r#"width="{width}""#)));
+    }
+}
@@ -0,0 +1,206 @@
+// Copyright 2025 Justin Hu
+//
+// This file is part of Grid Online.
+//
+// Grid Online is free software: you can redistribute it and/or modify it under
+// the terms of the GNU Affero General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// Grid Online is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU Affero General Public License
+// for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with Grid Online. If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! A description of every game option, for front ends that build their option forms at runtime
+//!
+//! This is read off the options' command line definitions and serde defaults, so a new option
+//! shows up here without being listed by hand. Only the smallest values of integers are kept here
+//! by hand, since the command line doesn't know them.
+
+use clap::{Args, Command};
+use grid_common::HAND_SIZE;
+use serde::Serialize;
+
+use crate::model::GameOptions;
+
+/// Bumped when the catalog's layout changes in a way older readers would misread
+const CATALOG_VERSION: u32 = 1;
+
+/// Every game option, in the order they're listed in `--help`
+#[derive(Debug, Serialize)]
+pub struct Catalog {
+    pub version: u32,
+    pub options: Vec<OptionInfo>,
+}
+
+/// One game option
+#[derive(Debug, Serialize)]
+pub struct OptionInfo {
+    /// Key in the options JSON
+    pub name: String,
+    /// Command line flag
+    pub flag: String,
+    pub description: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: OptionKind,
+    /// Whether the option can be left unset, as null
+    pub nullable: bool,
+    /// What the option is when left out of the options JSON
+    pub default: serde_json::Value,
+    /// What the option can be, if it's a choice
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<ValueInfo>,
+    /// Smallest the option can be, if it's an integer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<u64>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OptionKind {
+    Boolean,
+    Integer,
+    Choice,
+}
+
+/// One of the values a choice can take
+#[derive(Debug, Serialize)]
+pub struct ValueInfo {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+impl Catalog {
+    pub fn build() -> Self {
+        let defaults = serde_json::to_value(
+            serde_json::from_str::<GameOptions>("{}").expect("every option should have a default"),
+        )
+        .expect("options should always serialize");
+        let command = GameOptions::augment_args(Command::new("options"));
+
+        let options = command
+            .get_arguments()
+            .filter_map(|arg| {
+                let name = arg.get_id().as_str();
+                let default = defaults.get(name)?.clone();
+                let kind = match default {
+                    serde_json::Value::Bool(_) => OptionKind::Boolean,
+                    serde_json::Value::String(_) => OptionKind::Choice,
+                    // the unset options are all counts or seeds
+                    _ => OptionKind::Integer,
+                };
+                let values = if kind == OptionKind::Choice {
+                    arg.get_possible_values()
+                        .into_iter()
+                        .map(|value| ValueInfo {
+                            name: value.get_name().to_string(),
+                            description: value.get_help().map(ToString::to_string),
+                        })
+                        .collect()
+                } else {
+                    Vec::new()
+                };
+                let min = (kind == OptionKind::Integer).then(|| minimum(name));
+                Some(OptionInfo {
+                    name: name.to_string(),
+                    flag: format!("--{}", arg.get_long()?),
+                    description: arg.get_help().map(ToString::to_string),
+                    kind,
+                    nullable: default.is_null(),
+                    default,
+                    values,
+                    min,
+                })
+            })
+            .collect();
+
+        Catalog {
+            version: CATALOG_VERSION,
+            options,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("the catalog should always serialize")
+    }
+}
+
+/// Smallest value an integer option can have
+fn minimum(name: &str) -> u64 {
+    match name {
+        "max_hand_size" => HAND_SIZE as u64,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_option_listed() {
+        let defaults =
+            serde_json::to_value(serde_json::from_str::<GameOptions>("{}").unwrap()).unwrap();
+        let catalog = Catalog::build();
+
+        let mut fields = defaults
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut listed = catalog
+            .options
+            .iter()
+            .map(|option| option.name.clone())
+            .collect::<Vec<_>>();
+        fields.sort();
+        listed.sort();
+        assert_eq!(listed, fields);
+    }
+
+    #[test]
+    fn test_option_details() {
+        let json = serde_json::from_str::<serde_json::Value>(&Catalog::build().to_json()).unwrap();
+        assert_eq!(json["version"], 1);
+        let option = |name: &str| {
+            json["options"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|option| option["name"] == name)
+                .unwrap()
+                .clone()
+        };
+
+        let wrap = option("wrap");
+        assert_eq!(wrap["flag"], "--wrap");
+        assert_eq!(wrap["type"], "boolean");
+        assert_eq!(wrap["default"], false);
+        assert!(wrap["description"].as_str().unwrap().contains("Wrap"));
+
+        let first_move = option("first_move");
+        assert_eq!(first_move["type"], "choice");
+        assert_eq!(first_move["default"], "center");
+        let values = first_move["values"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|value| value["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(values, ["center", "center-line", "anywhere"]);
+
+        let max_capture = option("max_capture");
+        assert_eq!(max_capture["type"], "integer");
+        assert_eq!(max_capture["nullable"], true);
+        assert_eq!(max_capture["default"], serde_json::Value::Null);
+        assert_eq!(max_capture["min"], 0);
+        assert_eq!(option("max_hand_size")["min"], HAND_SIZE);
+    }
+}
@@ -0,0 +1,107 @@
+// Copyright 2025 Justin Hu
+//
+// This file is part of Grid Online.
+//
+// Grid Online is free software: you can redistribute it and/or modify it under
+// the terms of the GNU Affero General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// Grid Online is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU Affero General Public License
+// for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with Grid Online. If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Committing to a deal when a game starts, and revealing it when the game ends
+//!
+//! Everyone sees the hash first, so once the deal is revealed they can check it's the one the game
+//! was dealt, and that it wasn't changed partway through.
+
+use rand::{Rng, rng};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+pub struct DealCommitment {
+    /// The text the hash is of - a random salt, so the hash doesn't give the deal away, and the deal
+    reveal: String,
+    /// SHA-256 hash of the reveal, in hex
+    hash: String,
+}
+
+impl DealCommitment {
+    pub fn new(deal: &impl Serialize) -> Self {
+        Self::with_salt(deal, rng().random())
+    }
+
+    fn with_salt(deal: &impl Serialize, salt: [u8; 16]) -> Self {
+        #[derive(Serialize)]
+        struct Reveal<'a, T> {
+            salt: String,
+            deal: &'a T,
+        }
+        let reveal = serde_json::to_string(&Reveal {
+            salt: hex(&salt),
+            deal,
+        })
+        .expect("deals should always serialize");
+        let hash = hex(&Sha256::digest(reveal.as_bytes()));
+        Self { reveal, hash }
+    }
+
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    pub fn reveal(&self) -> &str {
+        &self.reveal
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use grid_common::{Card, Suit, Value};
+
+    use super::*;
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(hex(&[0x00, 0x0f, 0xa9]), "000fa9");
+        assert_eq!(
+            hex(&Sha256::digest(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_commitment() {
+        let deal = vec![
+            Card(Suit::Clubs, Value::Two),
+            Card(Suit::Hearts, Value::Ace),
+        ];
+        let commitment = DealCommitment::with_salt(&deal, [1; 16]);
+
+        // the hash is of exactly the text revealed
+        assert_eq!(
+            commitment.hash(),
+            hex(&Sha256::digest(commitment.reveal().as_bytes()))
+        );
+        let reveal = serde_json::from_str::<serde_json::Value>(commitment.reveal()).unwrap();
+        assert_eq!(reveal["salt"], "01".repeat(16));
+        assert_eq!(reveal["deal"], serde_json::to_value(&deal).unwrap());
+
+        // a different salt hides that it's the same deal
+        assert_ne!(
+            DealCommitment::with_salt(&deal, [2; 16]).hash(),
+            commitment.hash()
+        );
+    }
+}
@@ -0,0 +1,255 @@
+// Copyright 2025 Justin Hu
+//
+// This file is part of Grid Online.
+//
+// Grid Online is free software: you can redistribute it and/or modify it under
+// the terms of the GNU Affero General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// Grid Online is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU Affero General Public License
+// for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with Grid Online. If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Settings that can be changed while the server runs, without touching a game in progress
+
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use clap::ValueEnum;
+use serde::Deserialize;
+use thiserror::Error;
+use tracing_subscriber::{Registry, filter::LevelFilter, reload};
+
+use crate::model::GameOptions;
+
+/// How much to log
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+impl Display for LogLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = self.to_possible_value().expect("no log levels are skipped");
+        f.write_str(name.get_name())
+    }
+}
+
+/// Settings that apply to the whole server, rather than to one game
+#[derive(Clone, PartialEq)]
+pub struct Config {
+    pub log_level: LogLevel,
+    pub max_conns_per_ip: Option<usize>,
+    /// Options for lobbies from now on - a game already running keeps its own
+    pub options: GameOptions,
+}
+
+/// A config file, where anything left out keeps its value from the command line
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    log_level: Option<LogLevel>,
+    max_conns_per_ip: Option<usize>,
+    options: Option<GameOptions>,
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("no config file was given")]
+    NoFile,
+    #[error("couldn't read config file: {0}")]
+    Unreadable(#[from] std::io::Error),
+    #[error("couldn't parse config file: {0}")]
+    Unparseable(#[from] serde_json::Error),
+}
+
+impl Config {
+    /// These settings, with whatever the contents of a config file set instead
+    pub fn overridden_by(&self, contents: &str) -> Result<Config, ConfigError> {
+        let file = serde_json::from_str::<ConfigFile>(contents)?;
+        Ok(Config {
+            log_level: file.log_level.unwrap_or(self.log_level),
+            max_conns_per_ip: file.max_conns_per_ip.or(self.max_conns_per_ip),
+            options: file.options.unwrap_or_else(|| self.options.clone()),
+        })
+    }
+
+    /// These settings, with whatever the config file at `path` sets instead
+    pub fn overridden_by_file(&self, path: &Path) -> Result<Config, ConfigError> {
+        self.overridden_by(&std::fs::read_to_string(path)?)
+    }
+
+    /// Descriptions of each setting that differs in `new`
+    pub fn changes(&self, new: &Config) -> Vec<String> {
+        let mut changes = Vec::new();
+        if self.log_level != new.log_level {
+            changes.push(format!("log level {} -> {}", self.log_level, new.log_level));
+        }
+        if self.max_conns_per_ip != new.max_conns_per_ip {
+            let describe =
+                |max: Option<usize>| max.map_or("unlimited".to_string(), |max| max.to_string());
+            changes.push(format!(
+                "max connections per address {} -> {}",
+                describe(self.max_conns_per_ip),
+                describe(new.max_conns_per_ip)
+            ));
+        }
+        if self.options != new.options {
+            changes.push(format!(
+                "game options for new lobbies -> {}",
+                serde_json::to_string(&new.options).expect("options should always serialize")
+            ));
+        }
+        changes
+    }
+}
+
+/// The current settings, and where to reload them from
+pub struct Reloadable {
+    /// Settings from the command line, which the config file overrides
+    base: Config,
+    path: Option<PathBuf>,
+    current: Mutex<Config>,
+    log_level: reload::Handle<LevelFilter, Registry>,
+}
+
+impl Reloadable {
+    pub fn new(
+        base: Config,
+        path: Option<PathBuf>,
+        current: Config,
+        log_level: reload::Handle<LevelFilter, Registry>,
+    ) -> Self {
+        Self {
+            base,
+            path,
+            current: Mutex::new(current),
+            log_level,
+        }
+    }
+
+    pub fn current(&self) -> Config {
+        self.current.lock().unwrap().clone()
+    }
+
+    /// Read the config file again, without applying it yet
+    pub fn read(&self) -> Result<Config, ConfigError> {
+        let path = self.path.as_ref().ok_or(ConfigError::NoFile)?;
+        self.base.overridden_by_file(path)
+    }
+
+    /// Switch to new settings, returning what changed
+    pub fn apply(&self, new: Config) -> Vec<String> {
+        let mut current = self.current.lock().unwrap();
+        let changes = current.changes(&new);
+        if current.log_level != new.log_level {
+            // only fails if the subscriber is gone, and then there's nothing to log to anyway
+            let _ = self.log_level.reload(LevelFilter::from(new.log_level));
+        }
+        *current = new;
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPTIONS: &str = r#"{
+        "sequester_cards": false,
+        "sequester_count": 1,
+        "taking_variant": "same-number",
+        "seed": null,
+        "capture_order": "shuffled",
+        "capture_placement": "bottom",
+        "peek_top": false,
+        "wrap": false,
+        "balanced_deal": false,
+        "deck_seed_from_join_code": false,
+        "reserve_card": false,
+        "deal_to_empty": false,
+        "shared_deck": false,
+        "require_capture": false,
+        "hide_own_deck": false
+    }"#;
+
+    fn base() -> Config {
+        Config {
+            log_level: LogLevel::Info,
+            max_conns_per_ip: Some(4),
+            options: serde_json::from_str(OPTIONS).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_overridden_by() {
+        let base = base();
+
+        // an empty file changes nothing
+        assert!(base.overridden_by("{}").unwrap() == base);
+
+        let wrapping = OPTIONS.replace(r#""wrap": false"#, r#""wrap": true"#);
+        let new = base
+            .overridden_by(&format!(
+                r#"{{"log_level": "debug", "max_conns_per_ip": 8, "options": {wrapping}}}"#
+            ))
+            .unwrap();
+        assert_eq!(new.log_level, LogLevel::Debug);
+        assert_eq!(new.max_conns_per_ip, Some(8));
+        assert!(new.options == serde_json::from_str(&wrapping).unwrap());
+
+        assert!(matches!(
+            base.overridden_by(r#"{"log_level": "loud"}"#),
+            Err(ConfigError::Unparseable(_))
+        ));
+        assert!(matches!(
+            base.overridden_by(r#"{"num_players": 3}"#),
+            Err(ConfigError::Unparseable(_))
+        ));
+    }
+
+    #[test]
+    fn test_changes() {
+        let base = base();
+        assert!(base.changes(&base).is_empty());
+
+        let new = Config {
+            log_level: LogLevel::Warn,
+            max_conns_per_ip: None,
+            ..base.clone()
+        };
+        assert_eq!(
+            base.changes(&new),
+            vec![
+                "log level info -> warn".to_string(),
+                "max connections per address 4 -> unlimited".to_string(),
+            ]
+        );
+    }
+}
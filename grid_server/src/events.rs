@@ -0,0 +1,171 @@
+// Copyright 2025 Justin Hu
+//
+// This file is part of Grid Online.
+//
+// Grid Online is free software: you can redistribute it and/or modify it under
+// the terms of the GNU Affero General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// Grid Online is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU Affero General Public License
+// for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with Grid Online. If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Append-only record of what happens in each game, for auditing
+
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use grid_common::Card;
+use serde::Serialize;
+use tracing::error;
+
+use crate::model::PublicGameState;
+
+pub static EVENT_LOG: EventLog = EventLog::new();
+
+/// Something worth recording about a game
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event<'a> {
+    /// A lobby opened, waiting for players
+    GameCreated {
+        num_players: usize,
+    },
+    PlayerJoined {
+        username: &'a str,
+        rejoin: bool,
+    },
+    PlayerLeft {
+        username: &'a str,
+    },
+    PlayerForfeited {
+        username: &'a str,
+    },
+    /// Players in turn order
+    GameStarted {
+        players: &'a [String],
+    },
+    Move {
+        username: &'a str,
+        card: Card,
+        location: (usize, usize),
+        /// Positions of the cards it took, including itself - empty if it took nothing
+        taken: &'a [(usize, usize)],
+    },
+    /// No winner if everyone forfeited
+    GameEnded {
+        winner: Option<&'a str>,
+        /// How the game ended up
+        state: &'a PublicGameState,
+    },
+}
+
+/// One line of the log
+#[derive(Serialize)]
+struct Record<'a> {
+    /// Milliseconds since the Unix epoch
+    time: u128,
+    /// Which game the event is from
+    join_code: &'a str,
+    #[serde(flatten)]
+    event: Event<'a>,
+}
+
+/// Events as JSON lines in a file, if the server was asked to keep them
+pub struct EventLog {
+    file: Mutex<Option<File>>,
+}
+
+impl EventLog {
+    const fn new() -> Self {
+        Self {
+            file: Mutex::new(None),
+        }
+    }
+
+    /// Start recording events, after anything already in the file at `path`
+    pub fn open(&self, path: &Path) -> std::io::Result<()> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        *self.file.lock().unwrap() = Some(file);
+        Ok(())
+    }
+
+    /// Record an event from the game with this join code, if recording events
+    pub fn record(&self, join_code: &str, event: Event) {
+        let mut file = self.file.lock().unwrap();
+        let Some(file) = file.as_mut() else {
+            return;
+        };
+
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let line = record_line(time, join_code, event);
+        // one write per line, so lines from a crash are cut short rather than interleaved
+        if let Err(err) = file.write_all(line.as_bytes()) {
+            error!(%err, "couldn't write to event log");
+        }
+    }
+}
+
+fn record_line(time: u128, join_code: &str, event: Event) -> String {
+    let record = Record {
+        time,
+        join_code,
+        event,
+    };
+    let mut line = serde_json::to_string(&record).expect("events should always serialize");
+    line.push('\n');
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use grid_common::{Suit, Value};
+
+    use super::*;
+
+    #[test]
+    fn test_record_line() {
+        assert_eq!(
+            record_line(
+                1500,
+                "abc123",
+                Event::PlayerJoined {
+                    username: "Alice",
+                    rejoin: false
+                }
+            ),
+            "{\"time\":1500,\"join_code\":\"abc123\",\"event\":\"player-joined\",\"username\":\"Alice\",\"rejoin\":false}\n"
+        );
+
+        let line = record_line(
+            1500,
+            "abc123",
+            Event::Move {
+                username: "Bob",
+                card: Card(Suit::Clubs, Value::Two),
+                location: (5, 6),
+                taken: &[(5, 5), (5, 6)],
+            },
+        );
+        let record = serde_json::from_str::<serde_json::Value>(&line).unwrap();
+        assert_eq!(record["event"], "move");
+        assert_eq!(record["join_code"], "abc123");
+        assert_eq!(record["taken"], serde_json::json!([[5, 5], [5, 6]]));
+        assert!(line.ends_with('\n') && !line.trim_end().contains('\n'));
+    }
+}
@@ -0,0 +1,64 @@
+// Copyright 2025 Justin Hu
+//
+// This file is part of Grid Online.
+//
+// Grid Online is free software: you can redistribute it and/or modify it under
+// the terms of the GNU Affero General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// Grid Online is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU Affero General Public License
+// for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with Grid Online. If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Sorting out what a client's websocket frames mean
+
+use axum::extract::ws::Message;
+
+/// A frame from a client, as far as the handler reading it cares
+#[derive(Debug, PartialEq, Eq)]
+pub enum Incoming {
+    /// A text message to act on
+    Text(String),
+    /// A ping or pong, which needs nothing from us; the websocket library answers pings itself
+    Control,
+    /// The client closed the connection, or it dropped
+    Closed,
+    /// A frame the protocol never uses
+    Unexpected,
+}
+
+/// Classify the next frame read from a connection
+pub fn classify(frame: Option<Result<Message, axum::Error>>) -> Incoming {
+    match frame {
+        Some(Ok(Message::Text(text))) => Incoming::Text(text.to_string()),
+        Some(Ok(Message::Ping(_) | Message::Pong(_))) => Incoming::Control,
+        Some(Ok(Message::Close(_))) | Some(Err(_)) | None => Incoming::Closed,
+        Some(Ok(Message::Binary(_))) => Incoming::Unexpected,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            classify(Some(Ok(Message::text("resync")))),
+            Incoming::Text("resync".to_string())
+        );
+        assert_eq!(classify(Some(Ok(Message::Close(None)))), Incoming::Closed);
+        assert_eq!(classify(None), Incoming::Closed);
+        assert_eq!(
+            classify(Some(Ok(Message::Binary(vec![1, 2, 3].into())))),
+            Incoming::Unexpected
+        );
+    }
+}
@@ -0,0 +1,203 @@
+// Copyright 2025 Justin Hu
+//
+// This file is part of Grid Online.
+//
+// Grid Online is free software: you can redistribute it and/or modify it under
+// the terms of the GNU Affero General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// Grid Online is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU Affero General Public License
+// for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with Grid Online. If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Login message parsing for Grid Online server
+
+use grid_common::{PROTOCOL, PROTOCOL_WITH_DELTAS};
+use thiserror::Error;
+
+/// Longest login message accepted, in bytes
+pub const MAX_LOGIN_LENGTH: usize = 256;
+
+/// A parsed login message
+///
+/// Sent as the first message on a connection, as newline-separated fields,
+/// optionally followed by "spectate" to watch instead of play
+#[derive(Debug, PartialEq, Eq)]
+pub struct Login<'a> {
+    pub username: &'a str,
+    pub join_code: &'a str,
+    pub spectate: bool,
+}
+
+/// What a client can handle, going by the WebSocket subprotocol agreed on when it connected
+///
+/// Clients that offer no subprotocol get the plain text protocol, with nothing extra
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Whether it can be sent deltas instead of whole states
+    pub deltas: bool,
+}
+
+impl Capabilities {
+    /// Subprotocols the server speaks, most capable first
+    pub const PROTOCOLS: [&str; 2] = [PROTOCOL_WITH_DELTAS, PROTOCOL];
+
+    pub fn from_protocol(protocol: Option<&str>) -> Self {
+        Self {
+            deltas: protocol == Some(PROTOCOL_WITH_DELTAS),
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LoginError {
+    #[error("login message is {0} bytes long, more than {max}", max = MAX_LOGIN_LENGTH)]
+    Oversized(usize),
+    #[error("login message is missing fields")]
+    MissingFields,
+    #[error("login message has unexpected extra fields")]
+    ExtraFields,
+    #[error("login message has unknown mode {0:?}")]
+    UnknownMode(String),
+    #[error("username is blank")]
+    BlankUsername,
+}
+
+pub fn parse_login(message: &str) -> Result<Login<'_>, LoginError> {
+    if message.len() > MAX_LOGIN_LENGTH {
+        return Err(LoginError::Oversized(message.len()));
+    }
+
+    let mut fields = message.split('\n');
+    let (Some(username), Some(join_code)) = (fields.next(), fields.next()) else {
+        return Err(LoginError::MissingFields);
+    };
+    let spectate = match fields.next() {
+        None => false,
+        Some("spectate") => true,
+        Some(mode) => return Err(LoginError::UnknownMode(mode.to_string())),
+    };
+    if fields.next().is_some() {
+        return Err(LoginError::ExtraFields);
+    }
+
+    Ok(Login {
+        username: validate_username(username)?,
+        join_code,
+        spectate,
+    })
+}
+
+/// Trim surrounding whitespace from a username, so names that look the same are the same
+///
+/// Names that are nothing but whitespace are rejected
+pub fn validate_username(username: &str) -> Result<&str, LoginError> {
+    let username = username.trim();
+    if username.is_empty() {
+        return Err(LoginError::BlankUsername);
+    }
+    Ok(username)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_login_valid() {
+        assert_eq!(
+            parse_login("Alice\nabc123"),
+            Ok(Login {
+                username: "Alice",
+                join_code: "abc123",
+                spectate: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_login_spectate() {
+        assert_eq!(
+            parse_login("Carol\nabc123\nspectate"),
+            Ok(Login {
+                username: "Carol",
+                join_code: "abc123",
+                spectate: true,
+            })
+        );
+        assert_eq!(
+            parse_login("Carol\nabc123\nwatch"),
+            Err(LoginError::UnknownMode("watch".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_login_missing_fields() {
+        assert_eq!(parse_login("Alice"), Err(LoginError::MissingFields));
+        assert_eq!(parse_login(""), Err(LoginError::MissingFields));
+    }
+
+    #[test]
+    fn test_parse_login_extra_fields() {
+        assert_eq!(
+            parse_login("Alice\nabc123\nspectate\nextra"),
+            Err(LoginError::ExtraFields)
+        );
+        assert_eq!(
+            parse_login("Alice\nabc123\n"),
+            Err(LoginError::UnknownMode(String::new()))
+        );
+    }
+
+    #[test]
+    fn test_parse_login_padded_username() {
+        assert_eq!(
+            parse_login(" Alice\t\nabc123"),
+            Ok(Login {
+                username: "Alice",
+                join_code: "abc123",
+                spectate: false,
+            })
+        );
+        assert_eq!(validate_username("Alice "), Ok("Alice"));
+        assert_eq!(validate_username("Bob Smith"), Ok("Bob Smith"));
+    }
+
+    #[test]
+    fn test_parse_login_blank_username() {
+        assert_eq!(parse_login("\nabc123"), Err(LoginError::BlankUsername));
+        assert_eq!(parse_login("  \t \nabc123"), Err(LoginError::BlankUsername));
+        assert_eq!(
+            validate_username("\u{3000}"),
+            Err(LoginError::BlankUsername)
+        );
+    }
+
+    #[test]
+    fn test_capabilities() {
+        assert_eq!(Capabilities::from_protocol(None), Capabilities::default());
+        assert!(!Capabilities::from_protocol(Some(PROTOCOL)).deltas);
+        assert!(Capabilities::from_protocol(Some(PROTOCOL_WITH_DELTAS)).deltas);
+        assert!(!Capabilities::from_protocol(Some("grid-online.v9")).deltas);
+    }
+
+    #[test]
+    fn test_parse_login_too_long() {
+        let message = format!("{}\nabc123", "A".repeat(MAX_LOGIN_LENGTH));
+        assert_eq!(
+            parse_login(&message),
+            Err(LoginError::Oversized(MAX_LOGIN_LENGTH + 7))
+        );
+
+        // exactly at the limit is fine
+        let message = format!("{}\nabc123", "A".repeat(MAX_LOGIN_LENGTH - 7));
+        assert!(parse_login(&message).is_ok());
+    }
+}
@@ -19,168 +19,1482 @@
 
 //! Game server for Grid Online
 
+mod board_svg;
+mod catalog;
+mod commitment;
+mod config;
+mod events;
+mod incoming;
+mod login;
+mod metrics;
 mod model;
+mod position;
+mod tournament;
 
-use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use axum::{
     Router,
     extract::{
-        ConnectInfo, State,
+        ConnectInfo, Path, State,
         ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
     },
-    response::Response,
-    routing::get,
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use clap::{CommandFactory, FromArgMatches, Parser, ValueEnum};
+use futures_util::{
+    SinkExt, StreamExt,
+    stream::{SplitSink, SplitStream},
 };
-use clap::Parser;
-use futures_util::{SinkExt, StreamExt, stream::SplitSink};
 use rand::{Rng, distr::Alphanumeric, rng, seq::SliceRandom};
+use thiserror::Error;
 use tokio::{net::TcpListener, sync::Mutex};
+use tracing::{debug, error, info, warn};
+use tracing_subscriber::{
+    filter::LevelFilter, layer::SubscriberExt, reload, util::SubscriberInitExt,
+};
+
+use crate::{
+    catalog::Catalog,
+    commitment::DealCommitment,
+    config::{Config, LogLevel, Reloadable},
+    events::{EVENT_LOG, Event},
+    incoming::{Incoming, classify},
+    login::{Capabilities, Login, parse_login},
+    metrics::METRICS,
+    model::{FirstPlayer, GameOptions, GameState, MoveError, MoveOutcome, OptionsError, Preset},
+    position::{Position, PositionError},
+    tournament::Tournament,
+};
+use grid_common::{
+    GameStateDelta, PlayerMove, PlayerVisibleGameState, REACTIONS, TournamentStandings,
+};
 
-use crate::model::{GameOptions, GameState};
-use grid_common::PlayerMove;
+/// Largest message accepted from a client, in bytes
+const MAX_MESSAGE_SIZE: usize = 4096;
+/// Longest game name allowed, in characters
+const MAX_GAME_NAME_LENGTH: usize = 40;
+/// Most moves to recap for a player rejoining a game
+const RECENT_MOVES_ON_REJOIN: usize = 8;
 
 #[derive(Parser)]
 struct Args {
     #[clap(short)]
     num_players: usize,
+    /// Fewest players to start with, if no one else joins before the countdown ends
+    #[clap(long)]
+    min_players: Option<usize>,
+    /// Seconds to wait for more players once the minimum have joined
+    #[clap(long, default_value = "30")]
+    start_countdown: u64,
+    /// Seconds to wait for a lobby to fill once someone joins, before sending everyone away
+    #[clap(long)]
+    lobby_timeout: Option<u64>,
     #[clap(short, long, default_value = "3030")]
     port: u16,
+    /// Print the opening deal for the given options and exit
+    #[clap(long)]
+    deal_report: bool,
+    /// Send players only what changed since the last state they were sent, if their client says
+    /// it can take that
+    #[clap(long)]
+    diff_broadcast: bool,
+    /// Send each game's state at most once per this many milliseconds, holding back all but the
+    /// latest of any that come quicker
+    #[clap(long)]
+    broadcast_throttle: Option<u64>,
+    /// Keep every state of the game, and send spectators the whole game so far when they join
+    #[clap(long)]
+    spectator_replay: bool,
+    /// Announce a hash of each game's deal as it starts, and reveal the deal when it ends, so
+    /// everyone can check the deal wasn't changed
+    #[clap(long)]
+    commit_deal: bool,
+    /// Seconds to hold a disconnected player's seat before they forfeit
+    #[clap(long)]
+    allow_reconnect_grace: Option<u64>,
+    /// Reply to bad or out-of-turn moves with what was wrong, instead of disconnecting the sender
+    #[clap(long)]
+    lenient: bool,
+    /// Tell players as soon as their move is applied, before the new state goes out
+    #[clap(long)]
+    confirm_moves: bool,
+    /// Most simultaneous connections allowed from one address, not counting localhost
+    #[clap(long)]
+    max_conns_per_ip: Option<usize>,
+    /// Join code to use, instead of a random one
+    #[clap(long)]
+    join_code: Option<String>,
+    /// Name shown to everyone who joins, to tell games apart
+    #[clap(long)]
+    game_name: Option<String>,
+    /// Play this many games in a row as a tournament, tallying everyone's wins
+    #[clap(long)]
+    tournament_rounds: Option<usize>,
+    /// Start every game from this position code instead of a fresh deal
+    #[clap(long)]
+    position: Option<Position>,
+    /// Format for log messages
+    #[clap(long, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+    /// Least severe log messages to show
+    #[clap(long, value_enum, default_value_t = LogLevel::Info)]
+    log_level: LogLevel,
+    /// JSON file of settings to use instead of the command line's, reread on POST to /reload
+    ///
+    /// May set log_level, max_conns_per_ip, and options - new options only apply to later lobbies
+    #[clap(long)]
+    config: Option<PathBuf>,
+    /// Append a JSON line to this file for each join, leave, move, and game start and end
+    #[clap(long)]
+    event_log: Option<PathBuf>,
+    /// Start from a named bundle of game options, which any given explicitly override
+    #[clap(long, value_enum)]
+    preset: Option<Preset>,
+    /// Who takes the first turn of each game
+    #[clap(long, value_enum, default_value_t = FirstPlayer::Random)]
+    first_player: FirstPlayer,
     #[clap(flatten)]
     options: GameOptions,
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum LogFormat {
+    /// Human-readable lines
+    Text,
+    /// JSON objects, one per line
+    Json,
+}
+
+/// Shared state for the websocket handler
+#[derive(Clone)]
+struct AppState {
+    server_state: Arc<Mutex<ServerState>>,
+    connection_counts: ConnectionCounts,
+    config: Arc<Reloadable>,
+    lenient: bool,
+    confirm_moves: bool,
+}
+
+/// Number of open connections from each address
+#[derive(Clone, Default)]
+struct ConnectionCounts(Arc<std::sync::Mutex<HashMap<IpAddr, usize>>>);
+impl ConnectionCounts {
+    /// Count a new connection from an address, unless it already has `max` connections
+    fn try_add(&self, ip: IpAddr, max: usize) -> Option<ConnectionSlot> {
+        let mut counts = self.0.lock().unwrap();
+        let count = counts.entry(ip).or_default();
+        if *count >= max {
+            return None;
+        }
+        *count += 1;
+        Some(ConnectionSlot {
+            counts: self.clone(),
+            ip,
+        })
+    }
+}
+
+/// A counted connection, which stops being counted when dropped
+struct ConnectionSlot {
+    counts: ConnectionCounts,
+    ip: IpAddr,
+}
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        let mut counts = self.counts.0.lock().unwrap();
+        if let Some(count) = counts.get_mut(&self.ip) {
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(&self.ip);
+            }
+        }
+    }
+}
+
+/// Why a request to change the game options was refused
+#[derive(Debug, Error)]
+enum ChangeOptionsError {
+    #[error("the game has already started")]
+    NotInLobby,
+    #[error("only the host can change the options")]
+    NotHost,
+    #[error("couldn't read options: {0}")]
+    Unparseable(#[from] serde_json::Error),
+    #[error("{0}")]
+    Invalid(#[from] OptionsError),
+    #[error("{0}")]
+    InvalidForPosition(#[from] PositionError),
+}
+
+/// Why a request to pause or resume the game was refused
+#[derive(Debug, Error)]
+enum PauseError {
+    #[error("the game hasn't started")]
+    NotRunning,
+    #[error("only the host can pause or resume the game")]
+    NotHost,
+    #[error("the game is already paused")]
+    AlreadyPaused,
+    #[error("the game isn't paused")]
+    NotPaused,
+}
+
+/// A server state change asked for in a state it can't happen from, which is a bug somewhere
+///
+/// Logged and left alone, so the game carries on as it was instead of the task panicking
+#[derive(Debug, Error, PartialEq, Eq)]
+enum StateError {
+    #[error("{0} needs a running game, but the server is in the lobby")]
+    InLobby(&'static str),
+    #[error("{0} needs a lobby, but a game is running")]
+    InGame(&'static str),
+    #[error("{0} isn't connected")]
+    Unconnected(String),
+}
+
+/// When a lobby that isn't full may start anyway
+#[derive(Clone, Copy)]
+struct StartPolicy {
+    min_players: usize,
+    countdown: Duration,
+    /// How long to wait for enough players before giving up, if the lobby ever does
+    lobby_timeout: Option<Duration>,
+}
+
+/// Limits how often a game's state goes out, keeping only the latest of any held back
+struct BroadcastThrottle {
+    window: Duration,
+    last_sent: Option<Instant>,
+    /// Whether a newer state is waiting for the window to pass
+    pending: bool,
+}
+
 #[expect(clippy::large_enum_variant)]
 enum ServerState {
     Lobby {
         options: GameOptions,
         num_players: usize,
         connections: HashMap<String, SplitSink<WebSocket, Message>>,
+        /// The first player to join, who may change the options
+        host: Option<String>,
+        /// Players whose client can apply deltas
+        takes_deltas: HashSet<String>,
+        /// People watching the game instead of playing
+        spectators: HashMap<String, SplitSink<WebSocket, Message>>,
         join_code: String,
+        game_name: Option<String>,
+        /// Wins so far, if playing a tournament
+        tournament: Option<Tournament>,
+        /// Where each game starts from, instead of a fresh deal
+        position: Option<Position>,
+        diff_broadcast: bool,
+        /// Shortest time between broadcasts, if throttling them
+        broadcast_throttle: Option<Duration>,
+        spectator_replay: bool,
+        commit_deal: bool,
+        first_player: FirstPlayer,
+        start_policy: StartPolicy,
+        /// When the game starts without a full table, once enough players are in
+        countdown_deadline: Option<Instant>,
+        /// When the lobby gives up on filling, once someone's waiting
+        timeout_deadline: Option<Instant>,
     },
     Running {
         game_state: GameState,
+        /// Seats at the table, which may be more than are playing
+        num_players: usize,
         connections: HashMap<String, SplitSink<WebSocket, Message>>,
+        /// People watching the game instead of playing
+        spectators: HashMap<String, SplitSink<WebSocket, Message>>,
         join_code: String,
+        game_name: Option<String>,
+        /// Wins so far, if playing a tournament
+        tournament: Option<Tournament>,
+        /// Where each game starts from, instead of a fresh deal
+        position: Option<Position>,
+        /// Last state sent to each player, if only sending deltas
+        last_sent: Option<HashMap<String, PlayerVisibleGameState>>,
+        /// Players whose client can apply deltas, who are the only ones sent them
+        takes_deltas: HashSet<String>,
+        throttle: Option<BroadcastThrottle>,
+        /// Every state of the game so far, as spectators saw it, if keeping them for replays
+        replay: Option<Vec<PlayerVisibleGameState>>,
+        /// What the deal was committed to, to reveal at the end, if committing to deals
+        deal_commitment: Option<DealCommitment>,
+        /// When each disconnected player lost their connection
+        disconnected: HashMap<String, Instant>,
+        /// Spectators a player has let see their own view, and whose view it is
+        coaching: HashMap<String, String>,
+        /// Options for the next lobby, if they were reloaded during this game
+        next_options: Option<GameOptions>,
+        /// The first player to join the lobby, who may pause the game
+        host: Option<String>,
+        started: Instant,
+        first_player: FirstPlayer,
+        start_policy: StartPolicy,
     },
 }
 impl ServerState {
     /// Converts a Lobby state into a Running state
-    ///
-    /// Panics if state is already running
-    async fn start(&mut self) {
+    async fn start(&mut self) -> Result<(), StateError> {
         match self {
             ServerState::Lobby {
                 options,
+                num_players,
                 connections,
+                spectators,
                 join_code,
+                game_name,
+                tournament,
+                position,
+                diff_broadcast,
+                broadcast_throttle,
+                spectator_replay,
+                commit_deal,
+                first_player,
+                start_policy,
+                host,
+                takes_deltas,
                 ..
             } => {
                 // Extract player names from connections
                 let mut player_names: Vec<String> = connections.keys().cloned().collect();
                 player_names.shuffle(&mut rng());
+                let no_starts = HashMap::new();
+                first_player.seat(
+                    &mut player_names,
+                    host.as_deref(),
+                    tournament.as_ref().map_or(&no_starts, Tournament::starts),
+                );
+                if let Some(tournament) = tournament {
+                    tournament.record_start(&player_names[0]);
+                }
 
                 // Create the game state with the collected players
-                let game_state = GameState::new(player_names, options.clone());
+                let options = options.for_join_code(join_code);
+                let game_state = match position {
+                    Some(position) => {
+                        let game_state = GameState::from_position(player_names, options, position);
+                        // positions come from outside, so check this one left every card in play once
+                        if let Err(err) = game_state.validate_integrity() {
+                            error!(%err, "game from position doesn't hold a whole deck");
+                        }
+                        game_state
+                    }
+                    None => GameState::new(player_names, options),
+                };
+
+                // tell everyone the seating before the first state, which only shows counts
+                let player_names = game_state.get_player_names();
+                EVENT_LOG.record(
+                    join_code,
+                    Event::GameStarted {
+                        players: &player_names,
+                    },
+                );
+                let started_message = game_started_message(&player_names);
+
+                let deal_commitment = commit_deal.then(|| {
+                    let commitment = DealCommitment::new(&game_state.opening_deal());
+                    info!(hash = commitment.hash(), "committed to the deal");
+                    commitment
+                });
+                let commitment_message = deal_commitment.as_ref().map(deal_commitment_message);
 
                 // Convert to Running state by replacing self
                 *self = ServerState::Running {
                     game_state,
+                    num_players: *num_players,
                     connections: std::mem::take(connections),
+                    spectators: std::mem::take(spectators),
                     join_code: join_code.clone(),
+                    game_name: game_name.take(),
+                    tournament: tournament.take(),
+                    position: position.take(),
+                    last_sent: diff_broadcast.then(HashMap::new),
+                    takes_deltas: std::mem::take(takes_deltas),
+                    throttle: broadcast_throttle.map(|window| BroadcastThrottle {
+                        window,
+                        last_sent: None,
+                        pending: false,
+                    }),
+                    replay: spectator_replay.then(Vec::new),
+                    deal_commitment,
+                    disconnected: HashMap::new(),
+                    coaching: HashMap::new(),
+                    next_options: None,
+                    host: host.take(),
+                    started: Instant::now(),
+                    first_player: *first_player,
+                    start_policy: *start_policy,
                 };
+                METRICS.game_started();
+
+                self.broadcast_message(started_message).await;
+                if let Some(message) = commitment_message {
+                    self.broadcast_message(message).await;
+                }
 
                 // Send game state to all players
-                self.broadcast_state().await;
-            }
-            ServerState::Running { .. } => {
-                panic!("Cannot start game: already running");
+                self.broadcast_state().await
             }
+            ServerState::Running { .. } => Err(StateError::InGame("starting a game")),
         }
     }
 
-    async fn broadcast_state(&mut self) {
+    /// Broadcast the state, or if the last one went out too recently, leave it for
+    /// flush_broadcast to send once the throttle's window has passed
+    async fn broadcast_state_throttled(&mut self) -> Result<(), StateError> {
+        if let ServerState::Running {
+            throttle: Some(throttle),
+            ..
+        } = self
+            && throttle
+                .last_sent
+                .is_some_and(|last_sent| last_sent.elapsed() < throttle.window)
+        {
+            throttle.pending = true;
+            return Ok(());
+        }
+        self.broadcast_state().await
+    }
+
+    /// Send the state the throttle held back, if there is one and its window has passed
+    async fn flush_broadcast(&mut self) -> Result<(), StateError> {
+        if let ServerState::Running {
+            throttle: Some(throttle),
+            ..
+        } = self
+            && throttle.pending
+            && throttle
+                .last_sent
+                .is_none_or(|last_sent| last_sent.elapsed() >= throttle.window)
+        {
+            self.broadcast_state().await?;
+        }
+        Ok(())
+    }
+
+    async fn broadcast_state(&mut self) -> Result<(), StateError> {
         let ServerState::Running {
             game_state,
             connections,
+            spectators,
+            last_sent,
+            takes_deltas,
+            replay,
+            coaching,
+            throttle,
             ..
         } = self
         else {
-            panic!("tried to broadcast from a non-running server");
+            return Err(StateError::InLobby("broadcasting state"));
         };
 
-        eprintln!(
-            "broadcasting state to all {} believed-connected players",
-            connections.len()
-        );
+        if let Some(throttle) = throttle {
+            throttle.last_sent = Some(Instant::now());
+            throttle.pending = false;
+        }
+
+        if let Some(replay) = replay {
+            // spectators' views only differ by name, so one copy of each state does for them all
+            let spectator_state = game_state.state_for_spectator("");
+            if replay.last() != Some(&spectator_state) {
+                replay.push(spectator_state);
+            }
+        }
+
+        info!(players = connections.len(), "broadcasting state");
 
         let mut disconnected_players = Vec::new();
 
         for (username, connection) in connections.iter_mut() {
-            let player_state = game_state.state_for(
-                game_state
-                    .get_player_names()
-                    .iter()
-                    .position(|player_username| username == player_username)
-                    .unwrap(),
-            );
-            let game_state_json = serde_json::to_string(&player_state).unwrap();
+            let Some(player_state) = game_state.state_for_player(username) else {
+                // a bug elsewhere left a connection for someone not in the game
+                warn!(username = %username, "connection isn't for a player, not sending state");
+                continue;
+            };
+            let message = match last_sent
+                .as_ref()
+                .filter(|_| takes_deltas.contains(username))
+                .and_then(|last_sent| last_sent.get(username))
+            {
+                Some(prev) => serde_json::to_string(&GameStateDelta::between(prev, &player_state))
+                    .map(|delta| format!("delta\n{delta}")),
+                None => serde_json::to_string(&player_state),
+            };
+            let message = match message {
+                Ok(message) => message,
+                Err(err) => {
+                    // they keep whatever state they had, and can resync later
+                    error!(username = %username, %err, "couldn't serialize state");
+                    continue;
+                }
+            };
+            if let Some(last_sent) = last_sent {
+                last_sent.insert(username.clone(), player_state);
+            }
 
+            if connection.send(Message::text(message)).await.is_err() {
+                disconnected_players.push(username.clone());
+            }
+        }
+
+        // Spectators always get the full state
+        let mut departed_spectators = Vec::new();
+        for (username, connection) in spectators.iter_mut() {
+            let spectator_state = spectator_state(game_state, coaching, username);
+            let game_state_json = match serde_json::to_string(&spectator_state) {
+                Ok(game_state_json) => game_state_json,
+                Err(err) => {
+                    error!(spectator = %username, %err, "couldn't serialize state");
+                    continue;
+                }
+            };
             if connection
                 .send(Message::text(game_state_json))
                 .await
                 .is_err()
             {
-                disconnected_players.push(username.clone());
+                departed_spectators.push(username.clone());
             }
         }
+        for username in &departed_spectators {
+            spectators.remove(username);
+        }
 
         // Remove disconnected players
         for username in disconnected_players {
-            self.lost_connection(&username);
+            self.lost_connection(&username)?;
+        }
+
+        if !departed_spectators.is_empty() {
+            self.broadcast_spectator_count().await;
         }
+        Ok(())
     }
 
-    fn lost_connection(&mut self, username: &str) {
-        let ServerState::Running { connections, .. } = self else {
-            panic!("tried to disconnect from an non-running server");
+    /// Send the same message to every player and spectator connected
+    async fn broadcast_message(&mut self, message: Message) {
+        let (ServerState::Lobby {
+            connections,
+            spectators,
+            ..
+        }
+        | ServerState::Running {
+            connections,
+            spectators,
+            ..
+        }) = self;
+
+        for connection in connections.values_mut().chain(spectators.values_mut()) {
+            // a failed send means the connection's closing, and whoever's on it is cleaned up
+            // after when its receive loop ends
+            let _ = connection.send(message.clone()).await;
+        }
+    }
+
+    /// Tell everyone connected how many people are watching
+    async fn broadcast_spectator_count(&mut self) {
+        let (ServerState::Lobby { spectators, .. } | ServerState::Running { spectators, .. }) =
+            self;
+        let message = spectator_count(spectators.len());
+        self.broadcast_message(message).await;
+    }
+
+    /// Pass a player's reaction on to everyone connected
+    async fn broadcast_reaction(&mut self, username: &str, reaction: &str) {
+        self.broadcast_message(Message::text(format!("react\n{username}\n{reaction}")))
+            .await;
+    }
+
+    /// Replace the lobby's game options on behalf of a player, and tell everyone waiting
+    async fn change_options(
+        &mut self,
+        username: &str,
+        options_json: &str,
+    ) -> Result<(), ChangeOptionsError> {
+        let ServerState::Lobby {
+            options,
+            num_players,
+            connections,
+            host,
+            position,
+            ..
+        } = self
+        else {
+            return Err(ChangeOptionsError::NotInLobby);
         };
-        eprintln!("disconnecting {username}");
+        if host.as_deref() != Some(username) {
+            return Err(ChangeOptionsError::NotHost);
+        }
+
+        let new_options = serde_json::from_str::<GameOptions>(options_json)?;
+        new_options.validate(*num_players)?;
+        if let Some(position) = position {
+            position.validate(&new_options, *num_players)?;
+        }
+        *options = new_options;
+
+        let message = options_message(options);
+        for connection in connections.values_mut() {
+            // dead connections are replaced when someone rejoins with their name
+            let _ = connection.send(message.clone()).await;
+        }
+        Ok(())
+    }
+
+    /// Use new options from the next lobby on, leaving a game already running alone
+    ///
+    /// A lobby switches to them straight away, and tells everyone waiting
+    async fn set_next_options(
+        &mut self,
+        new_options: GameOptions,
+    ) -> Result<(), ChangeOptionsError> {
+        match self {
+            ServerState::Lobby {
+                options,
+                num_players,
+                connections,
+                position,
+                ..
+            } => {
+                new_options.validate(*num_players)?;
+                if let Some(position) = position {
+                    position.validate(&new_options, *num_players)?;
+                }
+                *options = new_options;
+
+                let message = options_message(options);
+                for connection in connections.values_mut() {
+                    // dead connections are replaced when someone rejoins with their name
+                    let _ = connection.send(message.clone()).await;
+                }
+            }
+            ServerState::Running {
+                num_players,
+                position,
+                next_options,
+                ..
+            } => {
+                new_options.validate(*num_players)?;
+                if let Some(position) = position {
+                    position.validate(&new_options, *num_players)?;
+                }
+                *next_options = Some(new_options);
+            }
+        }
+        Ok(())
+    }
+
+    /// Pause or resume the game on behalf of a player, and tell everyone connected
+    ///
+    /// Disconnected players' time to rejoin doesn't run down while the game is paused
+    async fn set_paused(&mut self, username: &str, pause: bool) -> Result<(), PauseError> {
+        let ServerState::Running {
+            game_state,
+            disconnected,
+            host,
+            ..
+        } = self
+        else {
+            return Err(PauseError::NotRunning);
+        };
+        if host.as_deref() != Some(username) {
+            return Err(PauseError::NotHost);
+        }
+
+        let message = if pause {
+            if !game_state.pause() {
+                return Err(PauseError::AlreadyPaused);
+            }
+            paused_message(username)
+        } else {
+            let Some(paused_for) = game_state.resume() else {
+                return Err(PauseError::NotPaused);
+            };
+            for since in disconnected.values_mut() {
+                // only the part of the pause they were gone for
+                *since += paused_for.min(since.elapsed());
+            }
+            Message::text("resumed")
+        };
+        self.broadcast_message(message).await;
+        Ok(())
+    }
+
+    /// Send a message to just one player
+    async fn send_to(&mut self, username: &str, message: Message) {
+        let (ServerState::Lobby { connections, .. } | ServerState::Running { connections, .. }) =
+            self;
+        if let Some(connection) = connections.get_mut(username) {
+            // as in broadcast_message, its receive loop cleans up after a closed connection
+            let _ = connection.send(message).await;
+        }
+    }
+
+    /// Let a spectator see a player's own view, or stop them seeing it
+    ///
+    /// Spectators coach one player at a time, so being let in by another moves them over. Each
+    /// player whose coaches changed is told who's watching them now.
+    async fn set_coach(&mut self, player: &str, coach: &str, allowed: bool) {
+        let ServerState::Running {
+            game_state,
+            spectators,
+            coaching,
+            ..
+        } = self
+        else {
+            warn!(username = %player, "ignored coaching change before the game started");
+            return;
+        };
+        if allowed && !spectators.contains_key(coach) {
+            // consent goes to a person, so they have to be here to get it
+            warn!(username = %player, coach = %coach, "ignored coaching for someone not spectating");
+            return;
+        }
+
+        let previous = if allowed {
+            coaching.insert(coach.to_string(), player.to_string())
+        } else if coaching.get(coach).is_some_and(|coached| coached == player) {
+            coaching.remove(coach)
+        } else {
+            None
+        };
+        info!(username = %player, coach = %coach, allowed, "changed coaching");
+
+        // what the coach sees changes straight away, not at the next move
+        let coach_state = spectator_state(game_state, coaching, coach);
+        if let Some(connection) = spectators.get_mut(coach) {
+            match serde_json::to_string(&coach_state) {
+                Ok(game_state_json) => {
+                    let _ = connection.send(Message::text(game_state_json)).await;
+                }
+                Err(err) => error!(spectator = %coach, %err, "couldn't serialize state"),
+            }
+        }
+
+        let messages = [Some(player.to_string()), previous]
+            .into_iter()
+            .flatten()
+            .map(|player| {
+                let message = coaches_message(&coaches_of(coaching, &player));
+                (player, message)
+            })
+            .collect::<Vec<_>>();
+        for (player, message) in messages {
+            self.send_to(&player, message).await;
+        }
+    }
+
+    /// Stop sending updates to a spectator
+    async fn remove_spectator(&mut self, username: &str) {
+        let (ServerState::Lobby { spectators, .. } | ServerState::Running { spectators, .. }) =
+            self;
+        if spectators.remove(username).is_some() {
+            info!(spectator = %username, "spectator left");
+            self.broadcast_spectator_count().await;
+        }
+        self.end_coaching(username).await;
+    }
+
+    /// Take back any player's consent for this spectator to see their view
+    ///
+    /// Consent is given by name, so it ends when the person with that name leaves or someone new
+    /// arrives under it.
+    async fn end_coaching(&mut self, coach: &str) {
+        let ServerState::Running { coaching, .. } = self else {
+            return;
+        };
+        let Some(player) = coaching.remove(coach) else {
+            return;
+        };
+        info!(username = %player, coach = %coach, "ended coaching");
+        let message = coaches_message(&coaches_of(coaching, &player));
+        self.send_to(&player, message).await;
+    }
+
+    /// What a newly arrived spectator needs to catch up on the lobby or the game in progress
+    fn spectator_catch_up(&self, username: &str) -> Vec<Message> {
+        let mut messages = self.welcome_messages();
+        let ServerState::Running {
+            game_state,
+            deal_commitment,
+            replay,
+            coaching,
+            host,
+            ..
+        } = self
+        else {
+            return messages;
+        };
+
+        // the seating, as everyone already here got it when the game started
+        messages.push(game_started_message(&game_state.get_player_names()));
+        if let Some(commitment) = deal_commitment {
+            messages.push(deal_commitment_message(commitment));
+        }
+        if game_state.is_paused() {
+            messages.push(paused_message(host.as_deref().unwrap_or_default()));
+        }
+        // from the start if keeping a replay
+        if let Some(replay) = replay {
+            match serde_json::to_string(replay) {
+                Ok(replay_json) => messages.push(Message::text(format!("replay\n{replay_json}"))),
+                Err(err) => error!(spectator = %username, %err, "couldn't serialize replay"),
+            }
+        }
+        match serde_json::to_string(&spectator_state(game_state, coaching, username)) {
+            Ok(game_state_json) => messages.push(Message::text(game_state_json)),
+            Err(err) => error!(spectator = %username, %err, "couldn't serialize state"),
+        }
+        messages
+    }
+
+    /// What a player rejoining their seat needs to pick up where they left off
+    fn rejoin_catch_up(&mut self, username: &str, player_index: usize) -> Vec<Message> {
+        let mut messages = self.welcome_messages();
+        let ServerState::Running {
+            game_state,
+            spectators,
+            last_sent,
+            deal_commitment,
+            host,
+            ..
+        } = self
+        else {
+            return messages;
+        };
+
+        // the seating, as everyone already here got it when the game started
+        messages.push(game_started_message(&game_state.get_player_names()));
+        if let Some(commitment) = deal_commitment {
+            messages.push(deal_commitment_message(commitment));
+        }
+
+        // Remind them what's happened since their last move
+        let recent = game_state.moves_since_last_by(username, RECENT_MOVES_ON_REJOIN);
+        if !recent.is_empty() {
+            match serde_json::to_string(recent) {
+                Ok(recent_json) => messages.push(Message::text(format!("recent\n{recent_json}"))),
+                Err(err) => {
+                    error!(username = %username, %err, "couldn't serialize recent moves");
+                }
+            }
+        }
+
+        let player_state = game_state.state_for(player_index);
+        match serde_json::to_string(&player_state) {
+            Ok(game_state_json) => {
+                messages.push(Message::text(game_state_json));
+                if let Some(last_sent) = last_sent {
+                    last_sent.insert(username.to_string(), player_state);
+                }
+            }
+            // they can still play once they resync
+            Err(err) => error!(username = %username, %err, "couldn't serialize state"),
+        }
+        messages.push(spectator_count(spectators.len()));
+        if game_state.is_paused() {
+            messages.push(paused_message(host.as_deref().unwrap_or_default()));
+        }
+        messages
+    }
+
+    /// The game's name and tournament standings, for anyone just let in
+    fn welcome_messages(&self) -> Vec<Message> {
+        let (ServerState::Lobby {
+            game_name,
+            tournament,
+            ..
+        }
+        | ServerState::Running {
+            game_name,
+            tournament,
+            ..
+        }) = self;
+        let mut messages = Vec::new();
+        if let Some(game_name) = game_name {
+            messages.push(game_name_message(game_name));
+        }
+        if let Some(tournament) = tournament
+            && tournament.has_started()
+        {
+            messages.push(tournament_message(&tournament.standings()));
+        }
+        messages
+    }
+
+    /// Re-send the current game state to a single player
+    async fn resync(&mut self, username: &str) -> Result<(), StateError> {
+        let ServerState::Running {
+            game_state,
+            connections,
+            last_sent,
+            ..
+        } = self
+        else {
+            // nothing to resync until the game starts
+            return Ok(());
+        };
+
+        let Some(player_state) = game_state.state_for_player(username) else {
+            return Ok(());
+        };
+        let game_state_json = match serde_json::to_string(&player_state) {
+            Ok(game_state_json) => game_state_json,
+            Err(err) => {
+                error!(username = %username, %err, "couldn't serialize state");
+                return Ok(());
+            }
+        };
+        if let Some(last_sent) = last_sent {
+            last_sent.insert(username.to_string(), player_state);
+        }
+
+        if let Some(connection) = connections.get_mut(username)
+            && connection
+                .send(Message::text(game_state_json))
+                .await
+                .is_err()
+        {
+            self.lost_connection(username)?;
+        }
+        Ok(())
+    }
+
+    fn lost_connection(&mut self, username: &str) -> Result<(), StateError> {
+        let ServerState::Running {
+            connections,
+            join_code,
+            last_sent,
+            disconnected,
+            ..
+        } = self
+        else {
+            return Err(StateError::InLobby("losing a player's connection"));
+        };
+        info!(username = %username, "lost connection");
+        EVENT_LOG.record(join_code, Event::PlayerLeft { username });
         connections.remove(username);
+        disconnected.insert(username.to_string(), Instant::now());
+        if let Some(last_sent) = last_sent {
+            last_sent.remove(username);
+        }
+        Ok(())
+    }
+
+    /// Whether the player's seat is still held by the connection this stream came from
+    ///
+    /// A player who reconnected has a new connection, and the old one closing shouldn't touch it
+    fn holds_connection(&self, username: &str, recv: &SplitStream<WebSocket>) -> bool {
+        let (ServerState::Lobby { connections, .. } | ServerState::Running { connections, .. }) =
+            self;
+        connections
+            .get(username)
+            .is_some_and(|send| recv.is_pair_of(send))
+    }
+
+    /// Drop a player whose connection has gone, whether or not the game has started
+    async fn connection_closed(&mut self, username: &str) -> Result<(), StateError> {
+        match self {
+            ServerState::Lobby {
+                connections,
+                join_code,
+                start_policy,
+                countdown_deadline,
+                timeout_deadline,
+                ..
+            } => {
+                info!(username = %username, "left the lobby");
+                EVENT_LOG.record(join_code, Event::PlayerLeft { username });
+                connections.remove(username);
+                // whoever joins next gets the whole wait again
+                if connections.is_empty() {
+                    *timeout_deadline = None;
+                }
+                // too few are left to start, so the countdown begins again once enough are back
+                if connections.len() < start_policy.min_players
+                    && countdown_deadline.take().is_some()
+                {
+                    info!("below minimum players, cancelled countdown");
+                    for connection in connections.values_mut() {
+                        // failed sends get noticed when the game starts
+                        let _ = connection.send(Message::text("countdown cancelled")).await;
+                    }
+                }
+                Ok(())
+            }
+            ServerState::Running { .. } => self.lost_connection(username),
+        }
+    }
+
+    /// Send everyone waiting in the lobby away, since not enough players joined in time
+    async fn close_lobby(&mut self) -> Result<(), StateError> {
+        let ServerState::Lobby {
+            connections,
+            spectators,
+            join_code,
+            host,
+            timeout_deadline,
+            ..
+        } = self
+        else {
+            return Err(StateError::InGame("closing the lobby"));
+        };
+        for (username, mut connection) in connections.drain() {
+            let _ = connection.send(lobby_timed_out()).await;
+            EVENT_LOG.record(
+                join_code,
+                Event::PlayerLeft {
+                    username: &username,
+                },
+            );
+        }
+        for (_, mut connection) in spectators.drain() {
+            let _ = connection.send(lobby_timed_out()).await;
+        }
+        *host = None;
+        *timeout_deadline = None;
+        Ok(())
     }
 
-    async fn server_disconnect(&mut self, username: &str, reason: Message) {
+    async fn server_disconnect(
+        &mut self,
+        username: &str,
+        reason: Message,
+    ) -> Result<(), StateError> {
         let ServerState::Running { connections, .. } = self else {
-            panic!("tried to drop client from a non-running server");
+            return Err(StateError::InLobby("dropping a player"));
         };
-        let _ = connections
-            .get_mut(username)
-            .expect("should only drop connected players")
-            .send(reason)
-            .await;
-        self.lost_connection(username);
+        let Some(connection) = connections.get_mut(username) else {
+            return Err(StateError::Unconnected(username.to_string()));
+        };
+        let _ = connection.send(reason).await;
+        self.lost_connection(username)
+    }
+
+    /// Forfeit any players who have been disconnected for longer than the grace period
+    async fn forfeit_disconnected(&mut self, grace: Duration) -> Result<(), StateError> {
+        let ServerState::Running {
+            game_state,
+            disconnected,
+            host,
+            ..
+        } = self
+        else {
+            return Ok(());
+        };
+
+        // a pause stops everyone's clock but the host's, who's the only one who can end it
+        let paused = game_state.is_paused();
+        let expired = disconnected
+            .iter()
+            .filter(|(username, since)| {
+                since.elapsed() >= grace && (!paused || host.as_ref() == Some(*username))
+            })
+            .map(|(username, _)| username.clone())
+            .collect::<Vec<_>>();
+        if expired.is_empty() {
+            return Ok(());
+        }
+
+        let resumed = if paused && let Some(paused_for) = game_state.resume() {
+            // the host is gone for good, so nobody's left to resume the game
+            info!("resumed game after the host forfeited");
+            for since in disconnected.values_mut() {
+                *since += paused_for.min(since.elapsed());
+            }
+            true
+        } else {
+            false
+        };
+        if resumed {
+            self.broadcast_message(Message::text("resumed")).await;
+        }
+
+        let ServerState::Running {
+            game_state,
+            join_code,
+            disconnected,
+            ..
+        } = self
+        else {
+            return Ok(());
+        };
+        for username in expired {
+            disconnected.remove(&username);
+            if let Some(player_index) = game_state
+                .get_player_names()
+                .iter()
+                .position(|player_username| *player_username == username)
+            {
+                game_state.forfeit(player_index);
+                info!(username = %username, "forfeited after being disconnected for too long");
+                EVENT_LOG.record(
+                    join_code,
+                    Event::PlayerForfeited {
+                        username: &username,
+                    },
+                );
+            }
+        }
+
+        // the forfeits may have been all a round was waiting on
+        let moves_before = game_state.moves().len();
+        if game_state.play_round_if_ready() {
+            for last_move in &game_state.moves()[moves_before..] {
+                METRICS.move_applied();
+                EVENT_LOG.record(
+                    join_code,
+                    Event::Move {
+                        username: &last_move.player,
+                        card: last_move.card,
+                        location: last_move.location,
+                        taken: &last_move.taken,
+                    },
+                );
+            }
+            for (player, err) in game_state.take_skipped() {
+                info!(username = %player, %err, "skipped move");
+            }
+        }
+
+        if game_state.someone_has_won() {
+            match game_state.winner().map(str::to_string) {
+                Some(winner) => {
+                    info!(winner = %winner, "game won");
+                    self.finish_game(Some(&winner)).await
+                }
+                None => {
+                    info!("everyone forfeited");
+                    self.finish_game(None).await
+                }
+            }
+        } else {
+            self.broadcast_state_throttled().await
+        }
+    }
+
+    /// Show everyone the deal committed to at the start, if there was one
+    async fn reveal_deal(&mut self) {
+        let ServerState::Running {
+            deal_commitment: Some(commitment),
+            ..
+        } = self
+        else {
+            return;
+        };
+        info!(reveal = commitment.reveal(), "revealed the deal");
+        let message = Message::text(format!("deal-reveal\n{}", commitment.reveal()));
+        self.broadcast_message(message).await;
+    }
+
+    /// Tell everyone who won, if anyone did, and reset to the lobby for the next game
+    async fn finish_game(&mut self, winner: Option<&str>) -> Result<(), StateError> {
+        // before the winner, since that closes everyone's connection
+        self.reveal_deal().await;
+
+        let ServerState::Running {
+            game_state,
+            join_code,
+            started,
+            ..
+        } = self
+        else {
+            return Err(StateError::InLobby("finishing a game"));
+        };
+        METRICS.game_completed(started.elapsed());
+        EVENT_LOG.record(
+            join_code,
+            Event::GameEnded {
+                winner,
+                state: &game_state.public_snapshot(),
+            },
+        );
+
+        // the tally goes out first, so it's there for the end of game screen
+        self.record_tournament_game(winner).await;
+
+        let ServerState::Running {
+            connections,
+            spectators,
+            ..
+        } = self
+        else {
+            return Err(StateError::InLobby("finishing a game"));
+        };
+        let winner_message = end_of_game(winner);
+        for (_, mut connection) in spectators.drain() {
+            let _ = connection.send(winner_message.clone()).await;
+        }
+        let to_disconnect = connections.keys().cloned().collect::<Vec<_>>();
+
+        for username in to_disconnect {
+            self.server_disconnect(&username, winner_message.clone())
+                .await?;
+        }
+
+        // Reset server to lobby for next game
+        self.reset()
+    }
+
+    /// Count a finished game towards the tournament, if playing one, and tell everyone the tally
+    async fn record_tournament_game(&mut self, winner: Option<&str>) {
+        let ServerState::Running {
+            tournament: Some(tournament),
+            ..
+        } = self
+        else {
+            return;
+        };
+        match winner {
+            Some(winner) => tournament.record_win(winner),
+            None => tournament.record_no_winner(),
+        }
+        let standings = tournament.standings();
+        if standings.is_over() {
+            info!(
+                champion = standings.champion().unwrap_or("none - shared lead"),
+                "tournament over"
+            );
+        }
+        let message = tournament_message(&standings);
+        self.broadcast_message(message).await;
     }
 
     /// Reset from Running state back to Lobby state for next game
-    fn reset(&mut self, num_players: usize) {
+    fn reset(&mut self) -> Result<(), StateError> {
         let ServerState::Running {
             game_state,
+            num_players,
             join_code,
+            game_name,
+            tournament,
+            position,
+            last_sent,
+            throttle,
+            replay,
+            deal_commitment,
+            next_options,
+            first_player,
+            start_policy,
             ..
         } = self
         else {
-            panic!("tried to reset a non-running server to lobby");
+            return Err(StateError::InLobby("resetting to a lobby"));
         };
+        // a finished tournament starts over
+        let tournament = tournament.take().map(|tournament| {
+            if tournament.is_over() {
+                tournament.restarted()
+            } else {
+                tournament
+            }
+        });
 
+        EVENT_LOG.record(
+            join_code,
+            Event::GameCreated {
+                num_players: *num_players,
+            },
+        );
         *self = ServerState::Lobby {
-            options: game_state.get_options().clone(),
-            num_players,
+            options: next_options
+                .take()
+                .unwrap_or_else(|| game_state.get_options().clone()),
+            num_players: *num_players,
             join_code: join_code.clone(),
+            game_name: game_name.take(),
+            tournament,
+            position: position.take(),
             connections: HashMap::new(),
+            host: None,
+            takes_deltas: HashSet::new(),
+            spectators: HashMap::new(),
+            diff_broadcast: last_sent.is_some(),
+            broadcast_throttle: throttle.as_ref().map(|throttle| throttle.window),
+            spectator_replay: replay.is_some(),
+            commit_deal: deal_commitment.is_some(),
+            first_player: *first_player,
+            start_policy: *start_policy,
+            countdown_deadline: None,
+            timeout_deadline: None,
         };
+        Ok(())
+    }
+}
+
+/// Deal a game without starting the server and print each player's opening hand and deck size
+///
+/// The deal is the one a game with this join code would get, if seeding from it
+fn print_deal_report(num_players: usize, options: &GameOptions, join_code: &str) {
+    let player_names = (1..=num_players)
+        .map(|n| format!("Player {n}"))
+        .collect::<Vec<_>>();
+    let game_state = GameState::new(player_names, options.for_join_code(join_code));
+
+    let mut dealt = 0;
+    for (name, player_state) in game_state.get_players() {
+        let hand = player_state
+            .hand()
+            .0
+            .iter()
+            .map(|card| card.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let deck_size = player_state.deck().0.len();
+        match player_state.reserve() {
+            Some(reserve) => {
+                println!("{name}: reserve {reserve}, hand {hand} ({deck_size} in deck)");
+                dealt += 1;
+            }
+            None => println!("{name}: hand {hand} ({deck_size} in deck)"),
+        }
+        dealt += player_state.hand().0.len() + deck_size;
+    }
+    // the shared deck is drawn from during the game, so it isn't held back
+    let held_back = game_state.out_of_play().len();
+    match game_state.shared_deck().len() {
+        0 => println!("{dealt} cards dealt, {held_back} held back"),
+        shared => {
+            println!("{dealt} cards dealt, {shared} in the shared deck, {held_back} held back")
+        }
+    }
+}
+
+fn end_of_game(winner: Option<&str>) -> Message {
+    let reason = match winner {
+        Some(winner) => format!("player won\n{winner}"),
+        None => "no winner".to_string(),
+    };
+    Message::Close(Some(CloseFrame {
+        code: 4000,
+        reason: reason.into(),
+    }))
+}
+
+fn lobby_timed_out() -> Message {
+    Message::Close(Some(CloseFrame {
+        code: 4004,
+        reason: "not enough players joined".into(),
+    }))
+}
+
+fn too_many_connections() -> Message {
+    Message::Close(Some(CloseFrame {
+        code: 4003,
+        reason: "too many connections".into(),
+    }))
+}
+
+/// What a spectator sees - the view of the player they're coaching, if they're still playing
+fn spectator_state(
+    game_state: &GameState,
+    coaching: &HashMap<String, String>,
+    spectator: &str,
+) -> PlayerVisibleGameState {
+    coaching
+        .get(spectator)
+        .and_then(|player| game_state.state_for_player(player))
+        .unwrap_or_else(|| game_state.state_for_spectator(spectator))
+}
+
+/// Who a player has let see their view, in name order
+fn coaches_of<'a>(coaching: &'a HashMap<String, String>, player: &str) -> Vec<&'a str> {
+    let mut coaches = coaching
+        .iter()
+        .filter(|(_, coached)| *coached == player)
+        .map(|(coach, _)| coach.as_str())
+        .collect::<Vec<_>>();
+    coaches.sort();
+    coaches
+}
+
+fn coaches_message(coaches: &[&str]) -> Message {
+    let coaches_json =
+        serde_json::to_string(coaches).expect("should always be able to serialize names");
+    Message::text(format!("coaches\n{coaches_json}"))
+}
+
+fn spectator_count(count: usize) -> Message {
+    Message::text(format!("spectators\n{count}"))
+}
+
+fn paused_message(host: &str) -> Message {
+    Message::text(format!("paused\n{host}"))
+}
+
+fn countdown_message(deadline: Instant) -> Message {
+    let seconds = deadline.saturating_duration_since(Instant::now()).as_secs();
+    Message::text(format!("countdown\n{seconds}"))
+}
+
+/// Announce the players' seats, in turn order
+fn game_started_message(players: &[String]) -> Message {
+    let players_json =
+        serde_json::to_string(players).expect("should always be able to serialize names");
+    Message::text(format!("game-started\n{players_json}"))
+}
+
+fn deal_commitment_message(commitment: &DealCommitment) -> Message {
+    Message::text(format!("deal-commitment\n{}", commitment.hash()))
+}
+
+fn options_message(options: &GameOptions) -> Message {
+    let options_json =
+        serde_json::to_string(options).expect("should always be able to serialize options");
+    Message::text(format!("options\n{options_json}"))
+}
+
+/// Tell a player their move was ignored, and why
+///
+/// Servers confirming moves say which kind of message was turned down
+fn move_rejected(reason: &str, confirm_moves: bool) -> Message {
+    if confirm_moves {
+        Message::text(format!("move-rejected\n{reason}"))
+    } else {
+        Message::text(format!("rejected\n{reason}"))
     }
 }
 
+/// Tell a player their move was applied, and how many cards it took
+fn move_ok(captured: usize) -> Message {
+    Message::text(format!("move-ok\n{captured}"))
+}
+
+fn tournament_message(standings: &TournamentStandings) -> Message {
+    let standings_json =
+        serde_json::to_string(standings).expect("should always be able to serialize standings");
+    Message::text(format!("tournament\n{standings_json}"))
+}
+
+fn game_name_message(name: &str) -> Message {
+    Message::text(format!("name\n{name}"))
+}
+
+/// Strip control characters and surrounding whitespace from a game name
+///
+/// None if there's nothing left, or too much
+fn clean_game_name(name: &str) -> Option<String> {
+    let name = name
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect::<String>()
+        .trim()
+        .to_string();
+    (!name.is_empty() && name.chars().count() <= MAX_GAME_NAME_LENGTH).then_some(name)
+}
+
 fn generate_join_code() -> String {
     (0..16)
         .map(|_| rng().sample(Alphanumeric) as char)
@@ -189,7 +1503,36 @@ fn generate_join_code() -> String {
 
 #[tokio::main]
 async fn main() {
-    let args = Args::parse();
+    let matches = Args::command()
+        .subcommand(
+            clap::Command::new("describe-options")
+                .about("Print every game option as JSON, with its type, values, and default"),
+        )
+        .subcommand_negates_reqs(true)
+        .get_matches();
+    if matches.subcommand_name() == Some("describe-options") {
+        println!("{}", Catalog::build().to_json());
+        return;
+    }
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    if let Some(preset) = args.preset {
+        args.options.apply_preset(preset, &matches);
+    }
+    let base_config = Config {
+        log_level: args.log_level,
+        max_conns_per_ip: args.max_conns_per_ip,
+        options: args.options.clone(),
+    };
+    let config = match &args.config {
+        Some(path) => match base_config.overridden_by_file(path) {
+            Ok(config) => config,
+            Err(err) => {
+                eprintln!("error: {err}");
+                return;
+            }
+        },
+        None => base_config.clone(),
+    };
     if !(2..=4).contains(&args.num_players) {
         eprintln!(
             "error: must have between 2 and 4 players, had {}",
@@ -197,21 +1540,149 @@ async fn main() {
         );
         return;
     }
+    let min_players = args.min_players.unwrap_or(args.num_players);
+    if !(2..=args.num_players).contains(&min_players) {
+        eprintln!(
+            "error: minimum players must be between 2 and {}, had {min_players}",
+            args.num_players
+        );
+        return;
+    }
+    if let Err(err) = config.options.validate(args.num_players) {
+        eprintln!("error: {err}");
+        return;
+    }
+
+    if let Some(join_code) = &args.join_code
+        && (join_code.is_empty() || join_code.contains('\n'))
+    {
+        eprintln!("error: join code must be one non-empty line");
+        return;
+    }
+
+    if args.broadcast_throttle == Some(0) {
+        eprintln!("error: broadcast throttle must be at least 1 millisecond");
+        return;
+    }
+
+    if args.lobby_timeout == Some(0) {
+        eprintln!("error: lobby timeout must be at least 1 second");
+        return;
+    }
+
+    if args.tournament_rounds == Some(0) {
+        eprintln!("error: a tournament must have at least one round");
+        return;
+    }
+    if args.first_player == FirstPlayer::Rotate && args.tournament_rounds.is_none() {
+        eprintln!("error: rotating who goes first needs a tournament to rotate over");
+        return;
+    }
+
+    // the game may start with anywhere from the minimum to a full table
+    if let Some(position) = &args.position
+        && let Err(err) = (min_players..=args.num_players)
+            .try_for_each(|num_players| position.validate(&config.options, num_players))
+    {
+        eprintln!("error: {err}");
+        return;
+    }
+
+    let game_name = match args.game_name.as_deref().map(clean_game_name) {
+        Some(None) => {
+            eprintln!("error: game name must be between 1 and {MAX_GAME_NAME_LENGTH} characters");
+            return;
+        }
+        game_name => game_name.flatten(),
+    };
+
+    let join_code = args.join_code.unwrap_or_else(generate_join_code);
+    if args.deal_report {
+        print_deal_report(args.num_players, &config.options, &join_code);
+        return;
+    }
+
+    let (log_level, log_level_handle) = reload::Layer::new(LevelFilter::from(config.log_level));
+    let logs = tracing_subscriber::registry().with(log_level);
+    let format = tracing_subscriber::fmt::layer().with_writer(std::io::stderr);
+    match args.log_format {
+        LogFormat::Text => logs.with(format).init(),
+        LogFormat::Json => logs.with(format.json()).init(),
+    }
 
     println!("Grid Online server version {}", env!("CARGO_PKG_VERSION"));
 
-    let join_code = generate_join_code();
+    if let Some(path) = &args.event_log
+        && let Err(err) = EVENT_LOG.open(path)
+    {
+        eprintln!("error: couldn't open event log: {err}");
+        return;
+    }
+
     println!("Join code: {join_code}");
+    EVENT_LOG.record(
+        &join_code,
+        Event::GameCreated {
+            num_players: args.num_players,
+        },
+    );
     let server_state = Arc::new(Mutex::new(ServerState::Lobby {
-        options: args.options,
+        options: config.options.clone(),
         num_players: args.num_players,
         join_code,
+        game_name,
+        tournament: args.tournament_rounds.map(Tournament::new),
+        position: args.position,
         connections: HashMap::new(),
+        host: None,
+        takes_deltas: HashSet::new(),
+        spectators: HashMap::new(),
+        diff_broadcast: args.diff_broadcast,
+        broadcast_throttle: args.broadcast_throttle.map(Duration::from_millis),
+        spectator_replay: args.spectator_replay,
+        commit_deal: args.commit_deal,
+        first_player: args.first_player,
+        start_policy: StartPolicy {
+            min_players,
+            countdown: Duration::from_secs(args.start_countdown),
+            lobby_timeout: args.lobby_timeout.map(Duration::from_secs),
+        },
+        countdown_deadline: None,
+        timeout_deadline: None,
     }));
 
+    if let Some(window) = args.broadcast_throttle {
+        tokio::spawn(flush_broadcasts(
+            server_state.clone(),
+            Duration::from_millis(window),
+        ));
+    }
+
+    if let Some(grace) = args.allow_reconnect_grace {
+        tokio::spawn(forfeit_disconnected(
+            server_state.clone(),
+            Duration::from_secs(grace),
+        ));
+    }
+
     let app = Router::new()
         .route("/", get(websocket_handler))
-        .with_state(server_state);
+        .route("/metrics", get(metrics_handler))
+        .route("/options", get(options_handler))
+        .route("/game/{code}/board.svg", get(board_svg_handler))
+        .route("/reload", post(reload_handler))
+        .with_state(AppState {
+            server_state,
+            connection_counts: ConnectionCounts::default(),
+            config: Arc::new(Reloadable::new(
+                base_config,
+                args.config,
+                config,
+                log_level_handle,
+            )),
+            lenient: args.lenient,
+            confirm_moves: args.confirm_moves,
+        });
 
     let addr = SocketAddr::from(([0, 0, 0, 0], args.port));
     println!("Starting WebSocket server on ws://{}", addr);
@@ -225,64 +1696,286 @@ async fn main() {
     .unwrap();
 }
 
+/// Start the game once the countdown ends, unless it already has
+async fn start_after_countdown(state: Arc<Mutex<ServerState>>, countdown: Duration) {
+    tokio::time::sleep(countdown).await;
+
+    let mut state_guard = state.lock().await;
+    let ServerState::Lobby {
+        connections,
+        start_policy,
+        countdown_deadline: Some(deadline),
+        ..
+    } = &*state_guard
+    else {
+        return;
+    };
+    // a countdown from an earlier lobby won't have reached this lobby's deadline
+    if *deadline > Instant::now() || connections.len() < start_policy.min_players {
+        return;
+    }
+    if let Err(err) = state_guard.start().await {
+        error!(%err, "couldn't start after countdown");
+        return;
+    }
+    info!("game starting after countdown");
+}
+
+/// Close the lobby once it's waited too long to fill, unless it's started or emptied since
+async fn close_idle_lobby(state: Arc<Mutex<ServerState>>, timeout: Duration) {
+    tokio::time::sleep(timeout).await;
+
+    let mut state_guard = loop {
+        let state_guard = state.lock().await;
+        let ServerState::Lobby {
+            countdown_deadline,
+            timeout_deadline: Some(deadline),
+            ..
+        } = &*state_guard
+        else {
+            return;
+        };
+        // a timer from an earlier wait won't have reached this one's deadline
+        if *deadline > Instant::now() {
+            return;
+        }
+        // a game about to start anyway is worth letting start, but the countdown may be called
+        // off if players leave
+        let Some(countdown_deadline) = *countdown_deadline else {
+            break state_guard;
+        };
+        drop(state_guard);
+        tokio::time::sleep_until((countdown_deadline + Duration::from_secs(1)).into()).await;
+    };
+    if let Err(err) = state_guard.close_lobby().await {
+        error!(%err, "couldn't close idle lobby");
+        return;
+    }
+    info!("lobby closed after waiting too long for players");
+}
+
+/// Periodically forfeit players who haven't reconnected in time
+async fn forfeit_disconnected(state: Arc<Mutex<ServerState>>, grace: Duration) {
+    loop {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        if let Err(err) = state.lock().await.forfeit_disconnected(grace).await {
+            error!(%err, "couldn't forfeit disconnected players");
+        }
+    }
+}
+
+/// Periodically send any states the broadcast throttle held back
+async fn flush_broadcasts(state: Arc<Mutex<ServerState>>, window: Duration) {
+    loop {
+        tokio::time::sleep(window).await;
+        if let Err(err) = state.lock().await.flush_broadcast().await {
+            error!(%err, "couldn't send held back state");
+        }
+    }
+}
+
 async fn websocket_handler(
     ws: WebSocketUpgrade,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    State(state): State<Arc<Mutex<ServerState>>>,
+    State(app_state): State<AppState>,
+) -> Response {
+    info!(%addr, "new connection");
+
+    // localhost is exempt, for testing
+    let slot = match app_state.config.current().max_conns_per_ip {
+        Some(max) if !addr.ip().is_loopback() => {
+            match app_state.connection_counts.try_add(addr.ip(), max) {
+                Some(slot) => Some(slot),
+                None => {
+                    warn!(%addr, "rejected connection - too many connections");
+                    return ws.on_upgrade(|mut socket| async move {
+                        let _ = socket.send(too_many_connections()).await;
+                    });
+                }
+            }
+        }
+        _ => None,
+    };
+
+    let state = app_state.server_state;
+    let lenient = app_state.lenient;
+    let confirm_moves = app_state.confirm_moves;
+    // clients that don't offer a subprotocol we know get the plain one
+    let ws = ws.protocols(Capabilities::PROTOCOLS);
+    let capabilities = Capabilities::from_protocol(
+        ws.selected_protocol()
+            .and_then(|protocol| protocol.to_str().ok()),
+    );
+    ws.max_message_size(MAX_MESSAGE_SIZE)
+        .on_upgrade(move |socket| async move {
+            let connection = METRICS.connection_opened();
+            handle_websocket(socket, state, capabilities, lenient, confirm_moves).await;
+            drop(connection);
+            drop(slot);
+        })
+}
+
+async fn metrics_handler() -> String {
+    METRICS.render()
+}
+
+/// Every game option, with its type, values, and default, for building an options form
+async fn options_handler() -> Response {
+    (
+        [(header::CONTENT_TYPE, "application/json")],
+        Catalog::build().to_json(),
+    )
+        .into_response()
+}
+
+/// The board of the game with this join code, as an image to share
+///
+/// The join code is all it takes to watch the game, so it's all it takes to see the board too
+async fn board_svg_handler(
+    Path(code): Path<String>,
+    State(app_state): State<AppState>,
 ) -> Response {
-    eprintln!("New WebSocket connection established from {}", addr);
-    ws.on_upgrade(move |socket| handle_websocket(socket, state))
+    match &*app_state.server_state.lock().await {
+        ServerState::Running {
+            game_state,
+            join_code,
+            ..
+        } if *join_code == code => (
+            [(header::CONTENT_TYPE, "image/svg+xml")],
+            board_svg::render(game_state.board()),
+        )
+            .into_response(),
+        _ => StatusCode::NOT_FOUND.into_response(),
+    }
 }
 
-async fn handle_websocket(socket: WebSocket, state: Arc<Mutex<ServerState>>) {
+/// Reread the config file, and report what changed
+///
+/// Only allowed from localhost, since anyone who can reach the server can reach this
+async fn reload_handler(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(app_state): State<AppState>,
+) -> (StatusCode, String) {
+    if !addr.ip().is_loopback() {
+        warn!(%addr, "rejected config reload from outside localhost");
+        return (
+            StatusCode::FORBIDDEN,
+            "config can only be reloaded from localhost\n".to_string(),
+        );
+    }
+
+    let new = match app_state.config.read() {
+        Ok(new) => new,
+        Err(err) => {
+            error!(%err, "couldn't reload config");
+            return (StatusCode::BAD_REQUEST, format!("{err}\n"));
+        }
+    };
+    // check the options fit this server before changing anything
+    if new.options != app_state.config.current().options
+        && let Err(err) = app_state
+            .server_state
+            .lock()
+            .await
+            .set_next_options(new.options.clone())
+            .await
+    {
+        error!(%err, "couldn't reload config");
+        return (StatusCode::BAD_REQUEST, format!("{err}\n"));
+    }
+
+    let changes = app_state.config.apply(new);
+    if changes.is_empty() {
+        info!("reloaded config - nothing changed");
+    }
+    for change in &changes {
+        info!(change, "reloaded config");
+    }
+    (
+        StatusCode::OK,
+        changes.iter().map(|change| format!("{change}\n")).collect(),
+    )
+}
+
+async fn handle_websocket(
+    socket: WebSocket,
+    state: Arc<Mutex<ServerState>>,
+    capabilities: Capabilities,
+    lenient: bool,
+    confirm_moves: bool,
+) {
     let protocol_error = Message::Close(Some(CloseFrame {
         code: 4002,
         reason: "protocol error".into(),
     }));
 
-    fn end_of_game(winner: &str) -> Message {
-        Message::Close(Some(CloseFrame {
-            code: 4000,
-            reason: format!("player won\n{winner}").into(),
-        }))
-    }
-
     let (mut send, mut recv) = socket.split();
 
     // Wait for login message, skipping any ping/pong messages
     let login = loop {
-        match recv.next().await {
-            Some(Ok(Message::Text(text))) => break text,
-            Some(Ok(Message::Ping(_))) => continue,
-            Some(Ok(Message::Pong(_))) => continue,
-            _ => {
+        match classify(recv.next().await) {
+            Incoming::Text(text) => break text,
+            Incoming::Control => continue,
+            Incoming::Closed => return,
+            Incoming::Unexpected => {
                 let _ = send.send(protocol_error).await;
                 return;
             }
         }
-    };
-    let login = login.split('\n').collect::<Vec<_>>();
-    let [username, attempt_join_code] = *login.as_slice() else {
-        let _ = send.send(protocol_error).await;
+    };
+    let Login {
+        username,
+        join_code: attempt_join_code,
+        spectate,
+    } = match parse_login(&login) {
+        Ok(login) => login,
+        Err(err) => {
+            let _ = send.send(protocol_error).await;
+            warn!(reason = %err, "disconnected for protocol error");
+            return;
+        }
+    };
+
+    if spectate {
+        // a spectator under a player's name could be mistaken for them
+        let seated = match &*state.lock().await {
+            ServerState::Lobby { connections, .. } => connections.contains_key(username),
+            ServerState::Running { game_state, .. } => game_state.seat_of(username).is_some(),
+        };
+        if seated {
+            let _ = send.send(Message::text("username taken")).await;
+            info!(spectator = %username, "rejected - name of a player");
+            return;
+        }
+        handle_spectator(send, recv, username, attempt_join_code, state).await;
         return;
-    };
+    }
 
     // login flow
     let mut state_guard = state.lock().await;
     match &mut *state_guard {
         ServerState::Lobby {
+            options,
             num_players,
             connections,
+            host,
+            spectators,
             join_code,
+            game_name,
+            tournament,
+            start_policy,
+            countdown_deadline,
+            timeout_deadline,
+            takes_deltas,
             ..
         } => {
-            eprintln!("{username:?} trying to join new game with code {attempt_join_code:?}");
+            info!(username = %username, join_code = %attempt_join_code, "joining new game");
 
             // check join code
             if join_code != attempt_join_code {
                 drop(state_guard);
                 let _ = send.send(Message::text("join code")).await;
-                eprintln!("{username:?} rejected - bad join code");
+                info!(username = %username, "rejected - bad join code");
                 return;
             }
 
@@ -290,7 +1983,7 @@ async fn handle_websocket(socket: WebSocket, state: Arc<Mutex<ServerState>>) {
             if connections.len() >= *num_players {
                 drop(state_guard);
                 let _ = send.send(Message::text("game full")).await;
-                eprintln!("{username:?} rejected - game full");
+                info!(username = %username, "rejected - game full");
                 return;
             }
 
@@ -303,9 +1996,7 @@ async fn handle_websocket(socket: WebSocket, state: Arc<Mutex<ServerState>>) {
             {
                 drop(state_guard);
                 let _ = send.send(Message::text("username taken")).await;
-                eprintln!(
-                    "{username:?} rejected - there is an existing connection for that username"
-                );
+                info!(username = %username, "rejected - username taken");
                 return;
             }
 
@@ -313,37 +2004,103 @@ async fn handle_websocket(socket: WebSocket, state: Arc<Mutex<ServerState>>) {
             if send.send(Message::text("ok")).await.is_err() {
                 return;
             }
+            if send.send(spectator_count(spectators.len())).await.is_err() {
+                return;
+            }
+            if let Some(game_name) = game_name
+                && send.send(game_name_message(game_name)).await.is_err()
+            {
+                return;
+            }
+            if let Some(tournament) = tournament
+                && tournament.has_started()
+                && send
+                    .send(tournament_message(&tournament.standings()))
+                    .await
+                    .is_err()
+            {
+                return;
+            }
+            if send.send(options_message(options)).await.is_err() {
+                return;
+            }
+            if let Some(deadline) = countdown_deadline
+                && send.send(countdown_message(*deadline)).await.is_err()
+            {
+                return;
+            }
 
             // Add player to connections
             connections.insert(username.to_string(), send);
+            if capabilities.deltas {
+                takes_deltas.insert(username.to_string());
+            } else {
+                takes_deltas.remove(username);
+            }
+            EVENT_LOG.record(
+                join_code,
+                Event::PlayerJoined {
+                    username,
+                    rejoin: false,
+                },
+            );
+            if host.is_none() {
+                info!(username = %username, "hosting");
+                *host = Some(username.to_string());
+            }
+            if let Some(timeout) = start_policy.lobby_timeout
+                && timeout_deadline.is_none()
+            {
+                *timeout_deadline = Some(Instant::now() + timeout);
+                tokio::spawn(close_idle_lobby(state.clone(), timeout));
+            }
 
             // If game is full, start it
             if connections.len() == *num_players {
-                state_guard.start().await;
-                eprintln!("game starting");
+                if let Err(err) = state_guard.start().await {
+                    error!(%err, "couldn't start full game");
+                    return;
+                }
+                info!("game starting");
+            } else if connections.len() >= start_policy.min_players && countdown_deadline.is_none()
+            {
+                // Otherwise, give everyone else a chance to join before starting anyway
+                let deadline = Instant::now() + start_policy.countdown;
+                *countdown_deadline = Some(deadline);
+                let message = countdown_message(deadline);
+                for connection in connections.values_mut() {
+                    let _ = connection.send(message.clone()).await;
+                }
+                tokio::spawn(start_after_countdown(state.clone(), start_policy.countdown));
+                info!(
+                    seconds = start_policy.countdown.as_secs(),
+                    "minimum players reached, starting countdown"
+                );
             }
         }
         ServerState::Running {
             game_state,
             connections,
             join_code,
+            last_sent,
+            disconnected,
+            ..
         } => {
-            eprintln!("{username:?} trying to join existing game with code {attempt_join_code:?}");
+            info!(username = %username, join_code = %attempt_join_code, "rejoining game");
 
             // Check join code
             if join_code != attempt_join_code {
                 drop(state_guard);
                 let _ = send.send(Message::text("join code")).await;
-                eprintln!("{username:?} rejected - bad join code");
+                info!(username = %username, "rejected - bad join code");
                 return;
             }
 
             // Check if username is already in the game
-            let player_names = game_state.get_player_names();
-            let Some(player_index) = player_names.iter().position(|name| name == username) else {
+            let Some(player_index) = game_state.seat_of(username) else {
                 drop(state_guard);
                 let _ = send.send(Message::text("full")).await;
-                eprintln!("{username:?} rejected - game full");
+                info!(username = %username, "rejected - game full");
                 return;
             };
 
@@ -356,26 +2113,58 @@ async fn handle_websocket(socket: WebSocket, state: Arc<Mutex<ServerState>>) {
             {
                 drop(state_guard);
                 let _ = send.send(Message::text("username")).await;
-                eprintln!(
-                    "{username:?} rejected - there is an existing connection for that username"
-                );
+                info!(username = %username, "rejected - username taken");
                 return;
             }
 
-            // Send ok response
-            if send.send(Message::text("ok")).await.is_err() {
+            // Players with no cards left will never get another turn, so they can only watch
+            if !game_state.get_players()[player_index].1.has_cards() {
+                connections.remove(username);
+                disconnected.remove(username);
+                if let Some(last_sent) = last_sent {
+                    last_sent.remove(username);
+                }
+                drop(state_guard);
+                info!(username = %username, "eliminated player rejoining as a spectator");
+                handle_spectator(send, recv, username, attempt_join_code, state).await;
                 return;
             }
 
-            // Send current game state to the reconnecting player
-            let player_state = game_state.state_for(player_index);
-            let game_state_json = serde_json::to_string(&player_state).unwrap();
-            if send.send(Message::text(game_state_json)).await.is_err() {
+            // Send ok response
+            if send.send(Message::text("ok")).await.is_err() {
                 return;
             }
+            for message in state_guard.rejoin_catch_up(username, player_index) {
+                if send.send(message).await.is_err() {
+                    return;
+                }
+            }
 
-            // Add player to connections
+            let ServerState::Running {
+                connections,
+                takes_deltas,
+                disconnected,
+                join_code,
+                ..
+            } = &mut *state_guard
+            else {
+                unreachable!("still running, since the lock is held");
+            };
+            // Add player to connections, holding their seat again, with whatever this client takes
             connections.insert(username.to_string(), send);
+            if capabilities.deltas {
+                takes_deltas.insert(username.to_string());
+            } else {
+                takes_deltas.remove(username);
+            }
+            disconnected.remove(username);
+            EVENT_LOG.record(
+                join_code,
+                Event::PlayerJoined {
+                    username,
+                    rejoin: true,
+                },
+            );
         }
     };
     drop(state_guard);
@@ -383,81 +2172,853 @@ async fn handle_websocket(socket: WebSocket, state: Arc<Mutex<ServerState>>) {
     // gameplay flow
     loop {
         // get a move
-        let text = match recv.next().await {
-            Some(Ok(Message::Text(text))) => text,
-            Some(Ok(Message::Ping(_))) => continue,
-            Some(Ok(Message::Pong(_))) => continue,
-            _ => {
-                state
-                    .lock()
-                    .await
-                    .server_disconnect(username, protocol_error)
-                    .await;
-                eprintln!(
-                    "disconnected {username:?} for sending a bad message and/or disconnecting"
-                );
+        let text = match classify(recv.next().await) {
+            Incoming::Text(text) => text,
+            Incoming::Control => continue,
+            Incoming::Closed => {
+                let mut state_guard = state.lock().await;
+                if state_guard.holds_connection(username, &recv)
+                    && let Err(err) = state_guard.connection_closed(username).await
+                {
+                    error!(username = %username, %err, "couldn't drop closed connection");
+                }
+                return;
+            }
+            Incoming::Unexpected => {
+                let mut state_guard = state.lock().await;
+                if state_guard.holds_connection(username, &recv) {
+                    state_guard.send_to(username, protocol_error).await;
+                    if let Err(err) = state_guard.connection_closed(username).await {
+                        error!(username = %username, %err, "couldn't drop closed connection");
+                    }
+                    warn!(
+                        username = %username,
+                        reason = "unexpected frame",
+                        "disconnected for protocol error"
+                    );
+                }
                 return;
             }
         };
 
-        // check if it's the current player's turn
         let mut state_guard = state.lock().await;
+
+        // resync requests may come from any player at any time
+        if text == "resync" {
+            if let Err(err) = state_guard.resync(username).await {
+                error!(username = %username, %err, "couldn't resync");
+                continue;
+            }
+            info!(username = %username, "resynced");
+            continue;
+        }
+
+        // as may reactions
+        if let Some(reaction) = text.strip_prefix("react\n") {
+            if REACTIONS.contains(&reaction) {
+                state_guard.broadcast_reaction(username, reaction).await;
+            } else {
+                warn!(username = %username, reaction, "ignored unknown reaction");
+            }
+            continue;
+        }
+
+        // the host may change the options before the game starts
+        if let Some(options_json) = text.strip_prefix("options\n") {
+            match state_guard.change_options(username, options_json).await {
+                Ok(()) => info!(username = %username, "changed options"),
+                Err(err) => {
+                    warn!(username = %username, %err, "rejected options change");
+                    let (ServerState::Lobby { connections, .. }
+                    | ServerState::Running { connections, .. }) = &mut *state_guard;
+                    if let Some(connection) = connections.get_mut(username) {
+                        let _ = connection
+                            .send(Message::text(format!("options rejected\n{err}")))
+                            .await;
+                    }
+                }
+            }
+            continue;
+        }
+
+        // the host may pause the game, and pick it up again
+        if text == "pause" || text == "resume" {
+            let pause = text == "pause";
+            match state_guard.set_paused(username, pause).await {
+                Ok(()) if pause => info!(username = %username, "paused game"),
+                Ok(()) => info!(username = %username, "resumed game"),
+                Err(err) => {
+                    warn!(username = %username, %err, "rejected pause or resume");
+                    state_guard
+                        .send_to(username, Message::text(format!("pause rejected\n{err}")))
+                        .await;
+                }
+            }
+            continue;
+        }
+
+        // and players may let a spectator see their hand, or stop them
+        if let Some(coach) = text.strip_prefix("coach\n") {
+            state_guard.set_coach(username, coach.trim(), true).await;
+            continue;
+        }
+        if let Some(coach) = text.strip_prefix("uncoach\n") {
+            state_guard.set_coach(username, coach.trim(), false).await;
+            continue;
+        }
+
         let ServerState::Running {
             game_state,
-            connections,
+            join_code,
             ..
         } = &mut *state_guard
         else {
-            unreachable!();
+            if lenient {
+                state_guard
+                    .send_to(username, move_rejected("game not started", confirm_moves))
+                    .await;
+                warn!(username = %username, reason = "game not started", "rejected move");
+                continue;
+            }
+            state_guard.send_to(username, protocol_error).await;
+            if let Err(err) = state_guard.connection_closed(username).await {
+                error!(username = %username, %err, "couldn't drop closed connection");
+            }
+            warn!(
+                username = %username,
+                reason = "game not started",
+                "disconnected for protocol error"
+            );
+            return;
         };
-        let current_player = game_state.current_player();
-        if username != current_player.0 {
-            // not the current player! protocol error!
+
+        // a paused game turns moves away, without holding it against the sender
+        if game_state.is_paused() {
+            let reason = MoveError::Paused.to_string();
             state_guard
-                .server_disconnect(username, protocol_error)
+                .send_to(username, move_rejected(&reason, confirm_moves))
                 .await;
-            eprintln!("disconnected {username:?} for playing a move out of turn");
-            return;
+            info!(username = %username, reason, "rejected move");
+            continue;
         }
 
-        // is current player - decode and try to apply the move
-        let Ok(player_move) = serde_json::from_str::<PlayerMove>(&text) else {
-            state_guard
-                .server_disconnect(username, protocol_error)
-                .await;
-            eprintln!("disconnected {username:?} unable to parse move");
-            return;
+        // decode the move, then let the game check it's legal and the player's turn
+        let simultaneous = game_state.is_simultaneous();
+        let moves_before = game_state.moves().len();
+        let outcome = match serde_json::from_str::<PlayerMove>(&text) {
+            Ok(player_move) if simultaneous => game_state
+                .submit_move_by(username, player_move)
+                .map_err(|err| err.to_string()),
+            Ok(player_move) => game_state
+                .apply_move_by(username, player_move)
+                .map_err(|err| err.to_string()),
+            Err(_) => Err("unparseable move".to_string()),
         };
+        let outcome = match outcome {
+            Ok(outcome) => outcome,
+            Err(reason) => {
+                if lenient {
+                    state_guard
+                        .send_to(username, move_rejected(&reason, confirm_moves))
+                        .await;
+                    warn!(username = %username, reason, "rejected move");
+                    continue;
+                }
+                if let Err(err) = state_guard
+                    .server_disconnect(username, protocol_error)
+                    .await
+                {
+                    error!(username = %username, %err, "couldn't drop player");
+                }
+                warn!(username = %username, reason, "disconnected for protocol error");
+                return;
+            }
+        };
+        // a move picked for a round that isn't over yet has made none
+        for last_move in &game_state.moves()[moves_before..] {
+            METRICS.move_applied();
+            EVENT_LOG.record(
+                join_code,
+                Event::Move {
+                    username: &last_move.player,
+                    card: last_move.card,
+                    location: last_move.location,
+                    taken: &last_move.taken,
+                },
+            );
+        }
+        let skipped = game_state.take_skipped();
+        debug!(
+            username = %username,
+            next_turn = game_state.turn(),
+            move_count = game_state.move_count(),
+            cards_on_board = game_state.board().played_card_count(),
+            board_hash = %format_args!("{:016x}", game_state.board().digest()),
+            "move applied"
+        );
+        let captured = game_state.last_captured();
+
+        if let MoveOutcome::Won(winner) = outcome {
+            info!(winner = %winner, players = game_state.player_count(), "game won");
+            if let Err(err) = state_guard.finish_game(Some(&winner)).await {
+                error!(%err, "couldn't finish game");
+            }
+            return;
+        }
+
+        // the mover hears first, so they aren't left waiting on everyone else's updates
+        if confirm_moves && !simultaneous {
+            state_guard.send_to(username, move_ok(captured)).await;
+        }
 
-        if !game_state.apply_move(player_move) {
-            // Invalid move, disconnect player
+        // Broadcast updated game state to all players, right away if a skipped move's rejection
+        // has to follow it
+        let broadcast = if skipped.is_empty() {
+            state_guard.broadcast_state_throttled().await
+        } else {
+            state_guard.broadcast_state().await
+        };
+        if let Err(err) = broadcast {
+            error!(%err, "couldn't broadcast state");
+        }
+        // moves picked in secret can be beaten to their spot by earlier seats in the round, which
+        // their players hear after the new state, so it isn't cleared away by it
+        for (player, err) in skipped {
+            info!(username = %player, %err, "skipped move");
             state_guard
-                .server_disconnect(username, protocol_error)
+                .send_to(&player, move_rejected(&err.to_string(), confirm_moves))
                 .await;
-            eprintln!("disconnected {username:?} for playing a bad move");
+        }
+        drop(state_guard);
+    }
+}
+
+async fn handle_spectator(
+    mut send: SplitSink<WebSocket, Message>,
+    mut recv: SplitStream<WebSocket>,
+    username: &str,
+    attempt_join_code: &str,
+    state: Arc<Mutex<ServerState>>,
+) {
+    let mut state_guard = state.lock().await;
+    info!(spectator = %username, join_code = %attempt_join_code, "joining as spectator");
+
+    let (ServerState::Lobby {
+        join_code,
+        spectators,
+        ..
+    }
+    | ServerState::Running {
+        join_code,
+        spectators,
+        ..
+    }) = &mut *state_guard;
+
+    // Check join code
+    if join_code != attempt_join_code {
+        drop(state_guard);
+        let _ = send.send(Message::text("join code")).await;
+        info!(username = %username, "rejected - bad join code");
+        return;
+    }
+
+    // Check if username is already spectating
+    if let Some(connection) = spectators.get_mut(username)
+        && connection
+            .send(Message::Ping("live-check".into()))
+            .await
+            .is_ok()
+    {
+        drop(state_guard);
+        let _ = send.send(Message::text("username taken")).await;
+        info!(spectator = %username, "rejected - username taken");
+        return;
+    }
+
+    // Send ok response
+    if send.send(Message::text("ok")).await.is_err() {
+        return;
+    }
+    // whoever had this name before may have been let in, but this might be someone else
+    state_guard.end_coaching(username).await;
+    for message in state_guard.spectator_catch_up(username) {
+        if send.send(message).await.is_err() {
             return;
         }
+    }
 
-        if game_state.someone_has_won() {
-            eprintln!("{username:?} has won");
+    let (ServerState::Lobby { spectators, .. } | ServerState::Running { spectators, .. }) =
+        &mut *state_guard;
+    spectators.insert(username.to_string(), send);
+    state_guard.broadcast_spectator_count().await;
+    info!(spectator = %username, "spectating");
+    drop(state_guard);
+
+    // spectators only ever ask for the state again
+    loop {
+        match classify(recv.next().await) {
+            Incoming::Text(text) if text == "resync" => {
+                let mut state_guard = state.lock().await;
+                let ServerState::Running {
+                    game_state,
+                    spectators,
+                    coaching,
+                    ..
+                } = &mut *state_guard
+                else {
+                    continue;
+                };
+                let spectator_state = spectator_state(game_state, coaching, username);
+                let game_state_json = match serde_json::to_string(&spectator_state) {
+                    Ok(game_state_json) => game_state_json,
+                    Err(err) => {
+                        error!(spectator = %username, %err, "couldn't serialize state");
+                        continue;
+                    }
+                };
+                if let Some(connection) = spectators.get_mut(username) {
+                    let _ = connection.send(Message::text(game_state_json)).await;
+                }
+            }
+            Incoming::Control => continue,
+            _ => {
+                state.lock().await.remove_spectator(username).await;
+                return;
+            }
+        }
+    }
+}
 
-            let winner_message = end_of_game(username);
-            let to_disconnect = connections.keys().cloned().collect::<Vec<_>>();
-            let num_players = game_state.get_player_names().len();
+#[cfg(test)]
+mod tests {
+    use grid_common::{MoveSource, PlayContext};
 
-            for username in to_disconnect {
-                let _ = state_guard
-                    .server_disconnect(&username, winner_message.clone())
-                    .await;
+    use super::*;
+
+    fn lobby() -> ServerState {
+        ServerState::Lobby {
+            options: serde_json::from_str("{}").unwrap(),
+            num_players: 2,
+            connections: HashMap::new(),
+            host: None,
+            takes_deltas: HashSet::new(),
+            spectators: HashMap::new(),
+            join_code: "ABCD".to_string(),
+            game_name: None,
+            tournament: None,
+            position: None,
+            diff_broadcast: false,
+            broadcast_throttle: None,
+            spectator_replay: false,
+            commit_deal: false,
+            first_player: FirstPlayer::Random,
+            start_policy: StartPolicy {
+                min_players: 2,
+                countdown: Duration::ZERO,
+                lobby_timeout: None,
+            },
+            countdown_deadline: None,
+            timeout_deadline: None,
+        }
+    }
+
+    fn running(players: &[&str]) -> ServerState {
+        let player_names = players.iter().map(|name| name.to_string()).collect();
+        ServerState::Running {
+            game_state: GameState::new(player_names, serde_json::from_str("{}").unwrap()),
+            num_players: players.len(),
+            connections: HashMap::new(),
+            spectators: HashMap::new(),
+            join_code: "ABCD".to_string(),
+            game_name: None,
+            tournament: None,
+            position: None,
+            last_sent: None,
+            takes_deltas: HashSet::new(),
+            throttle: None,
+            replay: None,
+            deal_commitment: None,
+            disconnected: HashMap::new(),
+            coaching: HashMap::new(),
+            next_options: None,
+            host: None,
+            started: Instant::now(),
+            first_player: FirstPlayer::Random,
+            start_policy: StartPolicy {
+                min_players: players.len(),
+                countdown: Duration::ZERO,
+                lobby_timeout: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_game_changes_in_lobby() {
+        let mut state = lobby();
+        assert_eq!(
+            state.reset(),
+            Err(StateError::InLobby("resetting to a lobby"))
+        );
+        assert_eq!(
+            state.lost_connection("alice"),
+            Err(StateError::InLobby("losing a player's connection"))
+        );
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            assert_eq!(
+                state.broadcast_state().await,
+                Err(StateError::InLobby("broadcasting state"))
+            );
+            assert_eq!(
+                state.server_disconnect("alice", Message::Close(None)).await,
+                Err(StateError::InLobby("dropping a player"))
+            );
+            assert_eq!(
+                state.finish_game(Some("alice")).await,
+                Err(StateError::InLobby("finishing a game"))
+            );
+        });
+        assert!(matches!(state, ServerState::Lobby { .. }));
+    }
+
+    #[test]
+    fn test_close_empty_lobby() {
+        let mut state = lobby();
+        // a lobby with nobody in it still closes fine
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            assert_eq!(state.close_lobby().await, Ok(()));
+        });
+        runtime.block_on(async {
+            assert_eq!(state.connection_closed("alice").await, Ok(()));
+        });
+    }
+
+    #[test]
+    fn test_coach_leaves() {
+        let mut state = running(&["alice", "bob"]);
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let sees_hand = |state: &ServerState| {
+            let ServerState::Running {
+                game_state,
+                coaching,
+                ..
+            } = state
+            else {
+                unreachable!();
+            };
+            !spectator_state(game_state, coaching, "carol")
+                .hand
+                .0
+                .is_empty()
+        };
+
+        // nobody called carol is watching, so there's no one to let in
+        runtime.block_on(state.set_coach("alice", "carol", true));
+        assert!(!sees_hand(&state));
+
+        // carol was let in while watching
+        let ServerState::Running { coaching, .. } = &mut state else {
+            unreachable!();
+        };
+        coaching.insert("carol".to_string(), "alice".to_string());
+        assert!(sees_hand(&state));
+
+        // once carol is gone, whoever joins under that name next sees only what any spectator does
+        runtime.block_on(state.remove_spectator("carol"));
+        assert!(!sees_hand(&state));
+    }
+
+    #[test]
+    fn test_coach_forfeited_player() {
+        let options = serde_json::from_str(r#"{"announce_draws": true}"#).unwrap();
+        let mut game_state = GameState::new(vec!["alice".to_string(), "bob".to_string()], options);
+        let location = game_state
+            .board()
+            .playable_positions(PlayContext::default())[0];
+        game_state
+            .apply_move_by("alice", PlayerMove::new(0, location, MoveSource::Hand))
+            .unwrap();
+        assert!(!game_state.state_for(0).drawn.is_empty());
+
+        // alice's drawn card went out of play with the rest of her hand
+        game_state.forfeit(0);
+        let coaching = HashMap::from([("carol".to_string(), "alice".to_string())]);
+        let coached = spectator_state(&game_state, &coaching, "carol");
+        assert!(coached.hand.0.is_empty());
+        assert!(coached.drawn.is_empty());
+    }
+
+    #[test]
+    fn test_host_leaves_paused_game() {
+        let mut state = running(&["alice", "bob", "carol"]);
+        let ServerState::Running { host, .. } = &mut state else {
+            unreachable!();
+        };
+        *host = Some("alice".to_string());
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            assert!(state.set_paused("alice", true).await.is_ok());
+            state.lost_connection("alice").unwrap();
+            state.lost_connection("bob").unwrap();
+            assert_eq!(state.forfeit_disconnected(Duration::ZERO).await, Ok(()));
+        });
+
+        // the host's clock ran out, which ended the pause, but bob's didn't run while paused
+        let ServerState::Running {
+            game_state,
+            disconnected,
+            ..
+        } = &state
+        else {
+            panic!("game ended");
+        };
+        assert!(!game_state.is_paused());
+        assert!(
+            !game_state.get_players()[game_state.seat_of("alice").unwrap()]
+                .1
+                .has_cards()
+        );
+        assert!(
+            game_state.get_players()[game_state.seat_of("bob").unwrap()]
+                .1
+                .has_cards()
+        );
+        assert!(disconnected.contains_key("bob"));
+    }
+
+    #[test]
+    fn test_everyone_forfeits() {
+        let mut state = running(&["alice", "bob"]);
+        let ServerState::Running { tournament, .. } = &mut state else {
+            unreachable!();
+        };
+        *tournament = Some(Tournament::new(3));
+        let games_completed = || {
+            METRICS
+                .render()
+                .lines()
+                .find_map(|line| line.strip_prefix("grid_games_completed_total "))
+                .and_then(|count| count.parse::<u64>().ok())
+                .unwrap()
+        };
+        let completed_before = games_completed();
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            state.lost_connection("alice").unwrap();
+            state.lost_connection("bob").unwrap();
+            assert_eq!(state.forfeit_disconnected(Duration::ZERO).await, Ok(()));
+        });
+
+        // the game ends like any other, counting for the tournament without a winner
+        let ServerState::Lobby {
+            tournament: Some(tournament),
+            ..
+        } = &state
+        else {
+            panic!("game didn't end");
+        };
+        assert_eq!(tournament.standings().played, 1);
+        assert!(tournament.standings().wins.is_empty());
+        assert!(games_completed() > completed_before);
+        assert_eq!(
+            end_of_game(None),
+            Message::Close(Some(CloseFrame {
+                code: 4000,
+                reason: "no winner".into(),
+            }))
+        );
+    }
+
+    #[test]
+    fn test_ping_mid_game() {
+        use tokio_tungstenite::{connect_async, tungstenite::Message as ClientMessage};
+
+        let state = Arc::new(Mutex::new(lobby()));
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let game = async {
+            let server_state = state.clone();
+            let app = Router::new().route(
+                "/",
+                get(move |ws: WebSocketUpgrade| async move {
+                    ws.on_upgrade(move |socket| {
+                        let capabilities = Capabilities::from_protocol(None);
+                        handle_websocket(socket, server_state, capabilities, false, false)
+                    })
+                }),
+            );
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("ws://{}/", listener.local_addr().unwrap());
+            tokio::spawn(async move { axum::serve(listener, app).await });
+
+            // the game starts once both players are in
+            let mut players = Vec::new();
+            for login in ["alice\nABCD", "bob\nABCD"] {
+                let (mut socket, _) = connect_async(&url).await.unwrap();
+                socket.send(ClientMessage::text(login)).await.unwrap();
+                players.push(socket);
+            }
+            let alice = &mut players[0];
+            while !matches!(&*state.lock().await, ServerState::Running { .. }) {
+                tokio::task::yield_now().await;
             }
 
-            // Reset server to lobby for next game
-            state_guard.reset(num_players);
-            return;
+            // the pong comes back once the ping has been read, between moves
+            alice
+                .send(ClientMessage::Ping("live-check".into()))
+                .await
+                .unwrap();
+            loop {
+                match alice.next().await {
+                    Some(Ok(ClientMessage::Pong(_))) => break,
+                    Some(Ok(ClientMessage::Text(_))) => continue,
+                    frame => panic!("connection ended with {frame:?}"),
+                }
+            }
+            match &*state.lock().await {
+                ServerState::Running {
+                    connections,
+                    disconnected,
+                    ..
+                } => {
+                    assert!(connections.contains_key("alice"));
+                    assert!(disconnected.is_empty());
+                }
+                ServerState::Lobby { .. } => panic!("game ended"),
+            }
+
+            // and alice can still be answered
+            alice.send(ClientMessage::text("resync")).await.unwrap();
+            let resynced = loop {
+                match alice.next().await {
+                    Some(Ok(ClientMessage::Text(text))) if text.starts_with('{') => break text,
+                    Some(Ok(_)) => continue,
+                    frame => panic!("connection ended with {frame:?}"),
+                }
+            };
+            let player_state = serde_json::from_str::<PlayerVisibleGameState>(&resynced).unwrap();
+            assert_eq!(player_state.username, "alice");
+        };
+        // a connection dropped by mistake would leave this waiting forever
+        runtime
+            .block_on(async { tokio::time::timeout(Duration::from_secs(10), game).await })
+            .expect("timed out");
+    }
+
+    #[test]
+    fn test_countdown_cancelled() {
+        let mut state = lobby();
+        let ServerState::Lobby {
+            countdown_deadline, ..
+        } = &mut state
+        else {
+            unreachable!();
+        };
+        *countdown_deadline = Some(Instant::now());
+
+        // a player leaving drops the lobby below the minimum of two
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            assert_eq!(state.connection_closed("alice").await, Ok(()));
+        });
+        assert!(matches!(
+            state,
+            ServerState::Lobby {
+                countdown_deadline: None,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_throttle_sends_last_state() {
+        let mut state = running(&["alice", "bob"]);
+        let ServerState::Running {
+            throttle, replay, ..
+        } = &mut state
+        else {
+            unreachable!();
+        };
+        *throttle = Some(BroadcastThrottle {
+            window: Duration::from_secs(3600),
+            last_sent: None,
+            pending: false,
+        });
+        // every state that goes out is kept here
+        *replay = Some(Vec::new());
+
+        let play = |state: &mut ServerState| {
+            let ServerState::Running { game_state, .. } = state else {
+                unreachable!();
+            };
+            let player = game_state.get_player_names()[game_state.turn()].clone();
+            let location = game_state
+                .board()
+                .playable_positions(PlayContext::default())[0];
+            game_state
+                .apply_move_by(&player, PlayerMove::new(0, location, MoveSource::Hand))
+                .unwrap();
+        };
+        let sent = |state: &ServerState| {
+            let ServerState::Running {
+                game_state,
+                replay: Some(replay),
+                ..
+            } = state
+            else {
+                unreachable!();
+            };
+            (
+                replay.len(),
+                replay.last() == Some(&game_state.state_for_spectator("")),
+            )
+        };
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            // the first state goes straight out, and the rest of the burst is held back
+            state.broadcast_state_throttled().await.unwrap();
+            assert_eq!(sent(&state), (1, true));
+            play(&mut state);
+            state.broadcast_state_throttled().await.unwrap();
+            play(&mut state);
+            state.broadcast_state_throttled().await.unwrap();
+            state.flush_broadcast().await.unwrap();
+            assert_eq!(sent(&state), (1, false));
+
+            // once the window has passed, the last state of the burst goes out, and nothing's left
+            let ServerState::Running {
+                throttle: Some(throttle),
+                ..
+            } = &mut state
+            else {
+                unreachable!();
+            };
+            throttle.window = Duration::ZERO;
+            state.flush_broadcast().await.unwrap();
+            assert_eq!(sent(&state), (2, true));
+            assert!(matches!(
+                state,
+                ServerState::Running {
+                    throttle: Some(BroadcastThrottle { pending: false, .. }),
+                    ..
+                }
+            ));
+        });
+    }
+
+    #[test]
+    fn test_rejoin_own_seat() {
+        let ServerState::Running { game_state, .. } = running(&["alice", "Alice", "alice2"]) else {
+            unreachable!();
+        };
+        for (login, username) in [
+            ("alice\nABCD", "alice"),
+            (" Alice \nABCD", "Alice"),
+            ("alice2\nABCD", "alice2"),
+        ] {
+            let login = parse_login(login).unwrap();
+            let seat = game_state.seat_of(login.username).unwrap();
+            let rejoined = game_state.state_for(seat);
+            assert_eq!(rejoined.username, username);
+            let (_, player_state) = &game_state.get_players()[seat];
+            assert_eq!(rejoined.hand, *player_state.hand());
         }
+        // names only match exactly, so near misses don't get anyone's seat
+        assert_eq!(game_state.seat_of("ALICE"), None);
+        assert_eq!(game_state.seat_of("alice "), None);
+    }
 
-        // Broadcast updated game state to all players
-        state_guard.broadcast_state().await;
-        drop(state_guard);
+    #[test]
+    fn test_clean_game_name() {
+        assert_eq!(
+            clean_game_name("  Friday night  "),
+            Some("Friday night".to_string())
+        );
+        // control characters go wherever they are, and whitespace is trimmed after
+        assert_eq!(
+            clean_game_name("\tFri\u{7}day\n night\r\n"),
+            Some("Friday night".to_string())
+        );
+        assert_eq!(clean_game_name("Café ♠"), Some("Café ♠".to_string()));
+    }
+
+    #[test]
+    fn test_clean_game_name_length() {
+        let longest = "é".repeat(MAX_GAME_NAME_LENGTH);
+        assert_eq!(clean_game_name(&longest), Some(longest.clone()));
+        assert_eq!(
+            clean_game_name(&format!("  {longest}\n")),
+            Some(longest.clone())
+        );
+        assert_eq!(clean_game_name(&format!("{longest}e")), None);
+    }
+
+    #[test]
+    fn test_clean_game_name_empty() {
+        assert_eq!(clean_game_name(""), None);
+        assert_eq!(clean_game_name("   "), None);
+        assert_eq!(clean_game_name("\u{1b}\n\t"), None);
+    }
+
+    #[test]
+    fn test_only_spectators_get_replay() {
+        let mut state = running(&["alice", "bob"]);
+        let ServerState::Running { replay, .. } = &mut state else {
+            unreachable!();
+        };
+        *replay = Some(Vec::new());
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            state.broadcast_state().await.unwrap();
+            let ServerState::Running { game_state, .. } = &mut state else {
+                unreachable!();
+            };
+            let location = game_state
+                .board()
+                .playable_positions(PlayContext::default())[0];
+            game_state
+                .apply_move_by("alice", PlayerMove::new(0, location, MoveSource::Hand))
+                .unwrap();
+            state.broadcast_state().await.unwrap();
+        });
+        let ServerState::Running {
+            replay: Some(replay),
+            ..
+        } = &state
+        else {
+            unreachable!();
+        };
+        let expected = format!("replay\n{}", serde_json::to_string(replay).unwrap());
+        let is_replay = |message: &Message| {
+            message
+                .to_text()
+                .is_ok_and(|text| text.starts_with("replay\n"))
+        };
+
+        // someone arriving mid-game sees everything from the start
+        let caught_up = state.spectator_catch_up("carol");
+        let replays = caught_up
+            .iter()
+            .filter(|message| is_replay(message))
+            .collect::<Vec<_>>();
+        assert_eq!(replays, [&Message::text(expected)]);
+
+        // players were there for it, so they only get reminded of the recent moves
+        let rejoined = state.rejoin_catch_up("bob", 1);
+        assert!(!rejoined.iter().any(is_replay));
+    }
+
+    #[test]
+    fn test_catch_up_with_seating() {
+        let mut state = running(&["alice", "bob"]);
+        let seating = game_started_message(&["alice".to_string(), "bob".to_string()]);
+        assert!(state.spectator_catch_up("carol").contains(&seating));
+        assert!(state.rejoin_catch_up("bob", 1).contains(&seating));
+        // nothing to seat before the game starts
+        assert!(!lobby().spectator_catch_up("carol").contains(&seating));
     }
 }
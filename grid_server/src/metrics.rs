@@ -0,0 +1,152 @@
+// Copyright 2025 Justin Hu
+//
+// This file is part of Grid Online.
+//
+// Grid Online is free software: you can redistribute it and/or modify it under
+// the terms of the GNU Affero General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// Grid Online is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU Affero General Public License
+// for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with Grid Online. If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Process-wide counters for monitoring the server
+
+use std::{
+    fmt::Write,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+pub static METRICS: Metrics = Metrics::new();
+
+pub struct Metrics {
+    games_started: AtomicU64,
+    games_completed: AtomicU64,
+    /// Total length of all completed games, in milliseconds
+    game_duration_millis: AtomicU64,
+    moves_applied: AtomicU64,
+    active_connections: AtomicU64,
+}
+
+impl Metrics {
+    const fn new() -> Self {
+        Self {
+            games_started: AtomicU64::new(0),
+            games_completed: AtomicU64::new(0),
+            game_duration_millis: AtomicU64::new(0),
+            moves_applied: AtomicU64::new(0),
+            active_connections: AtomicU64::new(0),
+        }
+    }
+
+    pub fn game_started(&self) {
+        self.games_started.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn game_completed(&self, duration: Duration) {
+        self.games_completed.fetch_add(1, Ordering::Relaxed);
+        self.game_duration_millis
+            .fetch_add(duration.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    pub fn move_applied(&self) {
+        self.moves_applied.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a connection as open until the returned guard is dropped
+    pub fn connection_opened(&'static self) -> ActiveConnection {
+        self.active_connections.fetch_add(1, Ordering::Relaxed);
+        ActiveConnection(self)
+    }
+
+    /// Render the counters in the Prometheus text format
+    ///
+    /// Average game length is the duration sum divided by the completed count
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, value: f64| {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} {kind}");
+            let _ = writeln!(out, "{name} {value}");
+        };
+        metric(
+            "grid_games_started_total",
+            "counter",
+            "Games started",
+            self.games_started.load(Ordering::Relaxed) as f64,
+        );
+        metric(
+            "grid_games_completed_total",
+            "counter",
+            "Games played to the end",
+            self.games_completed.load(Ordering::Relaxed) as f64,
+        );
+        metric(
+            "grid_game_duration_seconds_sum",
+            "counter",
+            "Total length of completed games",
+            self.game_duration_millis.load(Ordering::Relaxed) as f64 / 1000.0,
+        );
+        metric(
+            "grid_moves_applied_total",
+            "counter",
+            "Moves applied",
+            self.moves_applied.load(Ordering::Relaxed) as f64,
+        );
+        metric(
+            "grid_active_connections",
+            "gauge",
+            "Open websocket connections",
+            self.active_connections.load(Ordering::Relaxed) as f64,
+        );
+        out
+    }
+}
+
+/// An open connection, which stops being counted when dropped
+pub struct ActiveConnection(&'static Metrics);
+impl Drop for ActiveConnection {
+    fn drop(&mut self) {
+        self.0.active_connections.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::new();
+        metrics.game_started();
+        metrics.game_started();
+        metrics.move_applied();
+        metrics.game_completed(Duration::from_millis(1500));
+
+        let rendered = metrics.render();
+        assert!(rendered.contains("grid_games_started_total 2\n"));
+        assert!(rendered.contains("grid_games_completed_total 1\n"));
+        assert!(rendered.contains("grid_game_duration_seconds_sum 1.5\n"));
+        assert!(rendered.contains("grid_moves_applied_total 1\n"));
+        assert!(rendered.contains("# TYPE grid_active_connections gauge\n"));
+    }
+
+    #[test]
+    fn test_active_connections() {
+        static METRICS: Metrics = Metrics::new();
+        let first = METRICS.connection_opened();
+        let second = METRICS.connection_opened();
+        assert!(METRICS.render().contains("grid_active_connections 2\n"));
+        drop(first);
+        drop(second);
+        assert!(METRICS.render().contains("grid_active_connections 0\n"));
+    }
+}
@@ -19,43 +19,649 @@
 
 //! Game state for Grid online server
 
-use clap::{ArgAction, Args, ValueEnum};
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use clap::{ArgAction, ArgMatches, Args, ValueEnum, parser::ValueSource};
 use grid_common::{
-    BOARD_SIZE, Board, Card, Deck, HAND_SIZE, Hand, PlayerMove, PlayerVisibleGameState, Suit, Value,
+    BOARD_SIZE, Board, Card, DIRECTIONS, Deck, FirstMove, HAND_SIZE, Hand, LastMove, MoveSource,
+    PlayContext, PlayerMove, PlayerVisibleGameState, RankOrder, Suit, TakenTo, TakingVariant,
+    Value, can_take_any, find_captures_in, fnv1a,
 };
 use rand::{
-    rng,
+    SeedableRng,
+    rngs::StdRng,
     seq::{IteratorRandom, SliceRandom},
 };
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
-#[derive(Clone, Args)]
+use crate::position::Position;
+
+#[derive(Clone, PartialEq, Eq, Args, Serialize, Deserialize)]
 pub struct GameOptions {
-    #[clap(long, action = ArgAction::Set)]
+    /// Deal some players' worth of cards to no one, leaving them out of the game
+    #[clap(long, action = ArgAction::Set, default_value = "false")]
+    #[serde(default)]
     sequester_cards: bool,
+    /// Number of players' worth of cards to sequester
+    #[clap(long, default_value = "1")]
+    #[serde(default = "default_sequester_count")]
+    sequester_count: usize,
+    /// Which cards a played card takes
     #[clap(long)]
+    #[serde(default)]
     taking_variant: TakingVariant,
+    /// Which values are higher than which, when taking by suit
+    #[clap(long, value_enum, default_value_t = RankOrder::AceLow)]
+    #[serde(default)]
+    rank_order: RankOrder,
+    /// Where the first card of the game may go
+    #[clap(long, value_enum, default_value_t = FirstMove::Center)]
+    #[serde(default)]
+    first_move: FirstMove,
+    /// Seed for the shuffles, for reproducible games
+    #[clap(long)]
+    #[serde(default)]
+    seed: Option<u64>,
+    /// Order taken cards go into the deck in
+    #[clap(long, value_enum, default_value_t = CaptureOrder::Shuffled)]
+    #[serde(default)]
+    capture_order: CaptureOrder,
+    /// End of the deck taken cards go onto
+    #[clap(long, value_enum, default_value_t = CapturePlacement::Bottom)]
+    #[serde(default)]
+    capture_placement: CapturePlacement,
+    /// Show players the next card they'll draw
+    #[clap(long)]
+    #[serde(default)]
+    peek_top: bool,
+    /// Wrap the board edges around, so play and captures continue from the opposite edge
+    #[clap(long)]
+    #[serde(default)]
+    wrap: bool,
+    /// Deal so that each player's cards add up to about the same points, instead of at random
+    #[clap(long)]
+    #[serde(default)]
+    balanced_deal: bool,
+    /// Seed the shuffles from the join code, so the same code always deals the same game
+    #[clap(long)]
+    #[serde(default)]
+    deck_seed_from_join_code: bool,
+    /// Deal each player a face-up reserve card, which everyone can see and its owner can play
+    #[clap(long)]
+    #[serde(default)]
+    reserve_card: bool,
+    /// Start everyone with an empty hand, drawing up to a full hand when their turn starts
+    #[clap(long)]
+    #[serde(default)]
+    deal_to_empty: bool,
+    /// Draw from one deck shared by everyone, before falling back on your own pile of taken cards
+    #[clap(long)]
+    #[serde(default)]
+    shared_deck: bool,
+    /// Only allow plays that take cards, unless none of your cards can take anything
+    #[clap(long, action = ArgAction::Set, num_args = 0..=1, default_value = "false", default_missing_value = "true")]
+    #[serde(default)]
+    require_capture: bool,
+    /// Show players how many cards are in their own deck, but not which
+    #[clap(long)]
+    #[serde(default)]
+    hide_own_deck: bool,
+    /// Make sure everyone's opening hand has a face card - a jack, queen, or king
+    #[clap(long)]
+    #[serde(default)]
+    face_card_hands: bool,
+    /// Draw this many cards each turn, instead of drawing back up to a full hand
+    #[clap(long)]
+    #[serde(default)]
+    draw_per_turn: Option<usize>,
+    /// Most cards a hand can hold when drawing a set number each turn
+    #[clap(long, default_value_t = HAND_SIZE)]
+    #[serde(default = "default_max_hand_size")]
+    max_hand_size: usize,
+    /// Most cards one play can take, counted across every direction; the nearest are taken first
+    #[clap(long)]
+    #[serde(default)]
+    max_capture: Option<usize>,
+    /// Tell players which cards in their hand they've just drawn, so they can be shown arriving
+    #[clap(long)]
+    #[serde(default)]
+    announce_draws: bool,
+    /// Everyone picks a move in secret each round, then they're all played in turn order
+    #[clap(long)]
+    #[serde(default)]
+    simultaneous: bool,
+    /// Let players choose which directions to take cards in, including none at all
+    #[clap(long)]
+    #[serde(default)]
+    optional_capture: bool,
+    /// Make sure everyone's opening hand has two cards of the same value, for teaching games where
+    /// a take should come up early
+    #[clap(long)]
+    #[serde(default)]
+    teaching_deal: bool,
+}
+
+fn default_max_hand_size() -> usize {
+    HAND_SIZE
+}
+fn default_sequester_count() -> usize {
+    1
+}
+#[derive(Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CaptureOrder {
+    /// Shuffle taken cards before adding them
+    #[default]
+    Shuffled,
+    /// Keep taken cards in board order, left to right then top to bottom
+    Ordered,
+}
+#[derive(Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CapturePlacement {
+    /// Taken cards are drawn last
+    #[default]
+    Bottom,
+    /// Taken cards are drawn next
+    Top,
+}
+
+/// A named bundle of options, for a sensible ruleset without setting every flag
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Preset {
+    /// The plain rules, with no extras
+    Classic,
+    /// Plays must take cards where they can, and taken cards are drawn next
+    Aggressive,
+    /// Two players' worth of cards sequestered, so there's less to play through
+    Quick,
+}
+
+/// Who takes the first turn of a game
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FirstPlayer {
+    /// Whoever the shuffle seats first
+    Random,
+    /// The host, who joined first
+    Host,
+    /// Whoever has gone first the fewest times so far this tournament
+    Rotate,
+}
+
+impl FirstPlayer {
+    /// Turn the table so the player this picks sits first, keeping everyone's order around it
+    ///
+    /// Seats are in turn order, and `starts` counts the games each player has gone first in. Ties
+    /// go to the earliest seat.
+    pub fn seat(self, seats: &mut [String], host: Option<&str>, starts: &HashMap<String, usize>) {
+        let first = match self {
+            FirstPlayer::Random => None,
+            FirstPlayer::Host => seats.iter().position(|name| Some(name.as_str()) == host),
+            FirstPlayer::Rotate => seats
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, name)| starts.get(*name).copied().unwrap_or(0))
+                .map(|(seat, _)| seat),
+        };
+        if let Some(first) = first {
+            seats.rotate_left(first);
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum OptionsError {
+    #[error(
+        "sequestering {sequester_count} shares leaves fewer than {} cards for each of {num_players} players",
+        HAND_SIZE
+    )]
+    SequesterTooLarge {
+        num_players: usize,
+        sequester_count: usize,
+    },
+    #[error(
+        "a 52-card deck can't deal each of {num_players} players a card, with {sequester_count} players' worth sequestered"
+    )]
+    NoCardsToDeal {
+        num_players: usize,
+        sequester_count: usize,
+    },
+    #[error("can't both give a seed and seed from the join code")]
+    ConflictingSeeds,
+    #[error(
+        "can't deal to empty hands from a shared deck, since only the first player would get cards"
+    )]
+    SharedDeckDealToEmpty,
+    #[error("can't make sure opening hands have a face card when hands start empty")]
+    FaceCardHandsDealToEmpty,
+    #[error("can't deal teaching hands when hands start empty")]
+    TeachingDealToEmpty,
+    #[error(
+        "the most cards a hand can hold must be at least the {} dealt",
+        HAND_SIZE
+    )]
+    MaxHandSizeTooSmall,
+    #[error("the most cards a hand can hold can't be more than the 52 in the deck")]
+    MaxHandSizeTooLarge,
+    #[error("can't draw more than the 52 cards in the deck each turn")]
+    DrawPerTurnTooLarge,
+    #[error(
+        "can't deal to empty hands when everyone moves at once, since hands are drawn at the start of each player's turn"
+    )]
+    SimultaneousDealToEmpty,
+}
+
+/// Cards that aren't where a single deck says they should be
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("cards missing from the game: {missing:?}; cards in it more than once: {extra:?}")]
+pub struct IntegrityError {
+    pub missing: Vec<Card>,
+    /// Each extra copy of a card, so a card in three places is listed twice
+    pub extra: Vec<Card>,
+}
+
+/// Why a move wasn't applied
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum MoveError {
+    #[error("move out of turn")]
+    NotYourTurn,
+    #[error("move already picked this round")]
+    AlreadySubmitted,
+    #[error("card index {0} out of range")]
+    NoSuchCard(usize),
+    #[error("no reserve card to play")]
+    NoReserve,
+    #[error("can't play at position {0:?}")]
+    IllegalPosition((usize, usize)),
+    #[error("must play a card that takes something")]
+    MustCapture,
+    #[error("this game doesn't let you choose what to take")]
+    CaptureNotOptional,
+    #[error("there's no direction {0:?} to take in")]
+    NoSuchDirection((i32, i32)),
+    #[error("the game is paused")]
+    Paused,
+}
+
+/// What a move led to
+#[derive(Debug, PartialEq)]
+pub enum MoveOutcome {
+    /// The game goes on
+    Continues,
+    /// The game is over, won by this player
+    Won(String),
+}
+
+impl GameOptions {
+    /// Check that these options make a playable game for the given number of players
+    pub fn validate(&self, num_players: usize) -> Result<(), OptionsError> {
+        let sequester_count = if self.sequester_cards {
+            self.sequester_count
+        } else {
+            0
+        };
+        // a host can send any count, so the sum can overflow
+        let shares = num_players.checked_add(sequester_count);
+        if num_players == 0 || shares.is_none_or(|shares| 52 / shares == 0) {
+            return Err(OptionsError::NoCardsToDeal {
+                num_players,
+                sequester_count,
+            });
+        }
+        if self.sequester_cards
+            && num_players
+                .checked_add(self.sequester_count)
+                .is_none_or(|shares| 52 / shares < HAND_SIZE)
+        {
+            return Err(OptionsError::SequesterTooLarge {
+                num_players,
+                sequester_count: self.sequester_count,
+            });
+        }
+        if self.seed.is_some() && self.deck_seed_from_join_code {
+            return Err(OptionsError::ConflictingSeeds);
+        }
+        if self.shared_deck && self.deal_to_empty {
+            return Err(OptionsError::SharedDeckDealToEmpty);
+        }
+        if self.face_card_hands && self.deal_to_empty {
+            return Err(OptionsError::FaceCardHandsDealToEmpty);
+        }
+        if self.teaching_deal && self.deal_to_empty {
+            return Err(OptionsError::TeachingDealToEmpty);
+        }
+        if self.max_hand_size < HAND_SIZE {
+            return Err(OptionsError::MaxHandSizeTooSmall);
+        }
+        if self.max_hand_size > 52 {
+            return Err(OptionsError::MaxHandSizeTooLarge);
+        }
+        if self.draw_per_turn.is_some_and(|draws| draws > 52) {
+            return Err(OptionsError::DrawPerTurnTooLarge);
+        }
+        if self.simultaneous && self.deal_to_empty {
+            return Err(OptionsError::SimultaneousDealToEmpty);
+        }
+
+        Ok(())
+    }
+
+    /// The flag for an option that changes how cards are dealt, if any are set
+    ///
+    /// Games started from a position deal their own way, so can't have these
+    pub fn deal_option(&self) -> Option<&'static str> {
+        [
+            (self.sequester_cards, "--sequester-cards"),
+            (self.balanced_deal, "--balanced-deal"),
+            (self.reserve_card, "--reserve-card"),
+            (self.deal_to_empty, "--deal-to-empty"),
+            (self.shared_deck, "--shared-deck"),
+            (self.face_card_hands, "--face-card-hands"),
+            (self.teaching_deal, "--teaching-deal"),
+        ]
+        .into_iter()
+        .find_map(|(set, flag)| set.then_some(flag))
+    }
+
+    /// Whether a board could have been played out under these options
+    ///
+    /// That's every card joined to the rest, back to one where the first card of the game may go.
+    /// An empty board always could be.
+    pub fn could_reach(&self, board: &Board) -> bool {
+        let mut cards = (0..BOARD_SIZE)
+            .flat_map(|row| (0..BOARD_SIZE).map(move |col| (row, col)))
+            .filter(|&(row, col)| board.0[row][col].is_some());
+        let Some((row, col)) = cards.next() else {
+            return true;
+        };
+        let group = board.connected_group_wrapping(row, col, self.wrap);
+        let center = board.center();
+        group.len() == board.played_card_count()
+            && group
+                .iter()
+                .any(|&position| self.first_move.allows(center, position))
+    }
+
+    /// Set the options making up a preset, except those given on the command line
+    pub fn apply_preset(&mut self, preset: Preset, matches: &ArgMatches) {
+        let explicit = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        match preset {
+            Preset::Classic => {}
+            Preset::Aggressive => {
+                if !explicit("require_capture") {
+                    self.require_capture = true;
+                }
+                if !explicit("capture_placement") {
+                    self.capture_placement = CapturePlacement::Top;
+                }
+            }
+            Preset::Quick => {
+                if !explicit("sequester_cards") {
+                    self.sequester_cards = true;
+                }
+                if !explicit("sequester_count") {
+                    self.sequester_count = 2;
+                }
+            }
+        }
+    }
+
+    /// The options to deal a game joined with the given code with
+    ///
+    /// If seeding from the join code, this fills in the seed
+    pub fn for_join_code(&self, join_code: &str) -> GameOptions {
+        let mut options = self.clone();
+        if self.deck_seed_from_join_code {
+            options.seed = Some(seed_from_join_code(join_code));
+        }
+        options
+    }
+}
+
+/// Swap cards so the hand dealt from each pile has a face card, keeping every pile the same size
+///
+/// Each hand is the `HAND_SIZE` cards from `hand_start` on. Face cards come from later in the same
+/// pile if possible, then from cards out of play, then from other piles that can spare one. The
+/// hand card swapped out is its highest, to keep point totals close to what was dealt.
+fn give_each_hand_a_face_card(
+    piles: &mut [Vec<Card>],
+    out_of_play: &mut [Card],
+    hand_start: usize,
+) {
+    let hand = |pile: &[Card]| hand_start.min(pile.len())..(hand_start + HAND_SIZE).min(pile.len());
+    // None if the hand is empty or already has a face card
+    let swap_out = |pile: &[Card]| {
+        let hand = hand(pile);
+        if pile[hand.clone()].iter().any(Card::is_face) {
+            return None;
+        }
+        hand.max_by_key(|&index| pile[index].1.points())
+    };
+
+    for pile in piles.iter_mut() {
+        if let Some(to) = swap_out(pile)
+            && let Some(from) = (hand(pile).end..pile.len()).find(|&index| pile[index].is_face())
+        {
+            pile.swap(from, to);
+        }
+    }
+
+    for index in 0..piles.len() {
+        let Some(to) = swap_out(&piles[index]) else {
+            continue;
+        };
+        if let Some(from) = out_of_play.iter().position(Card::is_face) {
+            std::mem::swap(&mut piles[index][to], &mut out_of_play[from]);
+            continue;
+        }
+        // any face card but the last one in a hand
+        let spare = (0..piles.len())
+            .filter(|&other| other != index)
+            .find_map(|other| {
+                let pile = &piles[other];
+                let hand = hand(pile);
+                let in_hand = pile[hand.clone()]
+                    .iter()
+                    .filter(|card| card.is_face())
+                    .count();
+                (0..pile.len())
+                    .find(|&from| pile[from].is_face() && (!hand.contains(&from) || in_hand > 1))
+                    .map(|from| (other, from))
+            });
+        if let Some((other, from)) = spare {
+            let card = piles[index][to];
+            piles[index][to] = piles[other][from];
+            piles[other][from] = card;
+        }
+    }
+}
+
+/// Whether some two cards have the same value, so one can be played to take the other
+fn has_pair(cards: &[Card]) -> bool {
+    cards
+        .iter()
+        .enumerate()
+        .any(|(index, card)| cards[index + 1..].iter().any(|other| other.1 == card.1))
+}
+
+/// Swap cards so the hand dealt from each pile has two cards of the same value, keeping every pile
+/// the same size
+///
+/// Each hand is the `HAND_SIZE` cards from `hand_start` on. A card matching one in the hand comes
+/// from later in the same pile if possible, then from cards out of play, then from what other piles
+/// have past their hands. The hand card swapped out is the one closest in points to the match, to
+/// keep point totals close to what was dealt. With `keep_face_card`, a hand's only face card is
+/// never the one swapped out. Hands with no match anywhere they can take one from, or nothing they
+/// can give up for it, are left as they are.
+fn give_each_hand_a_pair(
+    piles: &mut [Vec<Card>],
+    out_of_play: &mut [Card],
+    hand_start: usize,
+    keep_face_card: bool,
+) {
+    let hand = |pile: &[Card]| hand_start.min(pile.len())..(hand_start + HAND_SIZE).min(pile.len());
+
+    for index in 0..piles.len() {
+        let hand_range = hand(&piles[index]);
+        let held = piles[index][hand_range.clone()].to_vec();
+        if held.len() < 2 || has_pair(&held) {
+            continue;
+        }
+        let matches = |card: &Card| held.iter().any(|held| held.1 == card.1);
+
+        // which pile the match is in, or None if it's out of play, and where
+        let from = (hand_range.end..piles[index].len())
+            .find(|&from| matches(&piles[index][from]))
+            .map(|from| (Some(index), from))
+            .or_else(|| {
+                out_of_play
+                    .iter()
+                    .position(matches)
+                    .map(|from| (None, from))
+            })
+            .or_else(|| {
+                (0..piles.len())
+                    .filter(|&other| other != index)
+                    .find_map(|other| {
+                        (hand(&piles[other]).end..piles[other].len())
+                            .find(|&from| matches(&piles[other][from]))
+                            .map(|from| (Some(other), from))
+                    })
+            });
+        let Some((from_pile, from)) = from else {
+            continue;
+        };
+        let incoming = match from_pile {
+            Some(other) => piles[other][from],
+            None => out_of_play[from],
+        };
+        let lone_face = keep_face_card
+            && !incoming.is_face()
+            && held.iter().filter(|card| card.is_face()).count() == 1;
+        let Some(to) = hand_range
+            .filter(|&to| piles[index][to].1 != incoming.1)
+            .filter(|&to| !(lone_face && piles[index][to].is_face()))
+            .min_by_key(|&to| piles[index][to].1.points().abs_diff(incoming.1.points()))
+        else {
+            continue;
+        };
+
+        let outgoing = piles[index][to];
+        piles[index][to] = incoming;
+        match from_pile {
+            Some(other) => piles[other][from] = outgoing,
+            None => out_of_play[from] = outgoing,
+        }
+    }
 }
-#[derive(Clone, Copy, ValueEnum)]
-pub enum TakingVariant {
-    SameNumber,
-    SameNumberOrSuitRanked,
+
+/// Random numbers for a game, from its seed if it has one
+fn rng_for(game_options: &GameOptions) -> StdRng {
+    match game_options.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_os_rng(),
+    }
+}
+
+/// Hash a join code into a seed, the same way on every platform and build (64-bit FNV-1a)
+fn seed_from_join_code(join_code: &str) -> u64 {
+    fnv1a(join_code.as_bytes())
 }
 
+/// A game in progress
+///
+/// Clones carry the RNG state along with everything else, so a copy given the same moves shuffles
+/// taken cards the same way the original would. This lets a copy be played ahead to see where a
+/// move leads without touching the real game, and equal states stay equal under the same moves.
+#[derive(Clone, PartialEq, Eq)]
 pub struct GameState {
     game_options: GameOptions,
     board: Board,
     players: Vec<(String, PlayerState)>,
     turn: usize,
+    rng: StdRng,
+    /// Cards no longer in the game, either sequestered or from forfeited players
+    out_of_play: Vec<Card>,
+    /// Cards everyone draws from, if playing with a shared deck
+    shared_deck: Vec<Card>,
+    /// Whether any card has been played, so an empty board isn't the start of the game
+    has_started_placing: bool,
+    /// Every move made so far, oldest first
+    history: Vec<LastMove>,
+    /// Cards the most recent move took from the board, not counting the one played
+    last_captured: usize,
+    /// Each player's move for this round, by seat, once picked, if everyone moves at once
+    submitted: Vec<Option<PlayerMove>>,
+    /// Moves from rounds played that couldn't be played by the time their turn came, and why,
+    /// until they're handed out
+    skipped: Vec<(String, MoveError)>,
+    /// When the game was paused, if it's paused
+    paused: Option<Instant>,
 }
+/// Where every card is before the first move, to commit to at the start of a game
+#[derive(Serialize)]
+pub struct OpeningDeal {
+    board: Board,
+    /// In turn order
+    players: Vec<OpeningPile>,
+    shared_deck: Vec<Card>,
+    /// Sequestered cards
+    out_of_play: Vec<Card>,
+}
+#[derive(Serialize)]
+struct OpeningPile {
+    player: String,
+    reserve: Option<Card>,
+    hand: Vec<Card>,
+    /// In drawing order
+    deck: Vec<Card>,
+}
+
+/// What anyone watching could see of a game, for logs - never anyone's hand or deck
+#[derive(Debug, Serialize)]
+pub struct PublicGameState {
+    board: Board,
+    turn: usize,
+    /// Each player's card count, in turn order
+    players: Vec<(String, u32)>,
+}
+
+#[derive(Clone, PartialEq, Eq)]
 pub struct PlayerState {
     hand: Hand,
     deck: Deck,
+    /// Face-up card everyone can see, if the game deals them and it hasn't been played
+    reserve: Option<Card>,
+    /// Cards drawn since the player last played, which are the last ones in the hand
+    drawn: usize,
 }
 
 impl PlayerState {
-    /// Check if the player has any cards (in hand or deck)
+    /// Check if the player has any cards (in hand, deck, or reserve)
     pub fn has_cards(&self) -> bool {
-        !self.hand.0.is_empty() || !self.deck.0.is_empty()
+        !self.hand.0.is_empty() || !self.deck.0.is_empty() || self.reserve.is_some()
+    }
+
+    /// Number of cards the player holds, wherever they are
+    fn card_count(&self) -> usize {
+        self.hand.0.len() + self.deck.0.len() + usize::from(self.reserve.is_some())
+    }
+
+    pub fn hand(&self) -> &Hand {
+        &self.hand
+    }
+
+    pub fn deck(&self) -> &Deck {
+        &self.deck
+    }
+
+    pub fn reserve(&self) -> Option<Card> {
+        self.reserve
     }
 }
 
@@ -65,87 +671,184 @@ impl GameState {
 
         // Generate a full deck of 52 cards
         let mut deck = Vec::new();
-        for suit in [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades] {
-            for value in [
-                Value::Ace,
-                Value::Two,
-                Value::Three,
-                Value::Four,
-                Value::Five,
-                Value::Six,
-                Value::Seven,
-                Value::Eight,
-                Value::Nine,
-                Value::Ten,
-                Value::Jack,
-                Value::Queen,
-                Value::King,
-            ] {
+        for suit in Suit::ALL {
+            for value in Value::ALL {
                 deck.push(Card(suit, value));
             }
         }
 
         // Shuffle the deck
-        let mut rng = rand::rng();
+        let mut rng = rng_for(&game_options);
         deck.shuffle(&mut rng);
 
-        let mut players = Vec::new();
-
-        if game_options.sequester_cards {
-            // Deal cards evenly to all players plus an extra "sequester" player
-            let effective_players = num_players + 1;
-            let cards_per_player = deck.len() / effective_players;
-
-            // Deal to actual players
-            for (i, player_name) in player_names.into_iter().enumerate() {
-                let player_cards =
-                    deck[(i * cards_per_player)..((i + 1) * cards_per_player)].to_vec();
-
-                let hand = Hand(player_cards[0..HAND_SIZE.min(player_cards.len())].to_vec());
-                let remaining_cards = player_cards[HAND_SIZE.min(player_cards.len())..].to_vec();
-
-                players.push((
-                    player_name.clone(),
-                    PlayerState {
-                        hand,
-                        deck: Deck(remaining_cards),
-                    },
-                ));
-            }
+        // Work out how many cards each player gets
+        let shares = if game_options.sequester_cards {
+            // Deal cards evenly to all players plus some extra "sequester" players
+            let effective_players = num_players.saturating_add(game_options.sequester_count);
+            vec![deck.len() / effective_players; num_players]
         } else {
             // Deal cards evenly to all players, distribute extra cards randomly
             let cards_per_player = deck.len() / num_players;
             let extra_cards = deck.len() % num_players;
             let gets_extra_cards = (0..num_players).choose_multiple(&mut rng, extra_cards);
+            (0..num_players)
+                .map(|i| cards_per_player + usize::from(gets_extra_cards.contains(&i)))
+                .collect()
+        };
+        let dealt = shares.iter().sum::<usize>();
 
-            for (i, player_name) in player_names.into_iter().enumerate() {
-                let extra_card: usize = gets_extra_cards.contains(&i).into();
-
-                let player_cards = deck
-                    [(i * cards_per_player)..((i + 1) * cards_per_player + extra_card)]
-                    .to_vec();
-
-                let hand = Hand(player_cards[0..HAND_SIZE.min(player_cards.len())].to_vec());
-                let remaining_cards = player_cards[HAND_SIZE.min(player_cards.len())..].to_vec();
+        let mut out_of_play = deck[dealt..].to_vec();
+        let mut piles = if game_options.balanced_deal {
+            balanced_piles(&deck[..dealt], &shares, &mut rng)
+        } else {
+            let mut start = 0;
+            shares
+                .iter()
+                .map(|share| {
+                    let pile = deck[start..(start + share)].to_vec();
+                    start += share;
+                    pile
+                })
+                .collect()
+        };
+        if game_options.face_card_hands {
+            // the reserve comes off the top of each pile, so the hand starts after it
+            let hand_start = usize::from(game_options.reserve_card);
+            give_each_hand_a_face_card(&mut piles, &mut out_of_play, hand_start);
+        }
+        if game_options.teaching_deal {
+            let hand_start = usize::from(game_options.reserve_card);
+            give_each_hand_a_pair(
+                &mut piles,
+                &mut out_of_play,
+                hand_start,
+                game_options.face_card_hands,
+            );
+        }
 
-                players.push((
-                    player_name.clone(),
+        let players = player_names
+            .into_iter()
+            .zip(piles)
+            .map(|(player_name, mut player_cards)| {
+                // the reserve comes off the top, before the hand is dealt
+                let reserve = (game_options.reserve_card && !player_cards.is_empty())
+                    .then(|| player_cards.remove(0));
+                let hand_size = if game_options.deal_to_empty {
+                    0
+                } else {
+                    HAND_SIZE.min(player_cards.len())
+                };
+                let hand = Hand(player_cards[0..hand_size].to_vec());
+                let remaining_cards = player_cards[hand_size..].to_vec();
+                (
+                    player_name,
                     PlayerState {
                         hand,
                         deck: Deck(remaining_cards),
+                        reserve,
+                        drawn: 0,
                     },
-                ));
-            }
-        }
+                )
+            })
+            .collect::<Vec<_>>();
 
-        Self {
+        let submitted = vec![None; players.len()];
+        let mut game_state = Self {
             game_options,
             board: Board([[None; BOARD_SIZE]; BOARD_SIZE]),
             players,
             turn: 0,
+            rng,
+            out_of_play,
+            shared_deck: Vec::new(),
+            has_started_placing: false,
+            history: Vec::new(),
+            last_captured: 0,
+            submitted,
+            skipped: Vec::new(),
+            paused: None,
+        };
+        if game_state.game_options.shared_deck {
+            // everything dealt past the hands is pooled, and mixed so the pool doesn't give away
+            // whose pile each card came from
+            for (_, player_state) in &mut game_state.players {
+                game_state.shared_deck.append(&mut player_state.deck.0);
+            }
+            game_state.shared_deck.shuffle(&mut game_state.rng);
+        }
+        if game_state.game_options.deal_to_empty {
+            // the first player draws before placing the first card
+            game_state.fill_hand(0);
         }
+        #[cfg(debug_assertions)]
+        game_state.assert_card_conservation();
+        game_state
+    }
+
+    /// Start a game from a shared position instead of a fresh deal
+    ///
+    /// Cards not on the board or in a given hand are shuffled and dealt out one at a time, and
+    /// anyone whose hand isn't given draws theirs from what they were dealt. The position must
+    /// already be validated for these options and players.
+    pub fn from_position(
+        player_names: Vec<String>,
+        game_options: GameOptions,
+        position: &Position,
+    ) -> Self {
+        let num_players = player_names.len();
+        let mut rng = rng_for(&game_options);
+        let mut undealt = position.undealt();
+        undealt.shuffle(&mut rng);
+
+        let mut undealt = undealt.into_iter();
+        let players = player_names
+            .into_iter()
+            .zip(position.pile_sizes(num_players))
+            .enumerate()
+            .map(|(index, (player_name, pile_size))| {
+                let mut pile = undealt.by_ref().take(pile_size).collect::<Vec<_>>();
+                let hand = match &position.hands {
+                    Some(hands) => hands[index].clone(),
+                    None => pile.drain(..HAND_SIZE.min(pile.len())).collect(),
+                };
+                (
+                    player_name,
+                    PlayerState {
+                        hand: Hand(hand),
+                        deck: Deck(pile),
+                        reserve: None,
+                        drawn: 0,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let submitted = vec![None; players.len()];
+        let game_state = Self {
+            game_options,
+            board: position.board.clone(),
+            players,
+            turn: position.turn,
+            rng,
+            out_of_play: Vec::new(),
+            shared_deck: Vec::new(),
+            has_started_placing: !position.board.is_empty(),
+            history: Vec::new(),
+            last_captured: 0,
+            submitted,
+            skipped: Vec::new(),
+            paused: None,
+        };
+        #[cfg(debug_assertions)]
+        game_state.assert_card_conservation();
+        game_state
     }
 
+    /// What the player in a seat sees
+    ///
+    /// # Panics
+    ///
+    /// If there's no such seat - see [`GameState::state_for_player`] to look players up by name
     pub fn state_for(&self, player_index: usize) -> PlayerVisibleGameState {
         if player_index >= self.players.len() {
             panic!(
@@ -157,178 +860,800 @@ impl GameState {
 
         let (player_name, player_state) = &self.players[player_index];
 
-        // Create list of all players with their card counts (hand + deck)
-        let players: Vec<(String, u32)> = self
-            .players
-            .iter()
-            .map(|(name, state)| {
-                let card_count = state.hand.0.len() + state.deck.0.len();
-                (name.clone(), card_count as u32)
-            })
-            .collect();
-
         PlayerVisibleGameState {
             board: self.board.clone(),
             hand: player_state.hand.clone(),
-            deck: player_state.deck.clone(),
+            deck: if self.game_options.hide_own_deck {
+                Deck(Vec::new())
+            } else {
+                player_state.deck.clone()
+            },
             username: player_name.clone(),
-            players,
+            players: self.card_counts(),
             turn: self.turn,
+            my_index: Some(player_index),
+            top_card: if self.game_options.peek_top {
+                self.shared_deck
+                    .first()
+                    .or(player_state.deck.0.first())
+                    .copied()
+            } else {
+                None
+            },
+            wrap: self.game_options.wrap,
+            taking_variant: self.game_options.taking_variant,
+            rank_order: self.game_options.rank_order,
+            first_move: self.game_options.first_move,
+            reserves: self.reserves(),
+            last_move: self.history.last().cloned(),
+            shared_deck: self
+                .game_options
+                .shared_deck
+                .then_some(self.shared_deck.len()),
+            hidden_deck: self
+                .game_options
+                .hide_own_deck
+                .then_some(player_state.deck.0.len()),
+            started: self.has_started_placing,
+            drawn: if self.game_options.announce_draws {
+                let hand_size = player_state.hand.0.len();
+                (hand_size - player_state.drawn..hand_size).collect()
+            } else {
+                Vec::new()
+            },
+            submitted: self.submitted_flags(),
+            move_count: self.move_count(),
+            board_hash: Some(self.board.digest()),
         }
     }
 
-    pub fn get_options(&self) -> &GameOptions {
-        &self.game_options
+    /// What a player sees, or None if no one by that name is playing
+    pub fn state_for_player(&self, username: &str) -> Option<PlayerVisibleGameState> {
+        self.seat_of(username)
+            .map(|player_index| self.state_for(player_index))
     }
 
-    pub fn get_player_names(&self) -> Vec<String> {
-        self.players.iter().map(|(name, _)| name.clone()).collect()
+    /// Index of the player with exactly this name, if they're in the game
+    pub fn seat_of(&self, username: &str) -> Option<usize> {
+        self.players
+            .iter()
+            .position(|(player, _)| player == username)
     }
 
-    pub fn current_player(&self) -> (&str, &PlayerState) {
+    /// State visible to someone watching the game - no hand or deck
+    pub fn state_for_spectator(&self, username: &str) -> PlayerVisibleGameState {
+        PlayerVisibleGameState {
+            board: self.board.clone(),
+            hand: Hand(Vec::new()),
+            deck: Deck(Vec::new()),
+            username: username.to_string(),
+            players: self.card_counts(),
+            turn: self.turn,
+            my_index: None,
+            top_card: None,
+            wrap: self.game_options.wrap,
+            taking_variant: self.game_options.taking_variant,
+            rank_order: self.game_options.rank_order,
+            first_move: self.game_options.first_move,
+            reserves: self.reserves(),
+            last_move: self.history.last().cloned(),
+            shared_deck: self
+                .game_options
+                .shared_deck
+                .then_some(self.shared_deck.len()),
+            hidden_deck: None,
+            started: self.has_started_placing,
+            drawn: Vec::new(),
+            submitted: self.submitted_flags(),
+            move_count: self.move_count(),
+            board_hash: Some(self.board.digest()),
+        }
+    }
+
+    /// List of all players with their card counts (hand + deck + reserve)
+    fn card_counts(&self) -> Vec<(String, u32)> {
         self.players
-            .get(self.turn)
-            .map(|(name, state)| (name.as_str(), state))
-            .unwrap()
+            .iter()
+            .map(|(name, state)| (name.clone(), state.card_count() as u32))
+            .collect()
     }
 
-    /// Check if any player has won (exactly one player has cards)
-    pub fn someone_has_won(&self) -> bool {
-        // note - zero should not be possible here, since one move ago exactly one player had a card
+    /// Every player's reserve card, in turn order - these are face up, so everyone sees them all
+    fn reserves(&self) -> Vec<Option<Card>> {
+        if !self.game_options.reserve_card {
+            return Vec::new();
+        }
         self.players
             .iter()
-            .filter(|(_, state)| state.has_cards())
-            .count()
-            <= 1
+            .map(|(_, state)| state.reserve)
+            .collect()
     }
 
-    /// Make a move
-    ///
-    /// If move is invalid, return false
-    pub fn apply_move(&mut self, player_move: PlayerMove) -> bool {
-        let (_, current_player) = &mut self.players[self.turn];
+    pub fn get_options(&self) -> &GameOptions {
+        &self.game_options
+    }
 
-        // Check - move must specify valid card within the current player's hand
-        if player_move.card >= current_player.hand.0.len() {
-            return false; // Card index out of bounds
-        }
+    pub fn get_players(&self) -> &[(String, PlayerState)] {
+        &self.players
+    }
 
-        // Check - validate move location according to game rules
-        let (row, col) = player_move.location;
-        if !self.board.can_play_at(row, col) {
-            return false;
-        }
+    /// Cards left for everyone to draw from, if playing with a shared deck
+    pub fn shared_deck(&self) -> &[Card] {
+        &self.shared_deck
+    }
 
-        // Play the card
-        let card = current_player.hand.0.remove(player_move.card);
-        self.board.0[row][col] = Some(card);
+    /// Cards sequestered or taken out by forfeits
+    pub fn out_of_play(&self) -> &[Card] {
+        &self.out_of_play
+    }
 
-        // Find cards to take before making any mutations
-        let cards_to_take = match self.game_options.taking_variant {
-            TakingVariant::SameNumber => {
-                // Find furthest-away cards orthogonally and diagonally with the same value
-                Self::find_taking_cards(&self.board, row, col, |target_card| {
-                    target_card.1 == card.1
-                })
-            }
-            TakingVariant::SameNumberOrSuitRanked => {
-                // Find furthest-away cards orthogonally and diagonally with either the same value or the same suit and a lesser value
-                Self::find_taking_cards(&self.board, row, col, |target_card| {
-                    target_card.1 == card.1
-                        || (target_card.0 == card.0 && (target_card.1 as u8) < (card.1 as u8))
-                })
-            }
-        };
+    /// The board as everyone sees it, without building a whole player's view
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
 
-        // If any were found, remove those cards, all cards between them, and the just-played card
-        let mut taken_cards = cards_to_take
-            .into_iter()
-            .filter_map(|(row, col)| self.board.0[row][col].take())
-            .collect::<Vec<_>>();
-        taken_cards.shuffle(&mut rng());
-        current_player.deck.0.extend(taken_cards);
+    /// Index of the player whose turn it is
+    pub fn turn(&self) -> usize {
+        self.turn
+    }
+
+    /// The moves from this player's own last one onward, or as many of the latest as fit in the
+    /// limit if that's further back
+    pub fn moves_since_last_by(&self, player: &str, limit: usize) -> &[LastMove] {
+        let own_last = self
+            .history
+            .iter()
+            .rposition(|last_move| last_move.player == player)
+            .unwrap_or(0);
+        &self.history[own_last.max(self.history.len().saturating_sub(limit))..]
+    }
 
-        // Draw cards from deck to fill hand to HAND_SIZE
-        while !current_player.deck.0.is_empty() && current_player.hand.0.len() < HAND_SIZE {
-            current_player.hand.0.push(current_player.deck.0.remove(0));
+    /// Where every card is now, which is the deal if no move has been made yet
+    pub fn opening_deal(&self) -> OpeningDeal {
+        OpeningDeal {
+            board: self.board.clone(),
+            players: self
+                .players
+                .iter()
+                .map(|(player, player_state)| OpeningPile {
+                    player: player.clone(),
+                    reserve: player_state.reserve,
+                    hand: player_state.hand.0.clone(),
+                    deck: player_state.deck.0.clone(),
+                })
+                .collect(),
+            shared_deck: self.shared_deck.clone(),
+            out_of_play: self.out_of_play.clone(),
         }
+    }
 
-        // Move to next player's turn, skip players with no cards (must have at least one player with cards)
-        self.turn = (self.turn + 1) % self.players.len();
-        while !self.current_player().1.has_cards() {
-            self.turn = (self.turn + 1) % self.players.len();
+    /// The board, turn, and card counts, which are safe to log
+    pub fn public_snapshot(&self) -> PublicGameState {
+        PublicGameState {
+            board: self.board.clone(),
+            turn: self.turn,
+            players: self.card_counts(),
         }
+    }
 
-        true
+    /// Whether everyone picks their move at once each round, instead of taking turns
+    pub fn is_simultaneous(&self) -> bool {
+        self.game_options.simultaneous
     }
 
-    /// Find cards that can be taken based on the given predicate
+    /// Stop taking moves until the game is resumed, returning false if it was already paused
     ///
-    /// Returns positions of cards to be taken
-    fn find_taking_cards(
-        board: &Board,
-        card_row: usize,
-        card_col: usize,
-        predicate: impl Fn(Card) -> bool,
-    ) -> Vec<(usize, usize)> {
-        let mut to_take = Vec::new();
-
-        // Define the 8 directions: 4 orthogonal + 4 diagonal
-        let directions = [
-            // orthogonal
-            (-1, 0),
-            (1, 0),
-            (0, -1),
-            (0, 1),
-            // diagonal
-            (-1, -1),
-            (-1, 1),
-            (1, -1),
-            (1, 1),
-        ];
+    /// Whoever's turn it is keeps it, and moves already picked this round stay picked
+    pub fn pause(&mut self) -> bool {
+        if self.paused.is_some() {
+            return false;
+        }
+        self.paused = Some(Instant::now());
+        true
+    }
 
-        for (dr, dc) in directions {
-            // Search in this direction for the last matching card
-            let mut row = card_row as i32 + dr;
-            let mut col = card_col as i32 + dc;
-            let mut found = None;
-            while (0..BOARD_SIZE as i32).contains(&row) && (0..BOARD_SIZE as i32).contains(&col) {
-                if let Some(board_card) = board.0[row as usize][col as usize]
-                    && predicate(board_card)
-                {
-                    found = Some((row, col))
-                }
+    /// Take moves again, returning how long the game was paused, or None if it wasn't
+    pub fn resume(&mut self) -> Option<Duration> {
+        self.paused.take().map(|paused| paused.elapsed())
+    }
 
-                row += dr;
-                col += dc;
-            }
+    pub fn is_paused(&self) -> bool {
+        self.paused.is_some()
+    }
 
-            if let Some((end_row, end_col)) = found {
-                let mut row = card_row as i32;
-                let mut col = card_col as i32;
-                while row != end_row || col != end_col {
-                    to_take.push((row as usize, col as usize));
-                    row += dr;
-                    col += dc;
-                }
-                // Also take the final matching card
-                to_take.push((end_row as usize, end_col as usize));
-            }
-        }
+    /// Every move made so far, oldest first
+    pub fn moves(&self) -> &[LastMove] {
+        &self.history
+    }
 
-        to_take
+    /// How many moves have been played, which unlike the turn only ever goes up
+    pub fn move_count(&self) -> usize {
+        self.history.len()
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// How many cards the most recent move took from the board, not counting the one played
+    ///
+    /// Unlike the taken positions of the last move, this skips any empty cells between them
+    pub fn last_captured(&self) -> usize {
+        self.last_captured
+    }
 
-    fn create_test_options(sequester: bool) -> GameOptions {
-        GameOptions {
-            sequester_cards: sequester,
-            taking_variant: TakingVariant::SameNumber,
-        }
+    /// Number of players, including any who have been eliminated
+    pub fn player_count(&self) -> usize {
+        self.players.len()
+    }
+
+    pub fn get_player_names(&self) -> Vec<String> {
+        self.players.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    pub fn current_player(&self) -> (&str, &PlayerState) {
+        self.players
+            .get(self.turn)
+            .map(|(name, state)| (name.as_str(), state))
+            .unwrap()
+    }
+
+    /// Check if any player has won (exactly one player has cards)
+    pub fn someone_has_won(&self) -> bool {
+        // note - zero should not be possible here, since one move ago exactly one player had a card
+        self.players
+            .iter()
+            .filter(|(_, state)| state.has_cards())
+            .count()
+            <= 1
+    }
+
+    /// The name of the player who won, if the game is over
+    ///
+    /// This is the only player left with cards, who isn't necessarily the one who just moved.
+    /// Playing your last card never wins, even if it leaves one other player with cards - that
+    /// player wins. If no-one has cards, which only happens when everyone forfeits, no-one wins.
+    /// Clients apply the same rule with [`PlayerVisibleGameState::winner`].
+    pub fn winner(&self) -> Option<&str> {
+        let mut with_cards = self.players.iter().filter(|(_, state)| state.has_cards());
+        match (with_cards.next(), with_cards.next()) {
+            (Some((username, _)), None) => Some(username),
+            _ => None,
+        }
+    }
+
+    /// Make a move on behalf of a player, checking it's their turn first
+    ///
+    /// Nothing changes if the move isn't applied
+    pub fn apply_move_by(
+        &mut self,
+        username: &str,
+        player_move: PlayerMove,
+    ) -> Result<MoveOutcome, MoveError> {
+        if self.is_paused() {
+            return Err(MoveError::Paused);
+        }
+        if username != self.current_player().0 {
+            return Err(MoveError::NotYourTurn);
+        }
+        self.try_move(player_move)?;
+        Ok(match self.winner() {
+            Some(winner) => MoveOutcome::Won(winner.to_string()),
+            None => MoveOutcome::Continues,
+        })
+    }
+
+    /// Pick a player's move for this round, to be played once everyone still in has picked theirs
+    ///
+    /// The move is checked against the game as it stands, and then again when it's played, since
+    /// moves earlier in the round can take its spot. Nothing changes if it isn't accepted.
+    pub fn submit_move_by(
+        &mut self,
+        username: &str,
+        player_move: PlayerMove,
+    ) -> Result<MoveOutcome, MoveError> {
+        if self.is_paused() {
+            return Err(MoveError::Paused);
+        }
+        let Some(seat) = self
+            .players
+            .iter()
+            .position(|(player, player_state)| player == username && player_state.has_cards())
+        else {
+            return Err(MoveError::NotYourTurn);
+        };
+        if self.submitted[seat].is_some() {
+            return Err(MoveError::AlreadySubmitted);
+        }
+        let mut lookahead = self.clone();
+        lookahead.turn = seat;
+        lookahead.try_move(player_move.clone())?;
+
+        self.submitted[seat] = Some(player_move);
+        self.play_round_if_ready();
+        Ok(match self.winner() {
+            Some(winner) => MoveOutcome::Won(winner.to_string()),
+            None => MoveOutcome::Continues,
+        })
+    }
+
+    /// Play out the round's moves in turn order, if everyone still in has picked one
+    ///
+    /// Moves that can no longer be played when their turn comes are skipped, and listed for
+    /// [`GameState::take_skipped`]. Returns whether the round was played.
+    pub fn play_round_if_ready(&mut self) -> bool {
+        let ready = self
+            .players
+            .iter()
+            .zip(&self.submitted)
+            .all(|((_, player_state), submitted)| submitted.is_some() || !player_state.has_cards());
+        if !self.game_options.simultaneous || !ready || self.submitted.iter().all(Option::is_none) {
+            return false;
+        }
+
+        let moves = std::mem::replace(&mut self.submitted, vec![None; self.players.len()]);
+        for (seat, player_move) in moves.into_iter().enumerate() {
+            let Some(player_move) = player_move else {
+                continue;
+            };
+            self.turn = seat;
+            if let Err(err) = self.try_move(player_move) {
+                self.skipped.push((self.players[seat].0.clone(), err));
+            }
+            if self.someone_has_won() {
+                return true;
+            }
+        }
+
+        // rounds always start from the first seat still in
+        self.turn = 0;
+        while !self.current_player().1.has_cards() {
+            self.turn += 1;
+        }
+        true
+    }
+
+    /// Moves from the last round that couldn't be played, with who picked them and why
+    ///
+    /// Each is only handed out once
+    pub fn take_skipped(&mut self) -> Vec<(String, MoveError)> {
+        std::mem::take(&mut self.skipped)
+    }
+
+    /// Whether each player has picked their move this round, if everyone moves at once
+    fn submitted_flags(&self) -> Option<Vec<bool>> {
+        self.game_options
+            .simultaneous
+            .then(|| self.submitted.iter().map(Option::is_some).collect())
+    }
+
+    /// Make a move, for tests that only care whether it was made
+    #[cfg(test)]
+    fn apply_move(&mut self, player_move: PlayerMove) -> bool {
+        self.try_move(player_move).is_ok()
+    }
+
+    /// Make a move for the current player, saying what's wrong with it if it can't be made
+    ///
+    /// Nothing changes if the move isn't applied
+    fn try_move(&mut self, player_move: PlayerMove) -> Result<(), MoveError> {
+        let (_, current_player) = &mut self.players[self.turn];
+
+        // Check - move must specify a card the current player has
+        match player_move.from {
+            MoveSource::Hand if player_move.card >= current_player.hand.0.len() => {
+                return Err(MoveError::NoSuchCard(player_move.card));
+            }
+            MoveSource::Reserve if current_player.reserve.is_none() => {
+                // already played, or never dealt
+                return Err(MoveError::NoReserve);
+            }
+            MoveSource::Hand | MoveSource::Reserve => {}
+        }
+
+        // Check - validate move location according to game rules
+        let (row, col) = player_move.location;
+        let context = PlayContext {
+            wrap: self.game_options.wrap,
+            started: self.has_started_placing,
+            first_move: self.game_options.first_move,
+        };
+        if !self.board.can_play_at(row, col, context) {
+            return Err(MoveError::IllegalPosition((row, col)));
+        }
+
+        // Check - only some games let players turn down taking cards, and only in real directions
+        if !player_move.declined.is_empty() && !self.game_options.optional_capture {
+            return Err(MoveError::CaptureNotOptional);
+        }
+        if let Some(&direction) = player_move
+            .declined
+            .iter()
+            .find(|direction| !DIRECTIONS.contains(direction))
+        {
+            return Err(MoveError::NoSuchDirection(direction));
+        }
+        let directions = DIRECTIONS
+            .into_iter()
+            .filter(|direction| !player_move.declined.contains(direction))
+            .collect::<Vec<_>>();
+
+        // Check - the card must take something, if the options ask for it and it's possible
+        let card = match player_move.from {
+            MoveSource::Hand => current_player.hand.0[player_move.card],
+            MoveSource::Reserve => current_player.reserve.expect("checked above"),
+        };
+        if self.game_options.require_capture
+            && find_captures_in(
+                &self.board,
+                card,
+                (row, col),
+                self.game_options.taking_variant,
+                self.game_options.rank_order,
+                self.game_options.wrap,
+                &directions,
+            )
+            .is_empty()
+            && self.current_player_can_take()
+        {
+            return Err(MoveError::MustCapture);
+        }
+
+        // Play the card
+        let (_, current_player) = &mut self.players[self.turn];
+        current_player.drawn = 0;
+        match player_move.from {
+            MoveSource::Hand => {
+                current_player.hand.0.remove(player_move.card);
+            }
+            MoveSource::Reserve => current_player.reserve = None,
+        }
+        self.board.0[row][col] = Some(card);
+        self.has_started_placing = true;
+
+        // Find cards to take before making any mutations
+        let cards_to_take = find_captures_in(
+            &self.board,
+            card,
+            (row, col),
+            self.game_options.taking_variant,
+            self.game_options.rank_order,
+            self.game_options.wrap,
+            &directions,
+        );
+        let cards_to_take = match self.game_options.max_capture {
+            Some(max_capture) => self.nearest_captures(cards_to_take, (row, col), max_capture),
+            None => cards_to_take,
+        };
+
+        self.history.push(LastMove {
+            player: self.players[self.turn].0.clone(),
+            card,
+            location: (row, col),
+            taken: cards_to_take.clone(),
+            // absorb_cards puts everything taken into the mover's deck
+            taken_to: TakenTo::MoverDeck,
+        });
+
+        // If any were found, remove those cards, all cards between them, and the just-played card.
+        // The positions are distinct, so the only cells without a card are gaps between them
+        let taken_cards = cards_to_take
+            .into_iter()
+            .filter_map(|(row, col)| self.board.0[row][col].take())
+            .collect::<Vec<_>>();
+        self.last_captured = taken_cards.len().saturating_sub(1);
+        self.absorb_cards(self.turn, taken_cards);
+
+        // Draw back up to a full hand, unless that waits for the start of the next turn
+        if !self.game_options.deal_to_empty {
+            self.fill_hand(self.turn);
+        }
+
+        // Move to next player's turn
+        self.advance_turn();
+
+        #[cfg(debug_assertions)]
+        self.assert_card_conservation();
+
+        Ok(())
+    }
+
+    /// Cut captures down to the played card and the nearest `max_capture` cards to it
+    ///
+    /// The cap is on the total across every direction, so a long line can crowd out a short one.
+    /// Spots the same distance away are taken in board order, and gaps aren't taken at all.
+    fn nearest_captures(
+        &self,
+        mut captures: Vec<(usize, usize)>,
+        played: (usize, usize),
+        max_capture: usize,
+    ) -> Vec<(usize, usize)> {
+        let wrap = self.game_options.wrap;
+        let offset = |from: usize, to: usize| {
+            let offset = from.abs_diff(to);
+            if wrap {
+                offset.min(BOARD_SIZE - offset)
+            } else {
+                offset
+            }
+        };
+        captures.sort_by_key(|&(row, col)| {
+            (offset(row, played.0).max(offset(col, played.1)), row, col)
+        });
+
+        let mut taken = 0;
+        captures.retain(|&(row, col)| {
+            if (row, col) == played {
+                return true;
+            }
+            if self.board.0[row][col].is_none() || taken == max_capture {
+                return false;
+            }
+            taken += 1;
+            true
+        });
+        captures.sort();
+        captures
+    }
+
+    /// Whether the current player has a card that would take something somewhere
+    fn current_player_can_take(&self) -> bool {
+        let (_, player_state) = &self.players[self.turn];
+        let cards = player_state
+            .hand
+            .0
+            .iter()
+            .copied()
+            .chain(player_state.reserve)
+            .collect::<Vec<_>>();
+        can_take_any(
+            &self.board,
+            &cards,
+            self.game_options.taking_variant,
+            self.game_options.rank_order,
+            self.game_options.wrap,
+            self.has_started_placing,
+            self.game_options.first_move,
+        )
+    }
+
+    /// Add taken cards to a player's deck, in the order and at the end set by the options
+    ///
+    /// Cards are given in board order
+    fn absorb_cards(&mut self, player_index: usize, mut cards: Vec<Card>) {
+        match self.game_options.capture_order {
+            CaptureOrder::Shuffled => cards.shuffle(&mut self.rng),
+            CaptureOrder::Ordered => {}
+        }
+
+        let deck = &mut self.players[player_index].1.deck.0;
+        match self.game_options.capture_placement {
+            CapturePlacement::Bottom => deck.extend(cards),
+            CapturePlacement::Top => {
+                deck.splice(0..0, cards);
+            }
+        }
+    }
+
+    /// Check that every card of the deck is in exactly one place - on the board, in a hand or
+    /// deck, or out of play
+    pub fn validate_integrity(&self) -> Result<(), IntegrityError> {
+        let mut unplaced = Suit::ALL
+            .into_iter()
+            .flat_map(|suit| Value::ALL.map(|value| Card(suit, value)))
+            .collect::<Vec<_>>();
+        let mut extra = Vec::new();
+        let cards = self
+            .board
+            .0
+            .iter()
+            .flatten()
+            .flatten()
+            .chain(self.players.iter().flat_map(|(_, state)| {
+                state
+                    .hand
+                    .0
+                    .iter()
+                    .chain(&state.deck.0)
+                    .chain(&state.reserve)
+            }))
+            .chain(&self.out_of_play)
+            .chain(&self.shared_deck);
+        for &card in cards {
+            match unplaced.iter().position(|&unplaced| unplaced == card) {
+                Some(index) => {
+                    unplaced.remove(index);
+                }
+                None => extra.push(card),
+            }
+        }
+
+        if unplaced.is_empty() && extra.is_empty() {
+            Ok(())
+        } else {
+            Err(IntegrityError {
+                missing: unplaced,
+                extra,
+            })
+        }
+    }
+
+    /// Panics if any card has been lost or duplicated
+    #[cfg(any(debug_assertions, test))]
+    fn assert_card_conservation(&self) {
+        if let Err(err) = self.validate_integrity() {
+            panic!("{err}");
+        }
+    }
+
+    /// Remove a player from the game, taking their cards out of play
+    ///
+    /// If it was their turn, play moves on to the next player
+    pub fn forfeit(&mut self, player_index: usize) {
+        let (_, player_state) = &mut self.players[player_index];
+        self.out_of_play.append(&mut player_state.hand.0);
+        self.out_of_play.append(&mut player_state.deck.0);
+        self.out_of_play.extend(player_state.reserve.take());
+        // nothing's left in the hand to have been drawn
+        player_state.drawn = 0;
+        if let Some(submitted) = self.submitted.get_mut(player_index) {
+            *submitted = None;
+        }
+
+        if player_index == self.turn && !self.someone_has_won() {
+            self.advance_turn();
+        }
+    }
+
+    /// Move to next player's turn, skip players with no cards (must have at least one player with cards)
+    fn advance_turn(&mut self) {
+        self.turn = (self.turn + 1) % self.players.len();
+        while !self.current_player().1.has_cards() {
+            self.turn = (self.turn + 1) % self.players.len();
+        }
+        if self.game_options.deal_to_empty {
+            self.fill_hand(self.turn);
+        }
+    }
+
+    /// Draw a player's cards for the turn, from the shared deck while it lasts and then from their
+    /// own deck
+    ///
+    /// That's back up to HAND_SIZE, or a set number of cards up to the largest hand allowed, if
+    /// the game draws a set number. Taken cards are already in the deck, so once it's empty there's
+    /// nothing left to reshuffle in - the hand just stays short
+    fn fill_hand(&mut self, player_index: usize) {
+        let (_, player_state) = &mut self.players[player_index];
+        let target = match self.game_options.draw_per_turn {
+            Some(draws) => player_state
+                .hand
+                .0
+                .len()
+                .saturating_add(draws)
+                .min(self.game_options.max_hand_size),
+            None => HAND_SIZE,
+        };
+        let before = player_state.hand.0.len();
+        while !self.shared_deck.is_empty() && player_state.hand.0.len() < target {
+            player_state.hand.0.push(self.shared_deck.remove(0));
+        }
+        while !player_state.deck.0.is_empty() && player_state.hand.0.len() < target {
+            player_state.hand.0.push(player_state.deck.0.remove(0));
+        }
+        player_state.drawn = player_state.hand.0.len() - before;
+    }
+}
+
+/// Split cards into piles of the given sizes, keeping the piles' total points as even as possible
+///
+/// Each card, highest first, goes to the pile with the fewest points that still has room. Piles are
+/// then shuffled so their order doesn't give away the deal.
+fn balanced_piles(cards: &[Card], shares: &[usize], rng: &mut StdRng) -> Vec<Vec<Card>> {
+    let mut cards = cards.to_vec();
+    cards.sort_by_key(|card| Reverse(card.1.points()));
+
+    let mut piles = vec![Vec::new(); shares.len()];
+    let mut totals = vec![0; shares.len()];
+    for card in cards {
+        let pile = (0..shares.len())
+            .filter(|&pile| piles[pile].len() < shares[pile])
+            .min_by_key(|&pile| totals[pile])
+            .expect("shares should add up to the number of cards");
+        piles[pile].push(card);
+        totals[pile] += card.1.points();
+    }
+
+    for pile in &mut piles {
+        pile.shuffle(rng);
+    }
+    piles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::PositionError;
+    use grid_common::{CardDestination, find_captures};
+
+    /// Options parsed from command line arguments, with a preset applied
+    fn options_with_preset(preset: Preset, args: &[&str]) -> GameOptions {
+        #[derive(clap::Parser)]
+        struct Cli {
+            #[clap(flatten)]
+            options: GameOptions,
+        }
+
+        let matches = <Cli as clap::CommandFactory>::command().get_matches_from(
+            ["grid_server", "--taking-variant", "same-number"]
+                .iter()
+                .chain(args),
+        );
+        let mut options = <Cli as clap::FromArgMatches>::from_arg_matches(&matches)
+            .unwrap()
+            .options;
+        options.apply_preset(preset, &matches);
+        options
+    }
+
+    fn create_test_options(sequester: bool) -> GameOptions {
+        GameOptions {
+            sequester_cards: sequester,
+            sequester_count: 1,
+            taking_variant: TakingVariant::SameNumber,
+            rank_order: RankOrder::AceLow,
+            first_move: FirstMove::Center,
+            seed: None,
+            capture_order: CaptureOrder::Shuffled,
+            capture_placement: CapturePlacement::Bottom,
+            peek_top: false,
+            wrap: false,
+            balanced_deal: false,
+            deck_seed_from_join_code: false,
+            reserve_card: false,
+            deal_to_empty: false,
+            shared_deck: false,
+            require_capture: false,
+            hide_own_deck: false,
+            face_card_hands: false,
+            draw_per_turn: None,
+            max_hand_size: HAND_SIZE,
+            max_capture: None,
+            announce_draws: false,
+            simultaneous: false,
+            optional_capture: false,
+            teaching_deal: false,
+        }
+    }
+
+    /// Move a card from wherever it is onto the board, refilling the hand if it came from there
+    fn place_on_board(game_state: &mut GameState, card: Card, (row, col): (usize, usize)) {
+        for (_, player_state) in &mut game_state.players {
+            if let Some(index) = player_state.hand.0.iter().position(|c| *c == card) {
+                player_state.hand.0[index] = player_state.deck.0.remove(0);
+            }
+            player_state.deck.0.retain(|c| *c != card);
+        }
+        game_state.out_of_play.retain(|c| *c != card);
+        game_state.board.0[row][col] = Some(card);
+    }
+
+    /// Swap a card into the first slot of the first player's hand
+    fn put_in_hand(game_state: &mut GameState, card: Card) {
+        let old_card = game_state.players[0].1.hand.0[0];
+        for (_, player_state) in &mut game_state.players {
+            for slot in player_state
+                .hand
+                .0
+                .iter_mut()
+                .chain(player_state.deck.0.iter_mut())
+            {
+                if *slot == card {
+                    *slot = old_card;
+                }
+            }
+        }
+        for slot in &mut game_state.out_of_play {
+            if *slot == card {
+                *slot = old_card;
+            }
+        }
+        game_state.players[0].1.hand.0[0] = card;
     }
 
     #[test]
@@ -342,6 +1667,10 @@ mod tests {
         assert_eq!(game_state.players[0].0, "Alice");
         assert_eq!(game_state.players[1].0, "Bob");
         assert_eq!(game_state.turn, 0);
+
+        assert_eq!(game_state.player_count(), 2);
+        assert_eq!(game_state.turn(), 0);
+        assert!(game_state.board().is_empty());
     }
 
     #[test]
@@ -368,6 +1697,170 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_game_state_creation_with_sequester_count() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+
+        // 52 cards / (2 real + 2 sequester) = 13 cards per player
+        let options = GameOptions {
+            sequester_count: 2,
+            ..create_test_options(true)
+        };
+        let game_state = GameState::new(player_names.clone(), options);
+        for (_, player_state) in &game_state.players {
+            let total_cards = player_state.hand.0.len() + player_state.deck.0.len();
+            assert_eq!(total_cards, 13);
+        }
+
+        // 52 cards / (2 real + 4 sequester) = 8 cards per player
+        let options = GameOptions {
+            sequester_count: 4,
+            ..create_test_options(true)
+        };
+        let game_state = GameState::new(player_names, options);
+        for (_, player_state) in &game_state.players {
+            let total_cards = player_state.hand.0.len() + player_state.deck.0.len();
+            assert_eq!(total_cards, 8);
+        }
+    }
+
+    #[test]
+    fn test_validate_sequester_count() {
+        let options = GameOptions {
+            sequester_count: 6,
+            ..create_test_options(true)
+        };
+        // 52 / (4 + 6) = 5 cards each, just enough for a hand
+        assert!(options.validate(4).is_ok());
+
+        let options = GameOptions {
+            sequester_count: 7,
+            ..create_test_options(true)
+        };
+        // 52 / (4 + 7) = 4 cards each, not enough for a hand
+        assert!(matches!(
+            options.validate(4),
+            Err(OptionsError::SequesterTooLarge { .. })
+        ));
+
+        // sequester count is ignored if not sequestering
+        let options = GameOptions {
+            sequester_count: 7,
+            ..create_test_options(false)
+        };
+        assert!(options.validate(4).is_ok());
+
+        // a count too big to add to the players is refused, not overflowed
+        let options = GameOptions {
+            sequester_count: usize::MAX,
+            ..create_test_options(true)
+        };
+        assert!(options.validate(4).is_err());
+        GameState::new(vec!["Alice".to_string(), "Bob".to_string()], options);
+    }
+
+    #[test]
+    fn test_validate_under_deal() {
+        // too many players for everyone to get a card, sequestering or not
+        assert!(matches!(
+            create_test_options(false).validate(53),
+            Err(OptionsError::NoCardsToDeal {
+                num_players: 53,
+                sequester_count: 0
+            })
+        ));
+        assert!(matches!(
+            create_test_options(true).validate(52),
+            Err(OptionsError::NoCardsToDeal {
+                num_players: 52,
+                sequester_count: 1
+            })
+        ));
+        assert!(matches!(
+            create_test_options(false).validate(0),
+            Err(OptionsError::NoCardsToDeal { .. })
+        ));
+        assert!(matches!(
+            GameOptions {
+                sequester_count: usize::MAX,
+                ..create_test_options(true)
+            }
+            .validate(2),
+            Err(OptionsError::NoCardsToDeal { .. })
+        ));
+
+        // one card each is a poor game, but a playable one
+        assert!(create_test_options(false).validate(52).is_ok());
+    }
+
+    #[test]
+    fn test_seed_from_join_code() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let options = GameOptions {
+            deck_seed_from_join_code: true,
+            ..create_test_options(false)
+        };
+        let deal = |join_code: &str| {
+            let game_state = GameState::new(player_names.clone(), options.for_join_code(join_code));
+            game_state
+                .players
+                .iter()
+                .map(|(_, player_state)| (player_state.hand.0.clone(), player_state.deck.0.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(deal("abc123"), deal("abc123"));
+        assert_ne!(deal("abc123"), deal("abc124"));
+
+        // the seed is part of the deal, so it mustn't change between builds
+        assert_eq!(seed_from_join_code(""), 0xcbf29ce484222325);
+        assert_eq!(seed_from_join_code("a"), 0xaf63dc4c8601ec8c);
+
+        // without the option, the join code is ignored
+        assert!(
+            create_test_options(false)
+                .for_join_code("abc123")
+                .seed
+                .is_none()
+        );
+        let options = GameOptions {
+            seed: Some(1),
+            ..options
+        };
+        assert!(matches!(
+            options.validate(2),
+            Err(OptionsError::ConflictingSeeds)
+        ));
+    }
+
+    #[test]
+    fn test_options_json() {
+        // option values are spelled the same as on the command line
+        let json = serde_json::to_value(create_test_options(true)).unwrap();
+        assert_eq!(json["taking_variant"], "same-number");
+        assert_eq!(json["capture_placement"], "bottom");
+
+        let options = serde_json::from_value::<GameOptions>(json).unwrap();
+        assert!(options.sequester_cards);
+        assert!(matches!(options.taking_variant, TakingVariant::SameNumber));
+
+        // options a client doesn't know about are left at their defaults, and ones the server
+        // doesn't know about are ignored
+        let options = serde_json::from_str::<GameOptions>(
+            r#"{"sequester_cards":true,"some_future_option":3}"#,
+        )
+        .unwrap();
+        assert!(options.sequester_cards);
+        assert_eq!(options.sequester_count, 1);
+        assert!(matches!(options.capture_order, CaptureOrder::Shuffled));
+        assert!(matches!(
+            options.capture_placement,
+            CapturePlacement::Bottom
+        ));
+        assert_eq!(options.max_hand_size, HAND_SIZE);
+        assert!(!options.require_capture);
+    }
+
     #[test]
     fn test_game_state_creation_without_sequester() {
         let player_names = vec!["Alice".to_string(), "Bob".to_string()];
@@ -445,6 +1938,11 @@ mod tests {
         assert_eq!(alice_state.username, "Alice");
         assert_eq!(bob_state.username, "Bob");
 
+        // and their own seat
+        assert_eq!(alice_state.my_index, Some(0));
+        assert_eq!(bob_state.my_index, Some(1));
+        assert!(alice_state.is_my_turn() && !bob_state.is_my_turn());
+
         // Each player should see the same board and turn
         assert_eq!(alice_state.board.0, bob_state.board.0);
         assert_eq!(alice_state.turn, bob_state.turn);
@@ -455,13 +1953,149 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "Invalid player index: 2 (only 2 players exist)")]
-    fn test_state_for_invalid_player_index() {
+    fn test_state_for_peek_top() {
         let player_names = vec!["Alice".to_string(), "Bob".to_string()];
-        let options = create_test_options(false);
 
-        let game_state = GameState::new(player_names, options);
-        let _ = game_state.state_for(2); // Should panic
+        let game_state = GameState::new(player_names.clone(), create_test_options(false));
+        assert_eq!(game_state.state_for(0).top_card, None);
+
+        let options = GameOptions {
+            peek_top: true,
+            ..create_test_options(false)
+        };
+        let mut game_state = GameState::new(player_names, options);
+        let alice_state = game_state.state_for(0);
+        assert_eq!(alice_state.top_card, Some(alice_state.deck.0[0]));
+
+        // and it's the card that gets drawn
+        let top_card = alice_state.top_card;
+        assert!(game_state.apply_move(PlayerMove::new(
+            0,
+            (BOARD_SIZE / 2, BOARD_SIZE / 2),
+            MoveSource::Hand
+        )));
+        assert_eq!(game_state.players[0].1.hand.0.last().copied(), top_card);
+    }
+
+    #[test]
+    fn test_state_for_spectator() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let options = create_test_options(false);
+
+        let game_state = GameState::new(player_names, options);
+        let alice_state = game_state.state_for(0);
+        let carol_state = game_state.state_for_spectator("Carol");
+
+        // Spectators see the board and standings, but no-one's cards
+        assert_eq!(carol_state.username, "Carol");
+        assert_eq!(carol_state.board.0, alice_state.board.0);
+        assert_eq!(carol_state.players, alice_state.players);
+        assert_eq!(carol_state.turn, alice_state.turn);
+        assert!(carol_state.hand.0.is_empty());
+        assert!(carol_state.deck.0.is_empty());
+        assert_eq!(carol_state.my_index, None);
+    }
+
+    #[test]
+    fn test_public_snapshot() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let options = GameOptions {
+            seed: Some(0),
+            ..create_test_options(false)
+        };
+        let mut game_state = GameState::new(player_names, options);
+        assert!(game_state.apply_move(PlayerMove::new(
+            0,
+            (BOARD_SIZE / 2, BOARD_SIZE / 2),
+            MoveSource::Hand
+        )));
+
+        let snapshot = game_state.public_snapshot();
+        assert_eq!(snapshot.board, game_state.board);
+        assert_eq!(snapshot.players, game_state.state_for(0).players);
+        assert_eq!(snapshot.turn, 1);
+
+        // none of the cards players hold show up, only those on the board
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let held = game_state
+            .players
+            .iter()
+            .flat_map(|(_, player_state)| player_state.hand.0.iter().chain(&player_state.deck.0));
+        for card in held {
+            assert!(!json.contains(&serde_json::to_string(card).unwrap()));
+        }
+        let played = game_state.board.0[BOARD_SIZE / 2][BOARD_SIZE / 2].unwrap();
+        assert!(json.contains(&serde_json::to_string(&played).unwrap()));
+    }
+
+    #[test]
+    fn test_move_count() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let options = GameOptions {
+            seed: Some(0),
+            ..create_test_options(false)
+        };
+        let mut game_state = GameState::new(player_names, options);
+        assert_eq!(game_state.move_count(), 0);
+
+        let center = BOARD_SIZE / 2;
+        for (played, col) in (center..center + 3).enumerate() {
+            assert_eq!(game_state.turn(), played % 2);
+            assert!(game_state.apply_move(PlayerMove::new(0, (center, col), MoveSource::Hand)));
+            assert_eq!(game_state.move_count(), played + 1);
+            assert_eq!(game_state.state_for(0).move_count, played + 1);
+        }
+        // the turn comes back around, but the count keeps going
+        assert_eq!(game_state.turn(), 1);
+    }
+
+    #[test]
+    fn test_first_player() {
+        let shuffled = ["Alice", "Bob", "Carol"].map(str::to_string);
+        let starts = HashMap::from([("Alice".to_string(), 1), ("Carol".to_string(), 1)]);
+
+        let mut seats = shuffled.clone();
+        FirstPlayer::Random.seat(&mut seats, Some("Carol"), &starts);
+        assert_eq!(seats, shuffled);
+
+        // everyone keeps their neighbours
+        let mut seats = shuffled.clone();
+        FirstPlayer::Host.seat(&mut seats, Some("Carol"), &starts);
+        assert_eq!(seats, ["Carol", "Alice", "Bob"]);
+
+        let mut seats = shuffled.clone();
+        FirstPlayer::Rotate.seat(&mut seats, None, &starts);
+        assert_eq!(seats, ["Bob", "Carol", "Alice"]);
+        let game_state = GameState::new(seats.to_vec(), create_test_options(false));
+        assert_eq!(game_state.get_player_names()[game_state.turn()], "Bob");
+
+        // ties go to the earliest seat
+        let mut seats = shuffled.clone();
+        FirstPlayer::Rotate.seat(&mut seats, None, &HashMap::new());
+        assert_eq!(seats, shuffled);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid player index: 2 (only 2 players exist)")]
+    fn test_state_for_invalid_player_index() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let options = create_test_options(false);
+
+        let game_state = GameState::new(player_names, options);
+        let _ = game_state.state_for(2); // Should panic
+    }
+
+    #[test]
+    fn test_state_for_player() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let game_state = GameState::new(player_names, create_test_options(false));
+
+        assert_eq!(
+            game_state.state_for_player("Bob"),
+            Some(game_state.state_for(1))
+        );
+        // a connection for someone who isn't seated, as when the two drift apart
+        assert_eq!(game_state.state_for_player("Mallory"), None);
     }
 
     #[test]
@@ -508,6 +2142,9 @@ mod tests {
 
         let total_cards: usize = card_counts.iter().sum();
         assert_eq!(total_cards, 52, "Total cards should be 52");
+
+        // And no card should have been dealt twice
+        game_state.assert_card_conservation();
     }
 
     #[test]
@@ -516,12 +2153,58 @@ mod tests {
 
         let options1 = GameOptions {
             sequester_cards: false,
+            sequester_count: 1,
             taking_variant: TakingVariant::SameNumber,
+            rank_order: RankOrder::AceLow,
+            first_move: FirstMove::Center,
+            seed: None,
+            capture_order: CaptureOrder::Shuffled,
+            capture_placement: CapturePlacement::Bottom,
+            peek_top: false,
+            wrap: false,
+            balanced_deal: false,
+            deck_seed_from_join_code: false,
+            reserve_card: false,
+            deal_to_empty: false,
+            shared_deck: false,
+            require_capture: false,
+            hide_own_deck: false,
+            face_card_hands: false,
+            draw_per_turn: None,
+            max_hand_size: HAND_SIZE,
+            max_capture: None,
+            announce_draws: false,
+            simultaneous: false,
+            optional_capture: false,
+            teaching_deal: false,
         };
 
         let options2 = GameOptions {
             sequester_cards: false,
+            sequester_count: 1,
             taking_variant: TakingVariant::SameNumberOrSuitRanked,
+            rank_order: RankOrder::AceLow,
+            first_move: FirstMove::Center,
+            seed: None,
+            capture_order: CaptureOrder::Shuffled,
+            capture_placement: CapturePlacement::Bottom,
+            peek_top: false,
+            wrap: false,
+            balanced_deal: false,
+            deck_seed_from_join_code: false,
+            reserve_card: false,
+            deal_to_empty: false,
+            shared_deck: false,
+            require_capture: false,
+            hide_own_deck: false,
+            face_card_hands: false,
+            draw_per_turn: None,
+            max_hand_size: HAND_SIZE,
+            max_capture: None,
+            announce_draws: false,
+            simultaneous: false,
+            optional_capture: false,
+            teaching_deal: false,
         };
 
         let game_state1 = GameState::new(player_names.clone(), options1);
@@ -539,19 +2222,31 @@ mod tests {
         let mut game_state = GameState::new(player_names, options);
 
         // First move must be in center (5, 5) on 11x11 board
-        let move_corner = PlayerMove {
-            card: 0,
-            location: (0, 0),
-        };
+        let move_corner = PlayerMove::new(0, (0, 0), MoveSource::Hand);
         assert!(!game_state.apply_move(move_corner));
 
-        let move_center = PlayerMove {
-            card: 0,
-            location: (5, 5),
-        };
+        let move_center = PlayerMove::new(0, (5, 5), MoveSource::Hand);
         assert!(game_state.apply_move(move_center));
     }
 
+    #[test]
+    fn test_cleared_board_allows_any_position() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let mut game_state = GameState::new(player_names, create_test_options(false));
+        assert!(!game_state.state_for(0).started);
+        assert!(game_state.apply_move(PlayerMove::new(0, (5, 5), MoveSource::Hand)));
+        assert!(game_state.state_for(1).started);
+
+        // every card on the board gets taken
+        let card = game_state.board.0[5][5].take().unwrap();
+        game_state.players[0].1.deck.0.push(card);
+        assert!(game_state.board.is_empty());
+
+        // which isn't the start of the game, so the center isn't required
+        assert!(game_state.apply_move(PlayerMove::new(0, (0, 3), MoveSource::Hand)));
+        assert!(game_state.board.0[0][3].is_some());
+    }
+
     #[test]
     fn test_move_validation() {
         let player_names = vec!["Alice".to_string()];
@@ -559,140 +2254,128 @@ mod tests {
         let mut game_state = GameState::new(player_names, options);
 
         // Place first card in center
-        let center_move = PlayerMove {
-            card: 0,
-            location: (5, 5),
-        };
+        let center_move = PlayerMove::new(0, (5, 5), MoveSource::Hand);
         assert!(game_state.apply_move(center_move));
 
         // Try to place card on occupied space
-        let invalid_move = PlayerMove {
-            card: 0,
-            location: (5, 5),
-        };
+        let invalid_move = PlayerMove::new(0, (5, 5), MoveSource::Hand);
         assert!(!game_state.apply_move(invalid_move));
 
         // Try to place card out of bounds
-        let out_of_bounds = PlayerMove {
-            card: 0,
-            location: (15, 15),
-        };
+        let out_of_bounds = PlayerMove::new(0, (15, 15), MoveSource::Hand);
         assert!(!game_state.apply_move(out_of_bounds));
 
         // Try to use invalid card index
-        let invalid_card = PlayerMove {
-            card: 10,
-            location: (4, 4),
-        };
+        let invalid_card = PlayerMove::new(10, (4, 4), MoveSource::Hand);
         assert!(!game_state.apply_move(invalid_card));
     }
 
     #[test]
     fn test_same_number_taking_orthogonal() {
-        let player_names = vec!["Alice".to_string()];
-        let options = GameOptions {
-            sequester_cards: false,
-            taking_variant: TakingVariant::SameNumber,
-        };
-        let mut game_state = GameState::new(player_names, options);
-
-        // Manually set up board for testing
-        let test_card_ace_clubs = Card(Suit::Clubs, Value::Ace);
-        let test_card_ace_hearts = Card(Suit::Hearts, Value::Ace);
-
-        // Place cards manually on board
-        game_state.board.0[5][5] = Some(test_card_ace_clubs); // Center
-        game_state.board.0[5][7] = Some(test_card_ace_hearts); // Two spaces right
-
-        // Set up player's hand with an Ace
-        game_state.players[0].1.hand.0[0] = test_card_ace_clubs;
-
-        // Place Ace at (5, 6) - between center and (5, 7), should take both
-        let move_between = PlayerMove {
-            card: 0,
-            location: (5, 6),
-        };
-
-        let initial_deck_size = game_state.players[0].1.deck.0.len();
-        assert!(game_state.apply_move(move_between));
-
-        // Check that the move took cards (board should be empty, cards in deck)
-        assert!(game_state.board.0[5][5].is_none());
-        assert!(game_state.board.0[5][6].is_none()); // Played card also taken
-        assert!(game_state.board.0[5][7].is_none());
-
-        // Check that cards were added to deck
-        assert!(game_state.players[0].1.deck.0.len() > initial_deck_size);
+        let board = Board::from_positions(&[
+            ((5, 5), Card(Suit::Clubs, Value::Ace)),
+            ((5, 7), Card(Suit::Hearts, Value::Ace)),
+        ]);
+
+        // Ace at (5, 6) - between center and (5, 7), should take both
+        assert_eq!(
+            find_captures(
+                &board,
+                Card(Suit::Spades, Value::Ace),
+                (5, 6),
+                TakingVariant::SameNumber,
+                RankOrder::AceLow,
+                false
+            ),
+            vec![(5, 5), (5, 6), (5, 7)]
+        );
     }
 
     #[test]
     fn test_same_number_taking_diagonal() {
-        let player_names = vec!["Alice".to_string()];
-        let options = GameOptions {
-            sequester_cards: false,
-            taking_variant: TakingVariant::SameNumber,
-        };
-        let mut game_state = GameState::new(player_names, options);
-
-        let test_card_king = Card(Suit::Clubs, Value::King);
-
-        // Place cards diagonally
-        game_state.board.0[4][4] = Some(test_card_king);
-        game_state.board.0[7][7] = Some(test_card_king);
-
-        // Set up player's hand
-        game_state.players[0].1.hand.0[0] = test_card_king;
-
-        // Place King at (5, 5) - on diagonal between the two existing Kings
-        let diagonal_move = PlayerMove {
-            card: 0,
-            location: (5, 5),
-        };
-
-        let initial_deck_size = game_state.players[0].1.deck.0.len();
-        assert!(game_state.apply_move(diagonal_move));
-
-        // Check that diagonal taking worked
-        assert!(game_state.board.0[3][3].is_none());
-        assert!(game_state.board.0[5][5].is_none());
-        assert!(game_state.board.0[7][7].is_none());
-        assert!(game_state.players[0].1.deck.0.len() > initial_deck_size);
+        let board = Board::from_positions(&[
+            ((4, 4), Card(Suit::Clubs, Value::King)),
+            ((7, 7), Card(Suit::Hearts, Value::King)),
+        ]);
+
+        // King at (5, 5) - on diagonal between the two existing Kings, taking the gap too
+        assert_eq!(
+            find_captures(
+                &board,
+                Card(Suit::Spades, Value::King),
+                (5, 5),
+                TakingVariant::SameNumber,
+                RankOrder::AceLow,
+                false
+            ),
+            vec![(4, 4), (5, 5), (6, 6), (7, 7)]
+        );
     }
 
     #[test]
     fn test_same_number_or_suit_ranked_taking() {
-        let player_names = vec!["Alice".to_string()];
-        let options = GameOptions {
-            sequester_cards: false,
-            taking_variant: TakingVariant::SameNumberOrSuitRanked,
-        };
-        let mut game_state = GameState::new(player_names, options);
-
-        let card_five_hearts = Card(Suit::Hearts, Value::Five);
-        let card_three_hearts = Card(Suit::Hearts, Value::Three); // Same suit, lower value
-        let card_five_clubs = Card(Suit::Clubs, Value::Five); // Same value, different suit
-
-        // Place cards on board
-        game_state.board.0[5][4] = Some(card_three_hearts); // Should be taken (same suit, lower)
-        game_state.board.0[5][7] = Some(card_five_clubs); // Should be taken (same value)
-
-        // Set up player's hand
-        game_state.players[0].1.hand.0[0] = card_five_hearts;
+        let board = Board::from_positions(&[
+            ((5, 4), Card(Suit::Hearts, Value::Three)), // same suit, lower value
+            ((5, 7), Card(Suit::Clubs, Value::Five)),   // same value, different suit
+            ((4, 5), Card(Suit::Hearts, Value::Nine)),  // same suit, higher value
+        ]);
+
+        // Five of Hearts at center takes the lesser heart and the other five, but not the nine
+        assert_eq!(
+            find_captures(
+                &board,
+                Card(Suit::Hearts, Value::Five),
+                (5, 5),
+                TakingVariant::SameNumberOrSuitRanked,
+                RankOrder::AceLow,
+                false
+            ),
+            vec![(5, 4), (5, 5), (5, 6), (5, 7)]
+        );
+        // which wouldn't take the lesser heart if only numbers counted
+        assert_eq!(
+            find_captures(
+                &board,
+                Card(Suit::Hearts, Value::Five),
+                (5, 5),
+                TakingVariant::SameNumber,
+                RankOrder::AceLow,
+                false
+            ),
+            vec![(5, 5), (5, 6), (5, 7)]
+        );
+    }
 
-        // Place Five of Hearts at center
-        let center_move = PlayerMove {
-            card: 0,
-            location: (5, 5),
+    #[test]
+    fn test_rank_order() {
+        let takes = |on_board, card, rank_order| {
+            find_captures(
+                &Board::from_positions(&[((5, 3), on_board)]),
+                card,
+                (5, 5),
+                TakingVariant::SameNumberOrSuitRanked,
+                rank_order,
+                false,
+            )
         };
-
-        let initial_deck_size = game_state.players[0].1.deck.0.len();
-        assert!(game_state.apply_move(center_move));
-
-        // Both cards should be taken
-        assert!(game_state.board.0[5][4].is_none()); // Three of Hearts taken
-        assert!(game_state.board.0[5][5].is_none()); // Played card taken
-        assert!(game_state.board.0[5][7].is_none()); // Five of Clubs taken
-        assert!(game_state.players[0].1.deck.0.len() > initial_deck_size);
+        let ace = Card(Suit::Spades, Value::Ace);
+        let king = Card(Suit::Spades, Value::King);
+
+        // a king takes an ace only when aces are low
+        assert_eq!(
+            takes(ace, king, RankOrder::AceLow),
+            vec![(5, 3), (5, 4), (5, 5)]
+        );
+        assert!(takes(ace, king, RankOrder::AceHigh).is_empty());
+
+        // and an ace takes a king only when aces are high
+        assert!(takes(king, ace, RankOrder::AceLow).is_empty());
+        assert_eq!(
+            takes(king, ace, RankOrder::AceHigh),
+            vec![(5, 3), (5, 4), (5, 5)]
+        );
+        assert_eq!(RankOrder::AceHigh.rank(Value::Two), 1);
+        assert_eq!(RankOrder::AceHigh.rank(Value::Ace), 13);
     }
 
     #[test]
@@ -700,7 +2383,30 @@ mod tests {
         let player_names = vec!["Alice".to_string()];
         let options = GameOptions {
             sequester_cards: false,
+            sequester_count: 1,
             taking_variant: TakingVariant::SameNumber,
+            rank_order: RankOrder::AceLow,
+            first_move: FirstMove::Center,
+            seed: None,
+            capture_order: CaptureOrder::Shuffled,
+            capture_placement: CapturePlacement::Bottom,
+            peek_top: false,
+            wrap: false,
+            balanced_deal: false,
+            deck_seed_from_join_code: false,
+            reserve_card: false,
+            deal_to_empty: false,
+            shared_deck: false,
+            require_capture: false,
+            hide_own_deck: false,
+            face_card_hands: false,
+            draw_per_turn: None,
+            max_hand_size: HAND_SIZE,
+            max_capture: None,
+            announce_draws: false,
+            simultaneous: false,
+            optional_capture: false,
+            teaching_deal: false,
         };
         let mut game_state = GameState::new(player_names, options);
 
@@ -708,16 +2414,13 @@ mod tests {
         let card_king = Card(Suit::Hearts, Value::King);
 
         // Place different card on board
-        game_state.board.0[5][6] = Some(card_king);
+        place_on_board(&mut game_state, card_king, (5, 6));
 
         // Set up player's hand
-        game_state.players[0].1.hand.0[0] = card_ace;
+        put_in_hand(&mut game_state, card_ace);
 
         // Place Ace at center - no taking should occur
-        let center_move = PlayerMove {
-            card: 0,
-            location: (5, 5),
-        };
+        let center_move = PlayerMove::new(0, (5, 5), MoveSource::Hand);
 
         let initial_deck_size = game_state.players[0].1.deck.0.len();
         let initial_hand_size = game_state.players[0].1.hand.0.len();
@@ -738,26 +2441,48 @@ mod tests {
         let player_names = vec!["Alice".to_string()];
         let options = GameOptions {
             sequester_cards: false,
+            sequester_count: 1,
             taking_variant: TakingVariant::SameNumber,
+            rank_order: RankOrder::AceLow,
+            first_move: FirstMove::Center,
+            seed: None,
+            capture_order: CaptureOrder::Shuffled,
+            capture_placement: CapturePlacement::Bottom,
+            peek_top: false,
+            wrap: false,
+            balanced_deal: false,
+            deck_seed_from_join_code: false,
+            reserve_card: false,
+            deal_to_empty: false,
+            shared_deck: false,
+            require_capture: false,
+            hide_own_deck: false,
+            face_card_hands: false,
+            draw_per_turn: None,
+            max_hand_size: HAND_SIZE,
+            max_capture: None,
+            announce_draws: false,
+            simultaneous: false,
+            optional_capture: false,
+            teaching_deal: false,
         };
         let mut game_state = GameState::new(player_names, options);
 
-        let card_ace = Card(Suit::Clubs, Value::Ace);
+        let card_ace_clubs = Card(Suit::Clubs, Value::Ace);
+        let card_ace_diamonds = Card(Suit::Diamonds, Value::Ace);
+        let card_ace_spades = Card(Suit::Spades, Value::Ace);
         let card_two = Card(Suit::Hearts, Value::Two);
 
         // Place cards with intervening card
-        game_state.board.0[5][3] = Some(card_ace);
-        game_state.board.0[5][5] = Some(card_two); // Intervening card (different value)
-        game_state.board.0[5][7] = Some(card_ace);
+        place_on_board(&mut game_state, card_ace_clubs, (5, 3));
+        place_on_board(&mut game_state, card_two, (5, 5)); // Intervening card (different value)
+        place_on_board(&mut game_state, card_ace_diamonds, (5, 7));
 
         // Set up player's hand
-        game_state.players[0].1.hand.0[0] = card_ace;
+        put_in_hand(&mut game_state, card_ace_spades);
 
         // Place Ace at (5, 4) - should take all cards in the line including intervening
-        let move_with_intervening = PlayerMove {
-            card: 0,
-            location: (5, 4),
-        };
+        let move_with_intervening = PlayerMove::new(0, (5, 4), MoveSource::Hand);
 
         let initial_deck_size = game_state.players[0].1.deck.0.len();
         let initial_hand_size = game_state.players[0].1.hand.0.len();
@@ -777,45 +2502,1563 @@ mod tests {
 
     #[test]
     fn test_multiple_direction_taking() {
-        let player_names = vec!["Alice".to_string()];
+        // Queens in three directions from center, and a lesser Spade in the fourth
+        let board = Board::from_positions(&[
+            ((5, 4), Card(Suit::Clubs, Value::Queen)),    // West
+            ((5, 7), Card(Suit::Diamonds, Value::Queen)), // East
+            ((3, 5), Card(Suit::Hearts, Value::Queen)),   // North
+            ((7, 5), Card(Suit::Spades, Value::Five)),    // South
+        ]);
+
+        // Queen of Spades at center takes in all 4 directions
+        assert_eq!(
+            find_captures(
+                &board,
+                Card(Suit::Spades, Value::Queen),
+                (5, 5),
+                TakingVariant::SameNumberOrSuitRanked,
+                RankOrder::AceLow,
+                false
+            ),
+            vec![
+                (3, 5),
+                (4, 5),
+                (5, 4),
+                (5, 5),
+                (5, 6),
+                (5, 7),
+                (6, 5),
+                (7, 5)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_forfeit_removes_cards() {
+        let player_names = vec![
+            "Alice".to_string(),
+            "Bob".to_string(),
+            "Charlie".to_string(),
+        ];
+        let mut game_state = GameState::new(player_names, create_test_options(false));
+
+        game_state.forfeit(1);
+
+        assert!(!game_state.players[1].1.has_cards());
+        assert!(game_state.players[0].1.has_cards());
+        assert!(game_state.players[2].1.has_cards());
+        assert!(!game_state.someone_has_won());
+        // not Bob's turn, so the turn doesn't change
+        assert_eq!(game_state.turn, 0);
+    }
+
+    #[test]
+    fn test_forfeit_on_own_turn() {
+        let player_names = vec![
+            "Alice".to_string(),
+            "Bob".to_string(),
+            "Charlie".to_string(),
+        ];
+        let mut game_state = GameState::new(player_names, create_test_options(false));
+
+        // Alice forfeits on her turn, so it's Bob's turn
+        game_state.forfeit(0);
+        assert_eq!(game_state.turn, 1);
+
+        // Charlie forfeits, so Bob is the only player left
+        game_state.forfeit(2);
+        assert!(game_state.someone_has_won());
+        assert_eq!(game_state.turn, 1);
+    }
+
+    /// Take all of a player's cards out of play, then give them just the given cards in hand
+    fn leave_only(game_state: &mut GameState, player_index: usize, cards: &[Card]) {
+        let player_state = &mut game_state.players[player_index].1;
+        game_state.out_of_play.append(&mut player_state.hand.0);
+        game_state.out_of_play.append(&mut player_state.deck.0);
+        for (_, player_state) in &mut game_state.players {
+            player_state.hand.0.retain(|c| !cards.contains(c));
+            player_state.deck.0.retain(|c| !cards.contains(c));
+        }
+        game_state.out_of_play.retain(|c| !cards.contains(c));
+        game_state.players[player_index].1.hand.0.extend(cards);
+    }
+
+    #[test]
+    fn test_clone_plays_out_the_same() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let mut game_state = GameState::new(player_names, create_test_options(false));
+        place_on_board(&mut game_state, Card(Suit::Hearts, Value::Seven), (5, 5));
+        place_on_board(&mut game_state, Card(Suit::Hearts, Value::Two), (5, 6));
+        put_in_hand(&mut game_state, Card(Suit::Clubs, Value::Seven));
+        let capture = || PlayerMove::new(0, (5, 7), MoveSource::Hand);
+
+        // trying a move on a copy leaves the original alone
+        let mut lookahead = game_state.clone();
+        assert!(lookahead == game_state);
+        assert!(lookahead.apply_move(capture()));
+        assert!(lookahead != game_state);
+        assert_eq!(game_state.turn, 0);
+
+        // and the original, given the same move, ends up in the same place, shuffles included
+        assert!(game_state.apply_move(capture()));
+        assert!(lookahead == game_state);
+    }
+
+    #[test]
+    fn test_require_capture() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let hand = [
+            Card(Suit::Clubs, Value::Seven),
+            Card(Suit::Clubs, Value::Two),
+        ];
+        let wasted_play = || PlayerMove::new(1, (5, 6), MoveSource::Hand);
+
+        // plays that take nothing are fine by default
+        let mut game_state = GameState::new(player_names.clone(), create_test_options(false));
+        place_on_board(&mut game_state, Card(Suit::Hearts, Value::Seven), (5, 5));
+        leave_only(&mut game_state, 0, &hand);
+        assert!(game_state.apply_move(wasted_play()));
+
+        // but not when the seven could have taken something
         let options = GameOptions {
-            sequester_cards: false,
-            taking_variant: TakingVariant::SameNumber,
+            require_capture: true,
+            ..create_test_options(false)
         };
+        let mut game_state = GameState::new(player_names.clone(), options.clone());
+        place_on_board(&mut game_state, Card(Suit::Hearts, Value::Seven), (5, 5));
+        leave_only(&mut game_state, 0, &hand);
+        assert!(!game_state.apply_move(wasted_play()));
+        assert_eq!(game_state.players[0].1.hand.0, hand);
+        assert_eq!(game_state.turn, 0);
+        assert!(game_state.apply_move(PlayerMove::new(0, (5, 6), MoveSource::Hand)));
+
+        // with nothing able to take, any play goes
         let mut game_state = GameState::new(player_names, options);
+        place_on_board(&mut game_state, Card(Suit::Hearts, Value::Seven), (5, 5));
+        leave_only(
+            &mut game_state,
+            0,
+            &[
+                Card(Suit::Clubs, Value::Three),
+                Card(Suit::Clubs, Value::Two),
+            ],
+        );
+        assert!(game_state.apply_move(wasted_play()));
+    }
 
-        let card_queen = Card(Suit::Clubs, Value::Queen);
+    #[test]
+    fn test_hand_shrinks_when_deck_empty() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let mut game_state = GameState::new(player_names, create_test_options(false));
+        leave_only(
+            &mut game_state,
+            0,
+            &[Card(Suit::Clubs, Value::Ace), Card(Suit::Clubs, Value::Two)],
+        );
+
+        // nothing to draw, so Alice is left a card short
+        assert!(game_state.apply_move(PlayerMove::new(0, (5, 5), MoveSource::Hand)));
+        assert_eq!(
+            game_state.players[0].1.hand.0,
+            vec![Card(Suit::Clubs, Value::Two)]
+        );
+        assert!(game_state.players[0].1.deck.0.is_empty());
+    }
 
-        // Place Queens in multiple directions from center
-        game_state.board.0[5][4] = Some(card_queen); // West
-        game_state.board.0[5][7] = Some(card_queen); // East  
-        game_state.board.0[3][5] = Some(card_queen); // North
-        game_state.board.0[7][5] = Some(card_queen); // South
+    #[test]
+    fn test_deal_to_empty_draws_at_turn_start() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let options = GameOptions {
+            deal_to_empty: true,
+            ..create_test_options(false)
+        };
+        let mut game_state = GameState::new(player_names, options);
 
-        // Set up player's hand
-        game_state.players[0].1.hand.0[0] = card_queen;
+        // only the first player has drawn, ready for the first placement
+        assert_eq!(game_state.players[0].1.hand.0.len(), HAND_SIZE);
+        assert!(game_state.players[1].1.hand.0.is_empty());
+        assert_eq!(game_state.state_for(1).players[1].1, 26);
+
+        assert!(game_state.apply_move(PlayerMove::new(
+            0,
+            (BOARD_SIZE / 2, BOARD_SIZE / 2),
+            MoveSource::Hand
+        )));
+
+        // Alice waits for her next turn to draw, and Bob draws as his starts
+        assert_eq!(game_state.players[0].1.hand.0.len(), HAND_SIZE - 1);
+        assert_eq!(game_state.players[1].1.hand.0.len(), HAND_SIZE);
+    }
+
+    #[test]
+    fn test_draw_per_turn() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let options = GameOptions {
+            draw_per_turn: Some(2),
+            max_hand_size: 7,
+            ..create_test_options(false)
+        };
+        let mut game_state = GameState::new(player_names, options);
+
+        // hands are dealt as usual, then grow by one a turn - two drawn for one played
+        assert_eq!(game_state.players[0].1.hand.0.len(), HAND_SIZE);
+        assert!(game_state.apply_move(PlayerMove::new(
+            0,
+            (BOARD_SIZE / 2, BOARD_SIZE / 2),
+            MoveSource::Hand
+        )));
+        assert_eq!(game_state.players[0].1.hand.0.len(), HAND_SIZE + 1);
+        assert_eq!(game_state.players[0].1.deck.0.len(), 26 - HAND_SIZE - 2);
+
+        // never past the largest hand allowed
+        let (_, alice) = &mut game_state.players[0];
+        alice.hand.0.push(alice.deck.0.remove(0));
+        game_state.fill_hand(0);
+        assert_eq!(game_state.players[0].1.hand.0.len(), 7);
+
+        // and only what's left in the deck
+        let (_, alice) = &mut game_state.players[0];
+        alice.hand.0.truncate(3);
+        alice.deck.0.truncate(1);
+        game_state.fill_hand(0);
+        assert_eq!(game_state.players[0].1.hand.0.len(), 4);
+        assert!(game_state.players[0].1.deck.0.is_empty());
+
+        // drawing none leaves the hand to run down
+        game_state.game_options.draw_per_turn = Some(0);
+        let (_, bob) = &mut game_state.players[1];
+        bob.hand.0.truncate(2);
+        game_state.fill_hand(1);
+        assert_eq!(game_state.players[1].1.hand.0.len(), 2);
+    }
+
+    #[test]
+    fn test_max_capture() {
+        let player_names = vec!["Alice".to_string()];
+        let options = GameOptions {
+            max_capture: Some(2),
+            ..create_test_options(false)
+        };
+        let mut game_state = GameState::new(player_names, options);
+        let ace = Card(Suit::Clubs, Value::Ace);
+        place_on_board(&mut game_state, Card(Suit::Diamonds, Value::Ace), (5, 1));
+        for (col, value) in [(2, Value::Two), (3, Value::Three), (4, Value::Four)] {
+            place_on_board(&mut game_state, Card(Suit::Hearts, value), (5, col));
+        }
+        put_in_hand(&mut game_state, ace);
+
+        // four cards in the line, but only the two nearest the played card are taken
+        assert!(game_state.apply_move(PlayerMove::new(0, (5, 5), MoveSource::Hand)));
+        assert_eq!(game_state.last_captured, 2);
+        assert_eq!(
+            game_state.moves().last().unwrap().taken,
+            vec![(5, 3), (5, 4), (5, 5)]
+        );
+        assert!(game_state.board.0[5][1].is_some());
+        assert!(game_state.board.0[5][2].is_some());
+        assert!(game_state.board.0[5][3].is_none());
+        assert!(game_state.board.0[5][5].is_none());
+    }
 
-        // Place Queen at center - should take all 4 directions
-        let center_move = PlayerMove {
+    #[test]
+    fn test_max_capture_under_cap() {
+        let player_names = vec!["Alice".to_string()];
+        let options = GameOptions {
+            max_capture: Some(10),
+            ..create_test_options(false)
+        };
+        let mut game_state = GameState::new(player_names, options);
+        let ace = Card(Suit::Clubs, Value::Ace);
+        place_on_board(&mut game_state, Card(Suit::Diamonds, Value::Ace), (5, 1));
+        for (col, value) in [(2, Value::Two), (3, Value::Three), (4, Value::Four)] {
+            place_on_board(&mut game_state, Card(Suit::Hearts, value), (5, col));
+        }
+        put_in_hand(&mut game_state, ace);
+
+        // the whole line fits under the cap
+        assert!(game_state.apply_move(PlayerMove::new(0, (5, 5), MoveSource::Hand)));
+        assert_eq!(game_state.last_captured, 4);
+        assert!((1..=5).all(|col| game_state.board.0[5][col].is_none()));
+    }
+
+    #[test]
+    fn test_optional_capture() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let options = GameOptions {
+            optional_capture: true,
+            ..create_test_options(false)
+        };
+        let setup = || {
+            let mut game_state = GameState::new(player_names.clone(), options.clone());
+            // a seven to the left of (5, 5), and another above it
+            place_on_board(&mut game_state, Card(Suit::Hearts, Value::Seven), (5, 4));
+            place_on_board(&mut game_state, Card(Suit::Diamonds, Value::Seven), (4, 5));
+            put_in_hand(&mut game_state, Card(Suit::Clubs, Value::Seven));
+            game_state
+        };
+        let play = |declined: Vec<(i32, i32)>| PlayerMove {
             card: 0,
             location: (5, 5),
+            from: MoveSource::Hand,
+            declined,
         };
 
-        let initial_deck_size = game_state.players[0].1.deck.0.len();
-        let initial_hand_size = game_state.players[0].1.hand.0.len();
-        assert!(game_state.apply_move(center_move));
+        // declining every direction that takes something leaves the board alone
+        let mut game_state = setup();
+        assert!(game_state.apply_move(play(vec![(0, -1), (-1, 0)])));
+        assert_eq!(game_state.last_captured, 0);
+        assert!(game_state.board.0[5][4].is_some());
+        assert!(game_state.board.0[4][5].is_some());
+        assert!(game_state.board.0[5][5].is_some());
+
+        // declining one direction still takes in the other
+        let mut game_state = setup();
+        assert!(game_state.apply_move(play(vec![(-1, 0)])));
+        assert_eq!(game_state.last_captured, 1);
+        assert!(game_state.board.0[5][4].is_none());
+        assert!(game_state.board.0[4][5].is_some());
+        assert!(game_state.board.0[5][5].is_none());
 
-        // All Queens should be taken
-        assert!(game_state.board.0[5][3].is_none()); // West taken
-        assert!(game_state.board.0[5][7].is_none()); // East taken
-        assert!(game_state.board.0[3][5].is_none()); // North taken
-        assert!(game_state.board.0[7][5].is_none()); // South taken
-        assert!(game_state.board.0[5][5].is_none()); // Center (played) taken
+        // declining nothing takes as usual
+        let mut game_state = setup();
+        assert!(game_state.apply_move(play(Vec::new())));
+        assert_eq!(game_state.last_captured, 2);
+
+        // made-up directions are turned away
+        let mut game_state = setup();
+        assert!(matches!(
+            game_state.apply_move_by("Alice", play(vec![(2, 0)])),
+            Err(MoveError::NoSuchDirection((2, 0)))
+        ));
+
+        // and nothing can be declined when the option is off
+        let mut game_state = GameState::new(player_names.clone(), create_test_options(false));
+        put_in_hand(&mut game_state, Card(Suit::Clubs, Value::Seven));
+        assert!(matches!(
+            game_state.apply_move_by("Alice", play(vec![(0, -1)])),
+            Err(MoveError::CaptureNotOptional)
+        ));
+    }
 
-        // 5 cards added to deck (4 taken + 1 played), then 1 card drawn to refill hand
-        // Net change: +4 cards to deck
-        assert_eq!(game_state.players[0].1.deck.0.len(), initial_deck_size + 4);
-        // Hand size should remain the same (played 1, drew 1)
-        assert_eq!(game_state.players[0].1.hand.0.len(), initial_hand_size);
+    #[test]
+    fn test_announce_draws() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let options = GameOptions {
+            announce_draws: true,
+            seed: Some(0),
+            ..create_test_options(false)
+        };
+        let mut game_state = GameState::new(player_names, options);
+
+        // dealt cards weren't drawn
+        assert!(game_state.state_for(0).drawn.is_empty());
+
+        // the card drawn after playing goes on the end of the hand
+        assert!(game_state.apply_move(PlayerMove::new(
+            0,
+            (BOARD_SIZE / 2, BOARD_SIZE / 2),
+            MoveSource::Hand
+        )));
+        assert_eq!(game_state.state_for(0).drawn, vec![HAND_SIZE - 1]);
+        assert!(game_state.state_for(1).drawn.is_empty());
+
+        // and stays marked until Alice plays again
+        let location = game_state.board.playable_positions(PlayContext::default())[0];
+        assert!(game_state.apply_move(PlayerMove::new(0, location, MoveSource::Hand)));
+        assert_eq!(game_state.state_for(0).drawn, vec![HAND_SIZE - 1]);
+        assert_eq!(game_state.state_for(1).drawn, vec![HAND_SIZE - 1]);
+
+        // games that don't announce draws leave them out
+        game_state.game_options.announce_draws = false;
+        assert!(game_state.state_for(0).drawn.is_empty());
+    }
+
+    #[test]
+    fn test_simultaneous_conflict() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let options = GameOptions {
+            simultaneous: true,
+            seed: Some(0),
+            ..create_test_options(false)
+        };
+        let mut game_state = GameState::new(player_names, options);
+        let center = (BOARD_SIZE / 2, BOARD_SIZE / 2);
+        let move_to = |location| PlayerMove::new(0, location, MoveSource::Hand);
+        let alice_card = game_state.players[0].1.hand.0[0];
+
+        // nothing is played until everyone has picked, and everyone can see who has
+        assert_eq!(
+            game_state.submit_move_by("Alice", move_to(center)),
+            Ok(MoveOutcome::Continues)
+        );
+        assert!(game_state.board.is_empty());
+        assert_eq!(game_state.state_for(1).submitted, Some(vec![true, false]));
+        assert!(game_state.state_for(1).is_my_turn() && !game_state.state_for(0).is_my_turn());
+        assert_eq!(
+            game_state.submit_move_by("Alice", move_to(center)),
+            Err(MoveError::AlreadySubmitted)
+        );
+
+        // both went for the centre, and Alice's seat comes first, so Bob's move is skipped
+        let bob_hand = game_state.players[1].1.hand.clone();
+        assert_eq!(
+            game_state.submit_move_by("Bob", move_to(center)),
+            Ok(MoveOutcome::Continues)
+        );
+        assert_eq!(game_state.board.0[center.0][center.1], Some(alice_card));
+        assert_eq!(game_state.players[1].1.hand, bob_hand);
+        assert_eq!(
+            game_state.take_skipped(),
+            vec![("Bob".to_string(), MoveError::IllegalPosition(center))]
+        );
+        assert!(game_state.take_skipped().is_empty());
+        assert_eq!(game_state.state_for(0).submitted, Some(vec![false, false]));
+        assert_eq!(game_state.turn(), 0);
+
+        // moves that can't be played even now are turned down straight away
+        assert_eq!(
+            game_state.submit_move_by("Bob", move_to((0, 0))),
+            Err(MoveError::IllegalPosition((0, 0)))
+        );
+        assert_eq!(game_state.state_for(0).submitted, Some(vec![false, false]));
+
+        // moves that don't get in each other's way are both played
+        let moves_before = game_state.moves().len();
+        assert!(
+            game_state
+                .submit_move_by("Bob", move_to((center.0, center.1 + 1)))
+                .is_ok()
+        );
+        assert!(
+            game_state
+                .submit_move_by("Alice", move_to((center.0, center.1 - 1)))
+                .is_ok()
+        );
+        assert!(game_state.take_skipped().is_empty());
+        let players = game_state.moves()[moves_before..]
+            .iter()
+            .map(|last_move| last_move.player.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(players, vec!["Alice", "Bob"]);
+    }
+
+    #[test]
+    fn test_simultaneous_forfeit_plays_round() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string(), "Carol".to_string()];
+        let options = GameOptions {
+            simultaneous: true,
+            ..create_test_options(false)
+        };
+        let mut game_state = GameState::new(player_names, options);
+        assert!(
+            game_state
+                .submit_move_by(
+                    "Alice",
+                    PlayerMove::new(0, (BOARD_SIZE / 2, BOARD_SIZE / 2), MoveSource::Hand)
+                )
+                .is_ok()
+        );
+        assert!(!game_state.play_round_if_ready());
+
+        // once Bob's gone, only Carol is left to pick, and Bob can't pick any more
+        game_state.forfeit(1);
+        assert!(!game_state.play_round_if_ready());
+        assert_eq!(
+            game_state.submit_move_by("Bob", PlayerMove::new(0, (0, 0), MoveSource::Hand)),
+            Err(MoveError::NotYourTurn)
+        );
+        game_state.forfeit(2);
+        assert!(game_state.play_round_if_ready());
+        assert_eq!(game_state.board.played_card_count(), 1);
+    }
+
+    #[test]
+    fn test_max_hand_size_validation() {
+        let options = GameOptions {
+            draw_per_turn: Some(2),
+            max_hand_size: HAND_SIZE - 1,
+            ..create_test_options(false)
+        };
+        assert!(matches!(
+            options.validate(2),
+            Err(OptionsError::MaxHandSizeTooSmall)
+        ));
+
+        let options = GameOptions {
+            draw_per_turn: Some(2),
+            max_hand_size: 53,
+            ..create_test_options(false)
+        };
+        assert!(matches!(
+            options.validate(2),
+            Err(OptionsError::MaxHandSizeTooLarge)
+        ));
+
+        let options = GameOptions {
+            draw_per_turn: Some(usize::MAX),
+            max_hand_size: 52,
+            ..create_test_options(false)
+        };
+        assert!(matches!(
+            options.validate(2),
+            Err(OptionsError::DrawPerTurnTooLarge)
+        ));
+        assert!(
+            GameOptions {
+                draw_per_turn: Some(52),
+                ..options
+            }
+            .validate(2)
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_hide_own_deck() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let options = GameOptions {
+            hide_own_deck: true,
+            peek_top: true,
+            ..create_test_options(false)
+        };
+        let mut game_state = GameState::new(player_names, options);
+
+        // only the size of the deck is sent, though peeking still shows the next draw
+        let deck = game_state.players[0].1.deck.0.clone();
+        let visible = game_state.state_for(0);
+        assert!(visible.deck.0.is_empty());
+        assert_eq!(visible.hidden_deck, Some(deck.len()));
+        assert_eq!(visible.top_card, deck.first().copied());
+        assert_eq!(game_state.state_for_spectator("Eve").hidden_deck, None);
+
+        // and it goes down as cards are drawn
+        assert!(game_state.apply_move(PlayerMove::new(0, (5, 5), MoveSource::Hand)));
+        assert_eq!(game_state.state_for(0).hidden_deck, Some(deck.len() - 1));
+    }
+
+    #[test]
+    fn test_shared_deck() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let options = GameOptions {
+            shared_deck: true,
+            ..create_test_options(false)
+        };
+        let mut game_state = GameState::new(player_names, options.clone());
+
+        // only hands are dealt, and the rest is pooled
+        for (_, player_state) in &game_state.players {
+            assert_eq!(player_state.hand.0.len(), HAND_SIZE);
+            assert!(player_state.deck.0.is_empty());
+        }
+        assert_eq!(game_state.shared_deck.len(), 52 - 2 * HAND_SIZE);
+        assert_eq!(
+            game_state.state_for(1).shared_deck,
+            Some(52 - 2 * HAND_SIZE)
+        );
+        game_state.assert_card_conservation();
+        assert!(matches!(
+            GameOptions {
+                deal_to_empty: true,
+                ..options
+            }
+            .validate(2),
+            Err(OptionsError::SharedDeckDealToEmpty)
+        ));
+
+        // refills come from the shared deck
+        let next_draw = game_state.shared_deck[0];
+        assert!(game_state.apply_move(PlayerMove::new(0, (5, 5), MoveSource::Hand)));
+        assert!(game_state.players[0].1.hand.0.contains(&next_draw));
+        assert_eq!(game_state.shared_deck.len(), 52 - 2 * HAND_SIZE - 1);
+
+        // taken cards go to the taker's own deck, which they draw from once the pool runs out
+        let played = game_state.board.0[5][5].unwrap();
+        let other_suit = if played.0 == Suit::Clubs {
+            Suit::Spades
+        } else {
+            Suit::Clubs
+        };
+        let taker = Card(other_suit, played.1);
+        let old_card = game_state.players[1].1.hand.0[0];
+        for slot in game_state
+            .players
+            .iter_mut()
+            .flat_map(|(_, state)| state.hand.0.iter_mut())
+            .chain(&mut game_state.shared_deck)
+            .chain(&mut game_state.out_of_play)
+        {
+            if *slot == taker {
+                *slot = old_card;
+            }
+        }
+        game_state.players[1].1.hand.0[0] = taker;
+        game_state.out_of_play.append(&mut game_state.shared_deck);
+
+        assert!(game_state.apply_move(PlayerMove::new(0, (5, 6), MoveSource::Hand)));
+        let bob = &game_state.players[1].1;
+        assert_eq!(bob.hand.0.len(), HAND_SIZE);
+        assert_eq!(bob.deck.0.len(), 1);
+        game_state.assert_card_conservation();
+    }
+
+    #[test]
+    fn test_last_move_records_captures() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let mut game_state = GameState::new(player_names, create_test_options(false));
+        assert_eq!(game_state.state_for(0).last_move, None);
+
+        place_on_board(&mut game_state, Card(Suit::Hearts, Value::Seven), (5, 5));
+        put_in_hand(&mut game_state, Card(Suit::Clubs, Value::Seven));
+        assert!(game_state.apply_move(PlayerMove::new(0, (5, 6), MoveSource::Hand)));
+
+        // everyone sees the move, including what it took
+        let last_move = LastMove {
+            player: "Alice".to_string(),
+            card: Card(Suit::Clubs, Value::Seven),
+            location: (5, 6),
+            taken: vec![(5, 5), (5, 6)],
+            taken_to: TakenTo::MoverDeck,
+        };
+        assert_eq!(game_state.state_for(1).last_move, Some(last_move.clone()));
+        assert_eq!(
+            game_state.state_for_spectator("Eve").last_move,
+            Some(last_move)
+        );
+    }
+
+    #[test]
+    fn test_capture_attribution() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let mut game_state = GameState::new(player_names, create_test_options(false));
+        place_on_board(&mut game_state, Card(Suit::Hearts, Value::Seven), (5, 5));
+        put_in_hand(&mut game_state, Card(Suit::Clubs, Value::Seven));
+        assert!(game_state.apply_move(PlayerMove::new(0, (5, 6), MoveSource::Hand)));
+
+        // both sevens went into Alice's deck, and the history says so
+        let last_move = game_state.history.last().unwrap();
+        assert_eq!(last_move.taken_to, TakenTo::MoverDeck);
+        for position in [(5, 5), (5, 6)] {
+            assert_eq!(
+                last_move.destination(position),
+                Some(CardDestination::Deck("Alice".to_string()))
+            );
+        }
+        assert_eq!(last_move.destination((0, 0)), None);
+        let deck = &game_state.players[0].1.deck.0;
+        assert!(deck.contains(&Card(Suit::Hearts, Value::Seven)));
+        assert!(deck.contains(&Card(Suit::Clubs, Value::Seven)));
+
+        // which is the usual case, so it isn't spelled out to clients
+        let json = serde_json::to_value(last_move).unwrap();
+        assert!(json.get("taken_to").is_none());
+    }
+
+    #[test]
+    fn test_empty_deck_refilled_by_capture() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let mut game_state = GameState::new(player_names, create_test_options(false));
+        place_on_board(&mut game_state, Card(Suit::Hearts, Value::Seven), (5, 5));
+        leave_only(
+            &mut game_state,
+            0,
+            &[
+                Card(Suit::Clubs, Value::Seven),
+                Card(Suit::Clubs, Value::Two),
+            ],
+        );
+
+        // taken cards go into the deck and are drawn straight away
+        assert!(game_state.apply_move(PlayerMove::new(0, (5, 6), MoveSource::Hand)));
+        let player_state = &game_state.players[0].1;
+        assert_eq!(player_state.hand.0.len(), 3);
+        assert!(
+            player_state
+                .hand
+                .0
+                .contains(&Card(Suit::Clubs, Value::Seven))
+        );
+        assert!(
+            player_state
+                .hand
+                .0
+                .contains(&Card(Suit::Hearts, Value::Seven))
+        );
+        assert!(player_state.deck.0.is_empty());
+    }
+
+    #[test]
+    fn test_winner_is_not_last_mover() {
+        let player_names = vec![
+            "Alice".to_string(),
+            "Bob".to_string(),
+            "Charlie".to_string(),
+        ];
+        let mut game_state = GameState::new(player_names, create_test_options(false));
+        assert_eq!(game_state.winner(), None);
+
+        // Charlie is out, then Alice plays her last card without taking anything
+        game_state.forfeit(2);
+        assert_eq!(game_state.winner(), None);
+        leave_only(&mut game_state, 0, &[Card(Suit::Clubs, Value::Ace)]);
+        assert!(game_state.apply_move(PlayerMove::new(0, (5, 5), MoveSource::Hand)));
+
+        assert!(game_state.someone_has_won());
+        assert_eq!(game_state.winner(), Some("Bob"));
+    }
+
+    #[test]
+    fn test_reserve_card_dealt_face_up() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let options = GameOptions {
+            reserve_card: true,
+            ..create_test_options(false)
+        };
+        let game_state = GameState::new(player_names, options);
+
+        // everyone sees every reserve, and it counts towards its owner's cards
+        let reserves = game_state
+            .players
+            .iter()
+            .map(|(_, state)| state.reserve)
+            .collect::<Vec<_>>();
+        assert!(reserves.iter().all(Option::is_some));
+        assert_eq!(game_state.state_for(0).reserves, reserves);
+        assert_eq!(game_state.state_for(1).reserves, reserves);
+        assert_eq!(game_state.state_for_spectator("Eve").reserves, reserves);
+        assert_eq!(game_state.state_for(0).players[0].1, 26);
+        assert_eq!(game_state.players[0].1.hand.0.len(), HAND_SIZE);
+
+        // no reserves without the option
+        let game_state = GameState::new(
+            vec!["Alice".to_string(), "Bob".to_string()],
+            create_test_options(false),
+        );
+        assert!(game_state.state_for(0).reserves.is_empty());
+    }
+
+    #[test]
+    fn test_play_from_reserve() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let options = GameOptions {
+            reserve_card: true,
+            ..create_test_options(false)
+        };
+        let mut game_state = GameState::new(player_names, options);
+        let reserve = game_state.players[0].1.reserve.unwrap();
+        let hand = game_state.players[0].1.hand.clone();
+
+        let reserve_move = || PlayerMove::new(0, (5, 5), MoveSource::Reserve);
+        assert!(game_state.apply_move(reserve_move()));
+        assert_eq!(game_state.board.0[5][5], Some(reserve));
+        assert_eq!(game_state.players[0].1.reserve, None);
+        assert_eq!(game_state.players[0].1.hand, hand);
+
+        // the reserve can only be played once
+        game_state.turn = 0;
+        assert!(!game_state.apply_move(PlayerMove {
+            location: (4, 5),
+            ..reserve_move()
+        }));
+    }
+
+    #[test]
+    fn test_pause() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let mut game_state = GameState::new(player_names.clone(), create_test_options(false));
+        let centre = PlayerMove::new(0, (BOARD_SIZE / 2, BOARD_SIZE / 2), MoveSource::Hand);
+
+        assert_eq!(game_state.resume(), None);
+        assert!(game_state.pause());
+        assert!(!game_state.pause());
+        let before = game_state.clone();
+        assert_eq!(
+            game_state.apply_move_by("Alice", centre.clone()),
+            Err(MoveError::Paused)
+        );
+        assert!(game_state == before);
+
+        // it's still Alice's turn once the game picks up again
+        assert!(game_state.resume().is_some());
+        assert!(!game_state.is_paused());
+        assert_eq!(
+            game_state.apply_move_by("Alice", centre.clone()),
+            Ok(MoveOutcome::Continues)
+        );
+        assert_eq!(game_state.turn(), 1);
+
+        // moves picked ahead of a round wait out a pause too
+        let options = GameOptions {
+            simultaneous: true,
+            ..create_test_options(false)
+        };
+        let mut game_state = GameState::new(player_names, options);
+        assert!(game_state.pause());
+        assert_eq!(
+            game_state.submit_move_by("Bob", centre.clone()),
+            Err(MoveError::Paused)
+        );
+        game_state.resume();
+        assert!(game_state.submit_move_by("Bob", centre).is_ok());
+    }
+
+    #[test]
+    fn test_apply_move_by() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let mut game_state = GameState::new(player_names, create_test_options(false));
+        let centre = || PlayerMove::new(0, (BOARD_SIZE / 2, BOARD_SIZE / 2), MoveSource::Hand);
+
+        // Bob can't move on Alice's turn, and nothing changes when he tries
+        assert!(matches!(
+            game_state.apply_move_by("Bob", centre()),
+            Err(MoveError::NotYourTurn)
+        ));
+        assert!(matches!(
+            game_state.apply_move_by("Eve", centre()),
+            Err(MoveError::NotYourTurn)
+        ));
+        assert_eq!(game_state.turn(), 0);
+        assert!(game_state.board().is_empty());
+
+        assert_eq!(
+            game_state.apply_move_by("Alice", centre()).unwrap(),
+            MoveOutcome::Continues
+        );
+        assert_eq!(game_state.turn(), 1);
+
+        // Alice has had her turn, and Bob's move must still be legal
+        assert!(matches!(
+            game_state.apply_move_by("Alice", centre()),
+            Err(MoveError::NotYourTurn)
+        ));
+        assert!(matches!(
+            game_state.apply_move_by("Bob", centre()),
+            Err(MoveError::IllegalPosition(_))
+        ));
+        assert!(matches!(
+            game_state.apply_move_by(
+                "Bob",
+                PlayerMove {
+                    card: HAND_SIZE,
+                    ..centre()
+                }
+            ),
+            Err(MoveError::NoSuchCard(HAND_SIZE))
+        ));
+        assert!(matches!(
+            game_state.apply_move_by(
+                "Bob",
+                PlayerMove {
+                    from: MoveSource::Reserve,
+                    ..centre()
+                }
+            ),
+            Err(MoveError::NoReserve)
+        ));
+        assert_eq!(
+            MoveError::IllegalPosition((1, 2)).to_string(),
+            "can't play at position (1, 2)"
+        );
+
+        // Bob playing his last card, without taking anything, hands Alice the win
+        let centre_card = game_state.board().0[BOARD_SIZE / 2][BOARD_SIZE / 2].unwrap();
+        let last_card = if centre_card.1 == Value::Ace {
+            Card(Suit::Clubs, Value::Two)
+        } else {
+            Card(Suit::Clubs, Value::Ace)
+        };
+        leave_only(&mut game_state, 1, &[last_card]);
+        assert_eq!(
+            game_state
+                .apply_move_by(
+                    "Bob",
+                    PlayerMove {
+                        location: (BOARD_SIZE / 2, BOARD_SIZE / 2 + 1),
+                        ..centre()
+                    }
+                )
+                .unwrap(),
+            MoveOutcome::Won("Alice".to_string())
+        );
+    }
+
+    #[test]
+    fn test_winner_when_mover_plays_last_card() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let mut game_state = GameState::new(player_names, create_test_options(false));
+
+        // Alice empties her hand, so Bob is the only one left with cards
+        leave_only(&mut game_state, 0, &[Card(Suit::Clubs, Value::Ace)]);
+        assert!(game_state.apply_move(PlayerMove::new(0, (5, 5), MoveSource::Hand)));
+
+        assert_eq!(game_state.winner(), Some("Bob"));
+        // and every player sees the same result
+        for index in 0..2 {
+            assert_eq!(game_state.state_for(index).winner(), Some("Bob"));
+        }
+    }
+
+    #[test]
+    fn test_last_card_capture_keeps_mover_in() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let mut game_state = GameState::new(player_names, create_test_options(false));
+
+        // Alice's last card takes a card on the board, so she still has cards
+        place_on_board(&mut game_state, Card(Suit::Hearts, Value::Ace), (5, 5));
+        leave_only(&mut game_state, 0, &[Card(Suit::Clubs, Value::Ace)]);
+        assert!(game_state.apply_move(PlayerMove::new(0, (5, 6), MoveSource::Hand)));
+
+        assert!(game_state.get_players()[0].1.has_cards());
+        assert_eq!(game_state.winner(), None);
+        assert!(!game_state.someone_has_won());
+    }
+
+    #[test]
+    fn test_winner_after_elimination() {
+        let player_names = vec![
+            "Alice".to_string(),
+            "Bob".to_string(),
+            "Charlie".to_string(),
+        ];
+        let mut game_state = GameState::new(player_names, create_test_options(false));
+
+        // Alice and Bob each play their last card, leaving Charlie
+        leave_only(&mut game_state, 0, &[Card(Suit::Clubs, Value::Ace)]);
+        leave_only(&mut game_state, 1, &[Card(Suit::Clubs, Value::Two)]);
+        assert!(game_state.apply_move(PlayerMove::new(0, (5, 5), MoveSource::Hand)));
+        assert!(!game_state.someone_has_won());
+        assert_eq!(game_state.winner(), None);
+        assert!(game_state.apply_move(PlayerMove::new(0, (4, 5), MoveSource::Hand)));
+
+        assert_eq!(game_state.winner(), Some("Charlie"));
+    }
+
+    /// Seeded game with capture options set, and the first three cards of Bob's deck taken out
+    fn capture_setup(
+        capture_order: CaptureOrder,
+        capture_placement: CapturePlacement,
+    ) -> (GameState, Vec<Card>) {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let options = GameOptions {
+            seed: Some(42),
+            capture_order,
+            capture_placement,
+            ..create_test_options(false)
+        };
+        let mut game_state = GameState::new(player_names, options);
+        let cards = game_state.players[1].1.deck.0.drain(0..3).collect();
+        (game_state, cards)
+    }
+
+    #[test]
+    fn test_absorb_cards_shuffled_bottom() {
+        let (mut game_state, cards) =
+            capture_setup(CaptureOrder::Shuffled, CapturePlacement::Bottom);
+        assert_eq!(
+            cards,
+            vec![
+                Card(Suit::Clubs, Value::King),
+                Card(Suit::Clubs, Value::Seven),
+                Card(Suit::Spades, Value::Queen),
+            ]
+        );
+        let before = game_state.players[0].1.deck.0.clone();
+
+        game_state.absorb_cards(0, cards);
+
+        // same seed, same shuffle
+        let deck = &game_state.players[0].1.deck.0;
+        assert_eq!(deck[..before.len()], before);
+        assert_eq!(
+            deck[before.len()..],
+            [
+                Card(Suit::Clubs, Value::Seven),
+                Card(Suit::Spades, Value::Queen),
+                Card(Suit::Clubs, Value::King),
+            ]
+        );
+        game_state.assert_card_conservation();
+    }
+
+    #[test]
+    fn test_absorb_cards_ordered_bottom() {
+        let (mut game_state, cards) =
+            capture_setup(CaptureOrder::Ordered, CapturePlacement::Bottom);
+        let before = game_state.players[0].1.deck.0.clone();
+
+        game_state.absorb_cards(0, cards.clone());
+
+        let deck = &game_state.players[0].1.deck.0;
+        assert_eq!(deck[..before.len()], before);
+        assert_eq!(deck[before.len()..], cards);
+        game_state.assert_card_conservation();
+    }
+
+    #[test]
+    fn test_absorb_cards_ordered_top() {
+        let (mut game_state, cards) = capture_setup(CaptureOrder::Ordered, CapturePlacement::Top);
+        let before = game_state.players[0].1.deck.0.clone();
+
+        game_state.absorb_cards(0, cards.clone());
+
+        let deck = &game_state.players[0].1.deck.0;
+        assert_eq!(deck[..cards.len()], cards);
+        assert_eq!(deck[cards.len()..], before);
+        game_state.assert_card_conservation();
+    }
+
+    #[test]
+    fn test_capture_order_ordered_in_move() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let options = GameOptions {
+            capture_order: CaptureOrder::Ordered,
+            ..create_test_options(false)
+        };
+        let mut game_state = GameState::new(player_names, options);
+
+        // Alice plays a 7 to the right of a 2 that's right of a 7, taking all three
+        place_on_board(&mut game_state, Card(Suit::Clubs, Value::Seven), (2, 1));
+        place_on_board(&mut game_state, Card(Suit::Hearts, Value::Two), (2, 2));
+        put_in_hand(&mut game_state, Card(Suit::Spades, Value::Seven));
+        let before = game_state.players[0].1.deck.0.len();
+
+        assert!(game_state.apply_move(PlayerMove::new(0, (2, 3), MoveSource::Hand)));
+
+        // taken cards go to the bottom in board order, after the hand is refilled
+        let deck = &game_state.players[0].1.deck.0;
+        assert_eq!(
+            deck[deck.len() - 3..],
+            [
+                Card(Suit::Clubs, Value::Seven),
+                Card(Suit::Hearts, Value::Two),
+                Card(Suit::Spades, Value::Seven),
+            ]
+        );
+        assert_eq!(deck.len(), before + 3 - 1);
+    }
+
+    #[test]
+    fn test_wrap_capture() {
+        let player_names = vec!["Alice".to_string()];
+        let options = GameOptions {
+            wrap: true,
+            ..create_test_options(false)
+        };
+        let mut game_state = GameState::new(player_names, options);
+
+        // An ace on the right edge, with a two between it and the left edge
+        place_on_board(&mut game_state, Card(Suit::Hearts, Value::Ace), (5, 9));
+        place_on_board(&mut game_state, Card(Suit::Diamonds, Value::Two), (5, 10));
+        put_in_hand(&mut game_state, Card(Suit::Spades, Value::Ace));
+
+        // Playing an ace on the left edge takes across the edge
+        assert!(game_state.apply_move(PlayerMove::new(0, (5, 0), MoveSource::Hand)));
+        assert!(game_state.board.0[5][0].is_none());
+        assert!(game_state.board.0[5][9].is_none());
+        assert!(game_state.board.0[5][10].is_none());
+    }
+
+    #[test]
+    fn test_wrap_capture_lines_meet() {
+        let player_names = vec!["Alice".to_string()];
+        let options = GameOptions {
+            wrap: true,
+            ..create_test_options(false)
+        };
+        let mut game_state = GameState::new(player_names, options);
+
+        // Scanning right and scanning left across the edge both end at the same ace
+        place_on_board(&mut game_state, Card(Suit::Hearts, Value::Ace), (5, 5));
+        place_on_board(&mut game_state, Card(Suit::Diamonds, Value::Two), (4, 0));
+        put_in_hand(&mut game_state, Card(Suit::Spades, Value::Ace));
+        let captures = find_captures(
+            &game_state.board,
+            Card(Suit::Spades, Value::Ace),
+            (5, 0),
+            TakingVariant::SameNumber,
+            RankOrder::AceLow,
+            true,
+        );
+        assert_eq!(
+            captures,
+            (0..BOARD_SIZE).map(|col| (5, col)).collect::<Vec<_>>()
+        );
+
+        // so the ace is only taken once, and the empty cells around it take nothing
+        assert!(game_state.apply_move(PlayerMove::new(0, (5, 0), MoveSource::Hand)));
+        assert_eq!(game_state.history.last().unwrap().taken, captures);
+        assert_eq!(game_state.last_captured(), 1);
+        assert!(game_state.board.0[5].iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_wrap_capture_full_row_terminates() {
+        // Fill the row with lesser clubs, save for one gap
+        let board = Board::from_positions(
+            &(0..BOARD_SIZE)
+                .filter(|&col| col != 3)
+                .zip(&Value::ALL[..10])
+                .map(|(col, &value)| ((5, col), Card(Suit::Clubs, value)))
+                .collect::<Vec<_>>(),
+        );
+
+        // Every direction along the row matches all the way around, but scanning still stops
+        assert_eq!(
+            find_captures(
+                &board,
+                Card(Suit::Clubs, Value::King),
+                (5, 3),
+                TakingVariant::SameNumberOrSuitRanked,
+                RankOrder::AceLow,
+                true
+            ),
+            (0..BOARD_SIZE).map(|col| (5, col)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_wrap_capture_full_diagonal_terminates() {
+        // Fill the main diagonal with lesser clubs, save for one gap
+        let board = Board::from_positions(
+            &(0..BOARD_SIZE)
+                .filter(|&i| i != 3)
+                .zip(&Value::ALL[..10])
+                .map(|(i, &value)| ((i, i), Card(Suit::Clubs, value)))
+                .collect::<Vec<_>>(),
+        );
+
+        // Diagonal scans wrap around both edges at once, and still stop short of the played card
+        assert_eq!(
+            find_captures(
+                &board,
+                Card(Suit::Clubs, Value::King),
+                (3, 3),
+                TakingVariant::SameNumberOrSuitRanked,
+                RankOrder::AceLow,
+                true
+            ),
+            (0..BOARD_SIZE).map(|i| (i, i)).collect::<Vec<_>>()
+        );
+    }
+
+    /// Total points of each player's cards
+    fn point_totals(game_state: &GameState) -> Vec<u32> {
+        game_state
+            .players
+            .iter()
+            .map(|(_, player_state)| {
+                player_state
+                    .hand
+                    .0
+                    .iter()
+                    .chain(&player_state.deck.0)
+                    .map(|card| card.1.points())
+                    .sum()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_balanced_deal_spread() {
+        for num_players in 2..=4 {
+            for sequester_cards in [false, true] {
+                for seed in 0..50 {
+                    let player_names = (1..=num_players)
+                        .map(|n| format!("Player {n}"))
+                        .collect::<Vec<_>>();
+                    let options = GameOptions {
+                        seed: Some(seed),
+                        balanced_deal: true,
+                        ..create_test_options(sequester_cards)
+                    };
+                    let totals = point_totals(&GameState::new(player_names, options));
+
+                    // a random deal is often off by 30 or more
+                    let spread = totals.iter().max().unwrap() - totals.iter().min().unwrap();
+                    assert!(
+                        spread <= 5,
+                        "{num_players} players, seed {seed}: totals {totals:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_balanced_deal_sizes() {
+        let player_names = vec![
+            "Alice".to_string(),
+            "Bob".to_string(),
+            "Charlie".to_string(),
+        ];
+        let options = GameOptions {
+            balanced_deal: true,
+            ..create_test_options(false)
+        };
+        let game_state = GameState::new(player_names, options);
+
+        // still 18 cards for one player and 17 for the others
+        let mut sizes = game_state
+            .players
+            .iter()
+            .map(|(_, player_state)| player_state.hand.0.len() + player_state.deck.0.len())
+            .collect::<Vec<_>>();
+        sizes.sort();
+        assert_eq!(sizes, vec![17, 17, 18]);
+        assert!(
+            game_state
+                .players
+                .iter()
+                .all(|(_, player_state)| player_state.hand.0.len() == HAND_SIZE)
+        );
+    }
+
+    #[test]
+    fn test_face_card_hands() {
+        let has_face_card = |game_state: &GameState| {
+            game_state
+                .players
+                .iter()
+                .all(|(_, player_state)| player_state.hand.0.iter().any(Card::is_face))
+        };
+        let mut unlucky_deals = 0;
+        for num_players in 2..=4 {
+            for (sequester_cards, reserve_card) in [(false, false), (true, false), (false, true)] {
+                for seed in 0..50 {
+                    let player_names = (1..=num_players)
+                        .map(|n| format!("Player {n}"))
+                        .collect::<Vec<_>>();
+                    let options = GameOptions {
+                        seed: Some(seed),
+                        reserve_card,
+                        ..create_test_options(sequester_cards)
+                    };
+                    let plain = GameState::new(player_names.clone(), options.clone());
+                    let guaranteed = GameState::new(
+                        player_names,
+                        GameOptions {
+                            face_card_hands: true,
+                            ..options
+                        },
+                    );
+
+                    assert!(
+                        has_face_card(&guaranteed),
+                        "{num_players} players, seed {seed}"
+                    );
+                    // deals that already have face cards everywhere are left alone
+                    if has_face_card(&plain) {
+                        assert!(plain.players == guaranteed.players);
+                    } else {
+                        unlucky_deals += 1;
+                    }
+                }
+            }
+        }
+        // the default doesn't make sure of anything
+        assert!(unlucky_deals > 0);
+    }
+
+    #[test]
+    fn test_teaching_deal() {
+        let hands_have_pairs = |game_state: &GameState| {
+            game_state
+                .players
+                .iter()
+                .all(|(_, player_state)| has_pair(&player_state.hand.0))
+        };
+        let mut unlucky_deals = 0;
+        for num_players in 2..=4 {
+            for (sequester_cards, reserve_card) in [(false, false), (true, false), (false, true)] {
+                for seed in 0..50 {
+                    let player_names = (1..=num_players)
+                        .map(|n| format!("Player {n}"))
+                        .collect::<Vec<_>>();
+                    let options = GameOptions {
+                        seed: Some(seed),
+                        reserve_card,
+                        ..create_test_options(sequester_cards)
+                    };
+                    let plain = GameState::new(player_names.clone(), options.clone());
+                    let teaching = GameState::new(
+                        player_names,
+                        GameOptions {
+                            teaching_deal: true,
+                            ..options
+                        },
+                    );
+
+                    assert!(
+                        hands_have_pairs(&teaching),
+                        "{num_players} players, seed {seed}"
+                    );
+                    teaching.assert_card_conservation();
+                    // deals that already have pairs everywhere are left alone
+                    if hands_have_pairs(&plain) {
+                        assert!(plain.players == teaching.players);
+                    } else {
+                        unlucky_deals += 1;
+                    }
+                }
+            }
+        }
+        // the default doesn't make sure of anything
+        assert!(unlucky_deals > 0);
+    }
+
+    #[test]
+    fn test_teaching_deal_with_face_cards() {
+        // making pairs never undoes the face card each hand was given
+        for num_players in 2..=4 {
+            for seed in 0..50 {
+                let player_names = (1..=num_players)
+                    .map(|n| format!("Player {n}"))
+                    .collect::<Vec<_>>();
+                let options = GameOptions {
+                    seed: Some(seed),
+                    face_card_hands: true,
+                    teaching_deal: true,
+                    ..create_test_options(false)
+                };
+                let game_state = GameState::new(player_names, options);
+                for (_, player_state) in &game_state.players {
+                    assert!(
+                        player_state.hand.0.iter().any(Card::is_face),
+                        "{num_players} players, seed {seed}"
+                    );
+                    assert!(
+                        has_pair(&player_state.hand.0),
+                        "{num_players} players, seed {seed}"
+                    );
+                }
+                game_state.assert_card_conservation();
+            }
+        }
+    }
+
+    #[test]
+    fn test_preset() {
+        let classic = options_with_preset(Preset::Classic, &[]);
+        assert!(classic == options_with_preset(Preset::Classic, &[]));
+        assert!(!classic.require_capture && !classic.sequester_cards);
+
+        let aggressive = options_with_preset(Preset::Aggressive, &[]);
+        assert!(aggressive.require_capture);
+        assert!(aggressive.capture_placement == CapturePlacement::Top);
+
+        // flags given explicitly win over the preset
+        let overridden = options_with_preset(
+            Preset::Aggressive,
+            &[
+                "--require-capture",
+                "false",
+                "--capture-placement",
+                "bottom",
+            ],
+        );
+        assert!(!overridden.require_capture);
+        assert!(overridden.capture_placement == CapturePlacement::Bottom);
+        assert!(options_with_preset(Preset::Classic, &["--require-capture"]).require_capture);
+
+        let quick = options_with_preset(Preset::Quick, &[]);
+        assert!(quick.sequester_cards);
+        assert_eq!(quick.sequester_count, 2);
+        assert!(quick.validate(4).is_ok());
+        let quick = options_with_preset(Preset::Quick, &["--sequester-count", "8"]);
+        assert_eq!(quick.sequester_count, 8);
+        assert!(quick.validate(4).is_err());
+    }
+
+    #[test]
+    fn test_from_position() {
+        let mut row = ["."; BOARD_SIZE];
+        row[5] = "AS";
+        row[6] = "KD";
+        let board = (0..BOARD_SIZE)
+            .map(|index| {
+                if index == 5 {
+                    row.concat()
+                } else {
+                    ".".repeat(BOARD_SIZE)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        let position = format!("{board} 2 2C3C/-").parse::<Position>().unwrap();
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let options = create_test_options(false);
+        assert!(position.validate(&options, 2).is_ok());
+
+        let game_state = GameState::from_position(player_names, options.clone(), &position);
+        assert_eq!(game_state.board, position.board);
+        assert_eq!(game_state.turn, 1);
+        assert!(game_state.has_started_placing);
+        assert_eq!(
+            game_state.players[0].1.hand.0,
+            vec![
+                Card(Suit::Clubs, Value::Two),
+                Card(Suit::Clubs, Value::Three)
+            ]
+        );
+        assert!(game_state.players[1].1.hand.0.is_empty());
+        // the other 48 cards are split between the decks
+        assert_eq!(game_state.players[0].1.deck.0.len(), 24);
+        assert_eq!(game_state.players[1].1.deck.0.len(), 24);
+        game_state.assert_card_conservation();
+
+        // without hands, everyone draws theirs from what they're dealt
+        let position = format!("{board} 1").parse::<Position>().unwrap();
+        let game_state = GameState::from_position(
+            vec!["Alice".to_string(), "Bob".to_string(), "Carol".to_string()],
+            options.clone(),
+            &position,
+        );
+        for (_, player_state) in &game_state.players {
+            assert_eq!(player_state.hand.0.len(), HAND_SIZE);
+        }
+        assert_eq!(
+            game_state
+                .players
+                .iter()
+                .map(|(_, player_state)| player_state.card_count())
+                .collect::<Vec<_>>(),
+            vec![17, 17, 16]
+        );
+        game_state.assert_card_conservation();
+    }
+
+    #[test]
+    fn test_validate_position() {
+        let board = vec![".".repeat(BOARD_SIZE); BOARD_SIZE].join("/");
+        let position = format!("{board} 3 AS/-").parse::<Position>().unwrap();
+        let options = create_test_options(false);
+        assert!(matches!(
+            position.validate(&options, 2),
+            Err(PositionError::TurnOutOfRange {
+                turn: 3,
+                num_players: 2
+            })
+        ));
+        assert!(matches!(
+            position.validate(&options, 3),
+            Err(PositionError::WrongHandCount {
+                hands: 2,
+                num_players: 3
+            })
+        ));
+        assert!(matches!(
+            position.validate(&create_test_options(true), 2),
+            Err(PositionError::ConflictingOption("--sequester-cards"))
+        ));
+
+        // someone with an empty hand and nothing left to deal them can't play
+        let mut cards = Suit::ALL
+            .into_iter()
+            .flat_map(|suit| Value::ALL.map(|value| Card(suit, value)));
+        let mut board = Board([[None; BOARD_SIZE]; BOARD_SIZE]);
+        // every card is on the board, filling the rows from just above the center down
+        for cell in board.0.iter_mut().skip(BOARD_SIZE / 2 - 2).flatten() {
+            *cell = cards.next();
+        }
+        let position = Position {
+            board,
+            turn: 0,
+            hands: Some(vec![Vec::new(), cards.collect()]),
+        };
+        assert!(matches!(
+            position.validate(&options, 2),
+            Err(PositionError::PlayerWithoutCards(1))
+        ));
+    }
+
+    #[test]
+    fn test_validate_integrity() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let mut game_state = GameState::new(player_names, create_test_options(true));
+        assert_eq!(game_state.validate_integrity(), Ok(()));
+
+        // a card copied onto the board is there twice
+        let copied = game_state.players[0].1.hand.0[0];
+        game_state.board.0[5][5] = Some(copied);
+        assert_eq!(
+            game_state.validate_integrity(),
+            Err(IntegrityError {
+                missing: Vec::new(),
+                extra: vec![copied],
+            })
+        );
+
+        // and one dropped from a deck is missing
+        let dropped = game_state.players[1].1.deck.0.remove(0);
+        game_state.board.0[5][5] = None;
+        let err = game_state.validate_integrity().unwrap_err();
+        assert_eq!(err.missing, vec![dropped]);
+        assert!(err.extra.is_empty());
+
+        // sequestered cards count, wherever they are
+        game_state.players[1].1.deck.0.push(dropped);
+        let sequestered = game_state.out_of_play.pop().unwrap();
+        game_state.players[0].1.deck.0.push(sequestered);
+        assert_eq!(game_state.validate_integrity(), Ok(()));
+    }
+
+    #[test]
+    fn test_moves_since_last_by() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string(), "Carol".to_string()];
+        // seeded so no move takes the cards the next one is played beside
+        let options = GameOptions {
+            seed: Some(0),
+            ..create_test_options(false)
+        };
+        let mut game_state = GameState::new(player_names, options);
+        assert!(game_state.moves_since_last_by("Alice", 5).is_empty());
+
+        let center = BOARD_SIZE / 2;
+        for col in center..center + 4 {
+            assert!(game_state.apply_move(PlayerMove::new(0, (center, col), MoveSource::Hand)));
+        }
+
+        // Alice moved first and fourth, Bob second, Carol third
+        let players = |moves: &[LastMove]| {
+            moves
+                .iter()
+                .map(|last_move| last_move.player.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            players(game_state.moves_since_last_by("Alice", 5)),
+            ["Alice"]
+        );
+        assert_eq!(
+            players(game_state.moves_since_last_by("Bob", 5)),
+            ["Bob", "Carol", "Alice"]
+        );
+        assert_eq!(
+            players(game_state.moves_since_last_by("Bob", 2)),
+            ["Carol", "Alice"]
+        );
+    }
+
+    #[test]
+    fn test_first_move() {
+        let player_names = vec!["Alice".to_string(), "Bob".to_string()];
+        let first_play = |first_move, location| {
+            let options = GameOptions {
+                first_move,
+                ..create_test_options(false)
+            };
+            GameState::new(player_names.clone(), options).apply_move(PlayerMove::new(
+                0,
+                location,
+                MoveSource::Hand,
+            ))
+        };
+        let center = BOARD_SIZE / 2;
+
+        assert!(first_play(FirstMove::Center, (center, center)));
+        assert!(!first_play(FirstMove::Center, (center, 0)));
+        assert!(first_play(FirstMove::CenterLine, (center, 0)));
+        assert!(!first_play(FirstMove::CenterLine, (0, 0)));
+        assert!(first_play(FirstMove::Anywhere, (0, 0)));
     }
 }
@@ -0,0 +1,392 @@
+// Copyright 2025 Justin Hu
+//
+// This file is part of Grid Online.
+//
+// Grid Online is free software: you can redistribute it and/or modify it under
+// the terms of the GNU Affero General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// Grid Online is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU Affero General Public License
+// for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with Grid Online. If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Starting positions, shared as text so a game can be set up to play out from them
+//!
+//! A position code is the board, whose turn it is, and optionally everyone's hands, separated by
+//! spaces. The board gives its rows top to bottom, separated by `/`, with each card written as its
+//! value then its suit (`TD` for the ten of diamonds) and each empty cell as `.`. Players are
+//! counted from 1. Hands are in turn order, separated by `/`, with `-` for an empty hand.
+
+use std::{fmt::Display, str::FromStr};
+
+use grid_common::{BOARD_SIZE, Board, Card, HAND_SIZE, Suit, Value};
+use thiserror::Error;
+
+use crate::model::GameOptions;
+
+const VALUE_CODES: [char; 13] = [
+    'A', '2', '3', '4', '5', '6', '7', '8', '9', 'T', 'J', 'Q', 'K',
+];
+const SUIT_CODES: [char; 4] = ['C', 'D', 'H', 'S'];
+
+/// A game in progress, to start from instead of a fresh deal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Position {
+    pub board: Board,
+    /// Index of the player whose turn it is
+    pub turn: usize,
+    /// Each player's hand, in turn order, if the position gives them
+    pub hands: Option<Vec<Vec<Card>>>,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PositionError {
+    #[error("a position is a board and a turn, optionally followed by hands")]
+    WrongFieldCount,
+    #[error("the board has {0} rows, not {BOARD_SIZE}")]
+    WrongRowCount(usize),
+    #[error("board row {row} has {cells} cells, not {BOARD_SIZE}")]
+    WrongRowLength { row: usize, cells: usize },
+    #[error("couldn't read {0:?} as a card")]
+    BadCard(String),
+    #[error("{0} is in the position more than once")]
+    DuplicateCard(String),
+    #[error("a hand has {0} cards, more than {HAND_SIZE}")]
+    HandTooLarge(usize),
+    #[error("couldn't read {0:?} as a player number")]
+    BadTurn(String),
+    #[error("it's player {turn}'s turn, but there are only {num_players} players")]
+    TurnOutOfRange { turn: usize, num_players: usize },
+    #[error("the position gives {hands} hands, but there are {num_players} players")]
+    WrongHandCount { hands: usize, num_players: usize },
+    #[error("player {0} would start with no cards")]
+    PlayerWithoutCards(usize),
+    #[error("the board has cards that couldn't have been played, away from the rest")]
+    Unreachable,
+    #[error("can't start from a position with {0}, which changes how cards are dealt")]
+    ConflictingOption(&'static str),
+}
+
+impl Position {
+    /// Check that this position makes a playable game with these options and number of players
+    pub fn validate(&self, options: &GameOptions, num_players: usize) -> Result<(), PositionError> {
+        if let Some(option) = options.deal_option() {
+            return Err(PositionError::ConflictingOption(option));
+        }
+        if !options.could_reach(&self.board) {
+            return Err(PositionError::Unreachable);
+        }
+        if self.turn >= num_players {
+            return Err(PositionError::TurnOutOfRange {
+                turn: self.turn + 1,
+                num_players,
+            });
+        }
+        if let Some(hands) = &self.hands
+            && hands.len() != num_players
+        {
+            return Err(PositionError::WrongHandCount {
+                hands: hands.len(),
+                num_players,
+            });
+        }
+
+        let piles = self.pile_sizes(num_players);
+        for (player, pile) in piles.into_iter().enumerate() {
+            let hand = self.hands.as_ref().map_or(0, |hands| hands[player].len());
+            if hand + pile == 0 {
+                return Err(PositionError::PlayerWithoutCards(player + 1));
+            }
+        }
+        Ok(())
+    }
+
+    /// Cards not on the board or in a hand, which are left to deal
+    pub fn undealt(&self) -> Vec<Card> {
+        let placed = self.placed().collect::<Vec<_>>();
+        Suit::ALL
+            .into_iter()
+            .flat_map(|suit| Value::ALL.map(|value| Card(suit, value)))
+            .filter(|card| !placed.contains(card))
+            .collect()
+    }
+
+    /// How many undealt cards each player gets, dealt out one at a time from the first player
+    pub fn pile_sizes(&self, num_players: usize) -> Vec<usize> {
+        let undealt = self.undealt().len();
+        (0..num_players)
+            .map(|player| undealt / num_players + usize::from(player < undealt % num_players))
+            .collect()
+    }
+
+    /// Cards on the board or in a hand
+    fn placed(&self) -> impl Iterator<Item = Card> {
+        self.board
+            .0
+            .iter()
+            .flatten()
+            .flatten()
+            .chain(self.hands.iter().flatten().flatten())
+            .copied()
+    }
+}
+
+impl FromStr for Position {
+    type Err = PositionError;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        let fields = code.split_whitespace().collect::<Vec<_>>();
+        let (board, turn, hands) = match fields[..] {
+            [board, turn] => (board, turn, None),
+            [board, turn, hands] => (board, turn, Some(hands)),
+            _ => return Err(PositionError::WrongFieldCount),
+        };
+
+        let board = parse_board(board)?;
+        let turn = turn
+            .parse::<usize>()
+            .ok()
+            .and_then(|turn| turn.checked_sub(1))
+            .ok_or_else(|| PositionError::BadTurn(turn.to_string()))?;
+        let hands = hands
+            .map(|hands| {
+                hands
+                    .split('/')
+                    .map(|hand| {
+                        let hand = if hand == "-" {
+                            Vec::new()
+                        } else {
+                            parse_cards(hand)?
+                        };
+                        if hand.len() > HAND_SIZE {
+                            return Err(PositionError::HandTooLarge(hand.len()));
+                        }
+                        Ok(hand)
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?;
+
+        let position = Position { board, turn, hands };
+        let placed = position.placed().collect::<Vec<_>>();
+        for (index, card) in placed.iter().enumerate() {
+            if placed[..index].contains(card) {
+                return Err(PositionError::DuplicateCard(card_code(*card)));
+            }
+        }
+        Ok(position)
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rows = self
+            .board
+            .0
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| cell.map_or(".".to_string(), card_code))
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>();
+        write!(f, "{} {}", rows.join("/"), self.turn + 1)?;
+
+        if let Some(hands) = &self.hands {
+            let hands = hands
+                .iter()
+                .map(|hand| {
+                    if hand.is_empty() {
+                        "-".to_string()
+                    } else {
+                        hand.iter().map(|card| card_code(*card)).collect()
+                    }
+                })
+                .collect::<Vec<_>>();
+            write!(f, " {}", hands.join("/"))?;
+        }
+        Ok(())
+    }
+}
+
+fn card_code(Card(suit, value): Card) -> String {
+    let suit_index = Suit::ALL
+        .iter()
+        .position(|&other| other == suit)
+        .expect("every suit is in ALL");
+    format!(
+        "{}{}",
+        VALUE_CODES[value as usize - 1],
+        SUIT_CODES[suit_index]
+    )
+}
+
+/// Read a card from its value and suit codes
+fn parse_card(value: char, suit: char) -> Option<Card> {
+    let value = VALUE_CODES.iter().position(|&code| code == value)?;
+    let suit = SUIT_CODES.iter().position(|&code| code == suit)?;
+    Some(Card(Suit::ALL[suit], Value::ALL[value]))
+}
+
+/// Read a run of cards with nothing between them
+fn parse_cards(code: &str) -> Result<Vec<Card>, PositionError> {
+    let chars = code.chars().collect::<Vec<_>>();
+    chars
+        .chunks(2)
+        .map(|chunk| match *chunk {
+            [value, suit] => parse_card(value, suit),
+            _ => None,
+        })
+        .map(|card| card.ok_or_else(|| PositionError::BadCard(code.to_string())))
+        .collect()
+}
+
+fn parse_board(code: &str) -> Result<Board, PositionError> {
+    let rows = code.split('/').collect::<Vec<_>>();
+    if rows.len() != BOARD_SIZE {
+        return Err(PositionError::WrongRowCount(rows.len()));
+    }
+
+    let mut board = Board([[None; BOARD_SIZE]; BOARD_SIZE]);
+    for (row, row_code) in rows.into_iter().enumerate() {
+        let mut cells = Vec::new();
+        let mut chars = row_code.chars();
+        while let Some(c) = chars.next() {
+            if c == '.' {
+                cells.push(None);
+            } else {
+                let suit = chars.next().unwrap_or_default();
+                let card = parse_card(c, suit)
+                    .ok_or_else(|| PositionError::BadCard(format!("{c}{suit}")))?;
+                cells.push(Some(card));
+            }
+        }
+        if cells.len() != BOARD_SIZE {
+            return Err(PositionError::WrongRowLength {
+                row: row + 1,
+                cells: cells.len(),
+            });
+        }
+        board.0[row].copy_from_slice(&cells);
+    }
+    Ok(board)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A board code with the given rows filled in, and the rest empty
+    fn board_code(rows: &[(usize, &str)]) -> String {
+        (0..BOARD_SIZE)
+            .map(|row| {
+                rows.iter()
+                    .find(|(index, _)| *index == row)
+                    .map_or(".".repeat(BOARD_SIZE), |(_, code)| code.to_string())
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    #[test]
+    fn test_parse() {
+        let code = format!("{} 2 AS2C/-", board_code(&[(5, "....TDKH.....")]));
+        let position = code.parse::<Position>().unwrap();
+        assert_eq!(
+            position.board.0[5][4],
+            Some(Card(Suit::Diamonds, Value::Ten))
+        );
+        assert_eq!(
+            position.board.0[5][5],
+            Some(Card(Suit::Hearts, Value::King))
+        );
+        assert_eq!(position.board.played_card_count(), 2);
+        assert_eq!(position.turn, 1);
+        assert_eq!(
+            position.hands,
+            Some(vec![
+                vec![
+                    Card(Suit::Spades, Value::Ace),
+                    Card(Suit::Clubs, Value::Two)
+                ],
+                Vec::new()
+            ])
+        );
+        assert_eq!(position.undealt().len(), 52 - 4);
+
+        // and it's written back out the same way
+        assert_eq!(position.to_string(), code);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let empty = board_code(&[]);
+        assert_eq!(
+            empty.parse::<Position>(),
+            Err(PositionError::WrongFieldCount)
+        );
+        assert_eq!(
+            "./. 1".parse::<Position>(),
+            Err(PositionError::WrongRowCount(2))
+        );
+        assert_eq!(
+            format!("{} 1", board_code(&[(0, "..")])).parse::<Position>(),
+            Err(PositionError::WrongRowLength { row: 1, cells: 2 })
+        );
+        assert_eq!(
+            format!("{} 1", board_code(&[(0, "ZZ.........")])).parse::<Position>(),
+            Err(PositionError::BadCard("ZZ".to_string()))
+        );
+        assert_eq!(
+            format!("{empty} 0").parse::<Position>(),
+            Err(PositionError::BadTurn("0".to_string()))
+        );
+        assert_eq!(
+            format!("{} 1 AS", board_code(&[(5, "AS..........")])).parse::<Position>(),
+            Err(PositionError::DuplicateCard("AS".to_string()))
+        );
+        assert_eq!(
+            format!("{empty} 1 AS2S3S4S5S6S").parse::<Position>(),
+            Err(PositionError::HandTooLarge(6))
+        );
+    }
+
+    #[test]
+    fn test_validate_unreachable() {
+        let options = serde_json::from_str::<GameOptions>("{}").unwrap();
+        let validate = |rows: &[(usize, &str)], options: &GameOptions| {
+            format!("{} 1", board_code(rows))
+                .parse::<Position>()
+                .unwrap()
+                .validate(options, 2)
+        };
+
+        assert_eq!(validate(&[], &options), Ok(()));
+        assert_eq!(
+            validate(&[(4, "......2C...."), (5, "....TDKH.....")], &options),
+            Ok(())
+        );
+
+        // a card off on its own couldn't have been played next to anything
+        assert_eq!(
+            validate(&[(0, "AS.........."), (5, "....TDKH.....")], &options),
+            Err(PositionError::Unreachable)
+        );
+        // and the cards that are together have to start where the first card can go
+        let off_center = [(0, "AS2S.........")];
+        assert_eq!(
+            validate(&off_center, &options),
+            Err(PositionError::Unreachable)
+        );
+        let anywhere =
+            serde_json::from_str::<GameOptions>(r#"{"first_move": "anywhere"}"#).unwrap();
+        assert_eq!(validate(&off_center, &anywhere), Ok(()));
+    }
+}
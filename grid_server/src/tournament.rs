@@ -0,0 +1,124 @@
+// Copyright 2025 Justin Hu
+//
+// This file is part of Grid Online.
+//
+// Grid Online is free software: you can redistribute it and/or modify it under
+// the terms of the GNU Affero General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// Grid Online is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU Affero General Public License
+// for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with Grid Online. If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Tally of wins across several games in a row
+
+use std::{cmp::Reverse, collections::HashMap};
+
+use grid_common::TournamentStandings;
+
+pub struct Tournament {
+    rounds: usize,
+    played: usize,
+    wins: HashMap<String, usize>,
+    /// Games each player has gone first in
+    starts: HashMap<String, usize>,
+}
+
+impl Tournament {
+    pub fn new(rounds: usize) -> Self {
+        Self {
+            rounds,
+            played: 0,
+            wins: HashMap::new(),
+            starts: HashMap::new(),
+        }
+    }
+
+    pub fn record_start(&mut self, first_player: &str) {
+        *self.starts.entry(first_player.to_string()).or_default() += 1;
+    }
+
+    pub fn starts(&self) -> &HashMap<String, usize> {
+        &self.starts
+    }
+
+    pub fn record_win(&mut self, winner: &str) {
+        self.played += 1;
+        *self.wins.entry(winner.to_string()).or_default() += 1;
+    }
+
+    /// A game that ended with nobody winning, since everyone forfeited
+    pub fn record_no_winner(&mut self) {
+        self.played += 1;
+    }
+
+    pub fn has_started(&self) -> bool {
+        self.played > 0
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.played >= self.rounds
+    }
+
+    /// The same number of rounds again, from scratch
+    pub fn restarted(&self) -> Self {
+        Self::new(self.rounds)
+    }
+
+    /// Wins so far, most first, with ties in name order
+    pub fn standings(&self) -> TournamentStandings {
+        let mut wins = self
+            .wins
+            .iter()
+            .map(|(name, &wins)| (name.clone(), wins))
+            .collect::<Vec<_>>();
+        wins.sort_by(|(a_name, a_wins), (b_name, b_wins)| {
+            (Reverse(a_wins), a_name).cmp(&(Reverse(b_wins), b_name))
+        });
+        TournamentStandings {
+            played: self.played,
+            rounds: self.rounds,
+            wins,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_standings() {
+        let mut tournament = Tournament::new(4);
+        assert!(!tournament.has_started());
+        tournament.record_win("Bob");
+        tournament.record_win("Alice");
+        assert!(!tournament.is_over());
+        tournament.record_no_winner();
+        assert!(!tournament.is_over());
+        tournament.record_win("Bob");
+        assert!(tournament.is_over());
+        tournament.record_start("Alice");
+        tournament.record_start("Alice");
+        assert_eq!(tournament.starts().get("Alice"), Some(&2));
+
+        let standings = tournament.standings();
+        assert_eq!(
+            standings.wins,
+            vec![("Bob".to_string(), 2), ("Alice".to_string(), 1)]
+        );
+        assert_eq!(standings.champion(), Some("Bob"));
+
+        let restarted = tournament.restarted();
+        assert!(!restarted.has_started());
+        assert_eq!(restarted.standings().rounds, 4);
+        assert!(restarted.starts().is_empty());
+    }
+}